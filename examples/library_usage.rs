@@ -10,49 +10,39 @@ async fn main() -> anyhow::Result<()> {
     // Load configuration from environment
     let config = Config::from_env()?;
 
-    // Create service based on provider type
-    match &config.provider_url {
-        evm_hot_wallet::config::ProviderUrl::Http(_) => {
-            // Create HTTP service
-            let service = HotWalletService::new_http(config).await?;
+    // Create the service; the transport (HTTP or WebSocket) follows the provider URL
+    let service = HotWalletService::new(config).await?;
 
-            // Start background services
-            service.start_background_services().await?;
+    // Start background services
+    service.start_background_services().await?;
 
-            // Use the service programmatically
-            example_usage(&service).await?;
-        }
-        evm_hot_wallet::config::ProviderUrl::Ws(_) => {
-            // Create WebSocket service
-            let service = HotWalletService::new_ws(config).await?;
+    // Use the service programmatically
+    example_usage(&service).await?;
 
-            // Start background services
-            service.start_background_services().await?;
-
-            // Use the service programmatically
-            example_usage(&service).await?;
-        }
-    }
+    // Let an in-flight sweep finish before exiting
+    service.shutdown().await?;
 
     Ok(())
 }
 
-async fn example_usage<T>(service: &HotWalletService<T>) -> anyhow::Result<()>
-where
-    T: alloy::transports::Transport + Clone + Send + Sync + 'static,
-{
-    // Check health
-    let health = service.health().await?;
-    println!("Health check: {}", health);
+async fn example_usage(service: &HotWalletService) -> anyhow::Result<()> {
+    // Check readiness
+    let readiness = service.readiness().await;
+    println!("Readiness: {}", readiness.status);
 
     // Register a new account
     let request = RegisterRequest {
         id: "example_user_123".to_string(),
         webhook_url: "https://example.com/webhook".to_string(),
+        // Watch the address on every configured chain
+        chain_ids: None,
     };
 
     let response = service.register(request).await?;
     println!("Registered address: {}", response.address);
+    for chain in &response.chains {
+        println!("  watched on chain {}", chain.chain_id);
+    }
 
     if let Some(tx) = response.funding_tx {
         println!("Funding transaction: {}", tx);
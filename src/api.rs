@@ -1,139 +1,974 @@
-use alloy::transports::Transport;
+use alloy::providers::Provider;
 use axum::{
-    extract::{Json, State},
-    http::StatusCode,
+    async_trait,
+    extract::{
+        rejection::JsonRejection, Extension, FromRequest, FromRequestParts, Json, Path, Query,
+        Request, State,
+    },
+    http::{header, request::Parts, HeaderMap, StatusCode},
+    middleware::{self, Next},
     response::{IntoResponse, Response},
-    routing::{get, post},
+    routing::{get, patch, post},
     Router,
 };
+use evm_hot_wallet::auth::{ApiKey, Scope};
+use evm_hot_wallet::error::{ErrorDetail, ErrorKind, ErrorResponse, ServiceError};
+use evm_hot_wallet::health::HealthReport;
+use evm_hot_wallet::settings::{
+    ChainSettings, RuntimeSettings, SettingChange, SettingsUpdate, SettingsUpdateResponse,
+};
 use evm_hot_wallet::{
-    HotWalletService, RegisterRequest, RegisterResponse, VerifyTransferRequest,
-    VerifyTransferResponse,
+    AccountResponse, BlockNumberResponse, ChainAddress, DepositListResponse, DepositQuery,
+    DepositResponse, FaucetSpendQuery, FaucetSpendReport, HotWalletService, RegisterBatchRequest,
+    RegisterBatchResponse, RegisterRequest, RegisterResponse, SetBlockNumberRequest,
+    UpdateAccountRequest, VerifyTransferRequest, VerifyTransferResponse,
 };
-use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
 use std::sync::Arc;
 
-#[derive(Deserialize)]
-pub struct SetBlockNumberRequest {
-    pub block_number: u64,
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::{IntoParams, Modify, OpenApi};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BlockNumberQuery {
+    /// The default chain if omitted
+    #[serde(default)]
+    pub chain_id: Option<u64>,
 }
 
-#[derive(Serialize)]
-pub struct BlockNumberResponse {
-    pub block_number: u64,
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TxDepositsQuery {
+    /// Every configured chain if omitted
+    #[serde(default)]
+    pub chain_id: Option<u64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditLogQuery {
+    /// Maximum number of entries to return (default 50, max 500)
+    #[serde(default)]
+    pub limit: Option<usize>,
 }
-use tokio::net::TcpListener;
 
 #[derive(Clone)]
-struct AppState<T>
+struct AppState<P>
 where
-    T: Transport + Clone,
+    P: Provider + Clone + 'static,
 {
-    service: Arc<HotWalletService<T>>,
+    service: Arc<HotWalletService<P>>,
 }
 
-pub async fn start_server<T>(service: HotWalletService<T>, port: u16)
-where
-    T: Transport + Clone + Send + Sync + 'static,
+/// The OpenAPI 3 document of the API, served at `/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "EVM Hot Wallet API",
+        description = "Deposit addresses, deposit tracking, and transfer verification. Every route but the health checks, metrics, and this document needs an API key with the route's scope."
+    ),
+    paths(
+        health,
+        liveness,
+        readiness,
+        register,
+        register_batch,
+        verify_transfer,
+        get_block_number,
+        set_block_number,
+        get_account,
+        update_account,
+        deactivate_account,
+        list_account_deposits,
+        list_deposits,
+        get_deposits_by_tx_hash,
+        export,
+        get_settings,
+        update_settings,
+        settings_audit_log,
+        faucet_spend,
+        metrics,
+        openapi_json,
+    ),
+    components(schemas(ChainAddress, ChainSettings, ErrorDetail)),
+    modifiers(&ApiKeyAuth),
+    tags(
+        (name = "accounts", description = "Register and manage accounts"),
+        (name = "deposits", description = "Deposits to registered addresses"),
+        (name = "transfers", description = "Verify transfers on chain"),
+        (name = "monitor", description = "Progress of the block monitor"),
+        (name = "admin", description = "Exports, runtime settings, and faucet spend"),
+        (name = "health", description = "Health checks, metrics, and this document"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the `Authorization: Bearer <key>` scheme the routes' `security` refers to
+struct ApiKeyAuth;
+
+impl Modify for ApiKeyAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "api_key",
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            );
+    }
+}
+
+/// Serve the API until `shutdown` is cancelled, then stop accepting connections and
+/// return once in-flight requests have completed
+pub async fn start_server<P>(
+    service: Arc<HotWalletService<P>>,
+    port: u16,
+    shutdown: CancellationToken,
+) where
+    P: Provider + Clone + 'static,
 {
-    let state = AppState {
-        service: Arc::new(service),
-    };
+    let state = AppState { service };
+
+    // Every route but the health checks and metrics needs an API key with the route's scope
+    let scope =
+        |scope: Scope| middleware::from_fn_with_state((state.clone(), scope), authorize::<P>);
 
     let app = Router::new()
-        .route("/health", get(health::<T>))
-        .route("/register", post(register::<T>))
-        .route("/verify_transfer", post(verify_transfer::<T>))
-        .route("/block_number", get(get_block_number::<T>))
-        .route("/block_number", post(set_block_number::<T>))
+        .route("/health", get(health::<P>))
+        .route("/health/live", get(liveness::<P>))
+        .route("/health/ready", get(readiness::<P>))
+        .route(
+            "/register",
+            post(register::<P>).route_layer(scope(Scope::Register)),
+        )
+        .route(
+            "/register/batch",
+            post(register_batch::<P>).route_layer(scope(Scope::Register)),
+        )
+        .route(
+            "/verify_transfer",
+            post(verify_transfer::<P>).route_layer(scope(Scope::Read)),
+        )
+        .route(
+            "/block_number",
+            get(get_block_number::<P>).route_layer(scope(Scope::Read)),
+        )
+        // Rewinds or skips the monitor
+        .route(
+            "/block_number",
+            post(set_block_number::<P>).route_layer(scope(Scope::Admin)),
+        )
+        .route(
+            "/accounts/:id",
+            get(get_account::<P>).route_layer(scope(Scope::Read)),
+        )
+        .route(
+            "/accounts/:id",
+            patch(update_account::<P>)
+                .delete(deactivate_account::<P>)
+                .route_layer(scope(Scope::Register)),
+        )
+        .route(
+            "/accounts/:id/deposits",
+            get(list_account_deposits::<P>).route_layer(scope(Scope::Read)),
+        )
+        .route(
+            "/deposits",
+            get(list_deposits::<P>).route_layer(scope(Scope::Read)),
+        )
+        .route(
+            "/deposits/:tx_hash",
+            get(get_deposits_by_tx_hash::<P>).route_layer(scope(Scope::Read)),
+        )
+        .route(
+            "/admin/export",
+            get(export::<P>).route_layer(scope(Scope::Admin)),
+        )
+        .route(
+            "/admin/settings",
+            get(get_settings::<P>)
+                .patch(update_settings::<P>)
+                .route_layer(scope(Scope::Admin)),
+        )
+        .route(
+            "/admin/settings/audit",
+            get(settings_audit_log::<P>).route_layer(scope(Scope::Admin)),
+        )
+        .route(
+            "/admin/faucet/spend",
+            get(faucet_spend::<P>).route_layer(scope(Scope::Admin)),
+        )
+        .route("/metrics", get(metrics))
+        .route("/openapi.json", get(openapi_json))
+        .fallback(route_not_found)
         .with_state(state);
 
     let addr = format!("0.0.0.0:{}", port);
     let listener = TcpListener::bind(&addr).await.unwrap();
     tracing::info!("Listening on {}", addr);
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await
+        .unwrap();
 }
 
-async fn health<T>(State(state): State<AppState<T>>) -> impl IntoResponse
+/// Same as `/health/ready`, for load balancers that probe `/health`
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "No check failed", body = HealthReport),
+        (status = 503, description = "A check failed", body = HealthReport),
+    ),
+)]
+async fn health<P>(State(state): State<AppState<P>>) -> impl IntoResponse
 where
-    T: Transport + Clone + Send + Sync + 'static,
+    P: Provider + Clone + 'static,
 {
-    match state.service.health().await {
-        Ok(msg) => (StatusCode::OK, msg),
-        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "ERROR".to_string()),
-    }
+    health_response(state.service.readiness().await)
 }
 
-async fn register<T>(
-    State(state): State<AppState<T>>,
-    Json(payload): Json<RegisterRequest>,
-) -> Result<Json<RegisterResponse>, ApiError>
+/// Whether the background services are running
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses(
+        (status = 200, description = "Every check passed", body = HealthReport),
+        (status = 503, description = "A check failed", body = HealthReport),
+    ),
+)]
+async fn liveness<P>(State(state): State<AppState<P>>) -> impl IntoResponse
 where
-    T: Transport + Clone + Send + Sync + 'static,
+    P: Provider + Clone + 'static,
 {
-    match state.service.register(payload).await {
-        Ok(response) => Ok(Json(response)),
-        Err(e) => Err(ApiError::Internal(format!("Failed to register: {}", e))),
+    health_response(state.service.liveness())
+}
+
+/// Whether the service can take traffic: RPC, monitor lag, and sweeper activity. A low
+/// faucet balance marks the report degraded without failing it.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "No check failed", body = HealthReport),
+        (status = 503, description = "A check failed", body = HealthReport),
+    ),
+)]
+async fn readiness<P>(State(state): State<AppState<P>>) -> impl IntoResponse
+where
+    P: Provider + Clone + 'static,
+{
+    health_response(state.service.readiness().await)
+}
+
+fn health_response(report: HealthReport) -> impl IntoResponse {
+    let status = if report.is_healthy() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
+
+/// Header a client sets to make retries of a request safe
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// Header set on responses replayed for an `Idempotency-Key`
+const IDEMPOTENT_REPLAYED: &str = "idempotent-replayed";
+
+/// Register an account and get its deposit address on each chain
+///
+/// Needs the `register` scope. With an `Idempotency-Key`, retries of the request within
+/// 24 hours return the original response with `Idempotent-Replayed: true`.
+#[utoipa::path(
+    post,
+    path = "/register",
+    tag = "accounts",
+    request_body = RegisterRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header,
+            description = "Unique key of the request, e.g. a UUID; up to 255 visible ASCII characters"),
+    ),
+    responses(
+        (status = 200, description = "The account's addresses", body = RegisterResponse,
+            headers(("Idempotent-Replayed" = String, description = "`true` when replayed for an Idempotency-Key"))),
+        (status = 400, description = "Malformed request or Idempotency-Key", body = ErrorResponse),
+        (status = 409, description = "The account is deactivated", body = ErrorResponse),
+        (status = 422, description = "A chain is not configured, the webhook URL is not allowed, or the Idempotency-Key was used for another request", body = ErrorResponse),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the register scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn register<P>(
+    State(state): State<AppState<P>>,
+    Extension(api_key): Extension<ApiKey>,
+    headers: HeaderMap,
+    ApiJson(payload): ApiJson<RegisterRequest>,
+) -> Result<Response, ApiError>
+where
+    P: Provider + Clone + 'static,
+{
+    let Some(key) = headers.get(IDEMPOTENCY_KEY) else {
+        return Ok(Json(state.service.register(payload).await?).into_response());
+    };
+    let key = key.to_str().map_err(|_| {
+        ServiceError::InvalidRequest(
+            "Idempotency-Key must only contain visible ASCII characters".to_string(),
+        )
+    })?;
+    // Keys are per API key, so clients can't replay each other's responses
+    let (response, replayed) = state
+        .service
+        .register_idempotent(&api_key.id, key, payload)
+        .await?;
+    if replayed {
+        Ok(([(IDEMPOTENT_REPLAYED, "true")], Json(response)).into_response())
+    } else {
+        Ok(Json(response).into_response())
     }
 }
 
-async fn verify_transfer<T>(
-    State(state): State<AppState<T>>,
-    Json(payload): Json<VerifyTransferRequest>,
+/// Register up to 1000 accounts in one transaction
+///
+/// Either every account is registered or none is. Faucet fundings are queued and sent at
+/// `FUNDING_RATE_PER_SEC` per chain. Needs the `register` scope.
+#[utoipa::path(
+    post,
+    path = "/register/batch",
+    tag = "accounts",
+    request_body = RegisterBatchRequest,
+    responses(
+        (status = 200, description = "The accounts' addresses, in request order", body = RegisterBatchResponse),
+        (status = 400, description = "Malformed request, an invalid entry, or a repeated id", body = ErrorResponse),
+        (status = 409, description = "An account is deactivated; nothing was registered", body = ErrorResponse),
+        (status = 422, description = "A chain is not configured or a webhook URL is not allowed", body = ErrorResponse),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the register scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn register_batch<P>(
+    State(state): State<AppState<P>>,
+    ApiJson(payload): ApiJson<RegisterBatchRequest>,
+) -> Result<Json<RegisterBatchResponse>, ApiError>
+where
+    P: Provider + Clone + 'static,
+{
+    Ok(Json(state.service.register_batch(payload).await?))
+}
+
+/// Check whether a transaction transferred at least an amount to an address
+///
+/// A transaction that does not match is a `200` with `status` `error`.
+///
+/// Needs the `read` scope.
+#[utoipa::path(
+    post,
+    path = "/verify_transfer",
+    tag = "transfers",
+    request_body = VerifyTransferRequest,
+    responses(
+        (status = 200, description = "Whether the transfer matched", body = VerifyTransferResponse),
+        (status = 400, description = "Malformed hash, address, or amount", body = ErrorResponse),
+        (status = 404, description = "The transaction does not exist", body = ErrorResponse),
+        (status = 422, description = "The chain is not configured", body = ErrorResponse),
+        (status = 503, description = "The chain's node did not answer", body = ErrorResponse),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the read scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn verify_transfer<P>(
+    State(state): State<AppState<P>>,
+    ApiJson(payload): ApiJson<VerifyTransferRequest>,
 ) -> Result<Json<VerifyTransferResponse>, ApiError>
 where
-    T: Transport + Clone + Send + Sync + 'static,
+    P: Provider + Clone + 'static,
 {
-    match state.service.verify_transfer(payload).await {
-        Ok(response) => Ok(Json(response)),
-        Err(e) => Err(ApiError::Internal(format!(
-            "Failed to verify transfer: {}",
-            e
-        ))),
-    }
+    Ok(Json(state.service.verify_transfer(payload).await?))
 }
 
-async fn get_block_number<T>(
-    State(state): State<AppState<T>>,
+/// The last block the monitor processed on a chain
+///
+/// Needs the `read` scope.
+#[utoipa::path(
+    get,
+    path = "/block_number",
+    tag = "monitor",
+    params(BlockNumberQuery),
+    responses(
+        (status = 200, description = "The last processed block", body = BlockNumberResponse),
+        (status = 422, description = "The chain is not configured", body = ErrorResponse),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the read scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn get_block_number<P>(
+    State(state): State<AppState<P>>,
+    ApiQuery(query): ApiQuery<BlockNumberQuery>,
 ) -> Result<Json<BlockNumberResponse>, ApiError>
 where
-    T: Transport + Clone + Send + Sync + 'static,
+    P: Provider + Clone + 'static,
 {
-    let block_number = state
-        .service
-        .get_block_number()
-        .map_err(|e| ApiError::Internal(e.to_string()))?;
-    Ok(Json(BlockNumberResponse { block_number }))
+    let (chain_id, block_number) = state.service.get_block_number(query.chain_id).await?;
+    Ok(Json(BlockNumberResponse {
+        chain_id,
+        block_number,
+    }))
 }
 
-async fn set_block_number<T>(
-    State(state): State<AppState<T>>,
-    Json(payload): Json<SetBlockNumberRequest>,
+/// Rewind or skip the monitor to a block
+///
+/// Needs the `admin` scope.
+#[utoipa::path(
+    post,
+    path = "/block_number",
+    tag = "monitor",
+    request_body = SetBlockNumberRequest,
+    responses(
+        (status = 200, description = "The new last processed block", body = BlockNumberResponse),
+        (status = 422, description = "The chain is not configured", body = ErrorResponse),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the admin scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn set_block_number<P>(
+    State(state): State<AppState<P>>,
+    ApiJson(payload): ApiJson<SetBlockNumberRequest>,
 ) -> Result<Json<BlockNumberResponse>, ApiError>
 where
-    T: Transport + Clone + Send + Sync + 'static,
+    P: Provider + Clone + 'static,
 {
-    state
+    let chain_id = state
         .service
-        .set_block_number(payload.block_number)
-        .map_err(|e| ApiError::Internal(e.to_string()))?;
+        .set_block_number(payload.chain_id, payload.block_number)
+        .await?;
     Ok(Json(BlockNumberResponse {
+        chain_id,
         block_number: payload.block_number,
     }))
 }
 
-// Error handling for the API
+/// An account's addresses, webhook URL, status, and metadata
+///
+/// Needs the `read` scope.
+#[utoipa::path(
+    get,
+    path = "/accounts/{id}",
+    tag = "accounts",
+    params(("id" = String, Path, description = "Registration id")),
+    responses(
+        (status = 200, description = "The account", body = AccountResponse),
+        (status = 404, description = "The account does not exist", body = ErrorResponse),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the read scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn get_account<P>(
+    State(state): State<AppState<P>>,
+    Path(id): Path<String>,
+) -> Result<Json<AccountResponse>, ApiError>
+where
+    P: Provider + Clone + 'static,
+{
+    Ok(Json(state.service.get_account(&id).await?))
+}
+
+/// The deposits of an account, oldest first (by block, then id)
+///
+/// Needs the `read` scope.
+#[utoipa::path(
+    get,
+    path = "/accounts/{id}/deposits",
+    tag = "deposits",
+    params(("id" = String, Path, description = "Registration id"), DepositQuery),
+    responses(
+        (status = 200, description = "A page of deposits", body = DepositListResponse),
+        (status = 400, description = "Malformed query", body = ErrorResponse),
+        (status = 404, description = "The account does not exist", body = ErrorResponse),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the read scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn list_account_deposits<P>(
+    State(state): State<AppState<P>>,
+    Path(id): Path<String>,
+    ApiQuery(query): ApiQuery<DepositQuery>,
+) -> Result<Json<DepositListResponse>, ApiError>
+where
+    P: Provider + Clone + 'static,
+{
+    Ok(Json(state.service.list_account_deposits(&id, query).await?))
+}
+
+/// Deposits across all accounts
+///
+/// Needs the `read` scope.
+#[utoipa::path(
+    get,
+    path = "/deposits",
+    tag = "deposits",
+    params(DepositQuery),
+    responses(
+        (status = 200, description = "A page of deposits", body = DepositListResponse),
+        (status = 400, description = "Malformed query", body = ErrorResponse),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the read scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn list_deposits<P>(
+    State(state): State<AppState<P>>,
+    ApiQuery(query): ApiQuery<DepositQuery>,
+) -> Result<Json<DepositListResponse>, ApiError>
+where
+    P: Provider + Clone + 'static,
+{
+    Ok(Json(state.service.list_deposits(query).await?))
+}
+
+/// Every deposit recorded for a transaction, on one chain or all of them
+///
+/// Needs the `read` scope.
+#[utoipa::path(
+    get,
+    path = "/deposits/{tx_hash}",
+    tag = "deposits",
+    params(("tx_hash" = String, Path, description = "Transaction hash"), TxDepositsQuery),
+    responses(
+        (status = 200, description = "The deposits", body = Vec<DepositResponse>),
+        (status = 404, description = "No deposits were recorded for the transaction", body = ErrorResponse),
+        (status = 422, description = "The chain is not configured", body = ErrorResponse),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the read scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn get_deposits_by_tx_hash<P>(
+    State(state): State<AppState<P>>,
+    Path(tx_hash): Path<String>,
+    ApiQuery(query): ApiQuery<TxDepositsQuery>,
+) -> Result<Json<Vec<DepositResponse>>, ApiError>
+where
+    P: Provider + Clone + 'static,
+{
+    Ok(Json(
+        state
+            .service
+            .get_deposits_by_tx_hash(query.chain_id, &tx_hash)
+            .await?,
+    ))
+}
+
+/// Change an account's webhook URL or metadata
+///
+/// Needs the `register` scope.
+#[utoipa::path(
+    patch,
+    path = "/accounts/{id}",
+    tag = "accounts",
+    params(("id" = String, Path, description = "Registration id")),
+    request_body = UpdateAccountRequest,
+    responses(
+        (status = 200, description = "The updated account", body = AccountResponse),
+        (status = 404, description = "The account does not exist", body = ErrorResponse),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the register scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn update_account<P>(
+    State(state): State<AppState<P>>,
+    Path(id): Path<String>,
+    ApiJson(payload): ApiJson<UpdateAccountRequest>,
+) -> Result<Json<AccountResponse>, ApiError>
+where
+    P: Provider + Clone + 'static,
+{
+    Ok(Json(state.service.update_account(&id, payload).await?))
+}
+
+/// Deactivate an account; later deposits are swept without webhooks
+///
+/// Needs the `register` scope.
+#[utoipa::path(
+    delete,
+    path = "/accounts/{id}",
+    tag = "accounts",
+    params(("id" = String, Path, description = "Registration id")),
+    responses(
+        (status = 200, description = "The deactivated account", body = AccountResponse),
+        (status = 404, description = "The account does not exist", body = ErrorResponse),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the register scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn deactivate_account<P>(
+    State(state): State<AppState<P>>,
+    Path(id): Path<String>,
+) -> Result<Json<AccountResponse>, ApiError>
+where
+    P: Provider + Clone + 'static,
+{
+    Ok(Json(state.service.deactivate_account(&id).await?))
+}
+
+/// A consistent snapshot of the database as JSON lines
+///
+/// Needs the `admin` scope.
+#[utoipa::path(
+    get,
+    path = "/admin/export",
+    tag = "admin",
+    responses(
+        (status = 200, description = "The snapshot", body = String, content_type = "application/x-ndjson"),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the admin scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn export<P>(State(state): State<AppState<P>>) -> Result<Response, ApiError>
+where
+    P: Provider + Clone + 'static,
+{
+    let snapshot = state
+        .service
+        .export()
+        .await
+        .map_err(|e| ApiError::internal(e.context("Failed to export database")))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"wallet-export.jsonl\"",
+            ),
+        ],
+        snapshot,
+    )
+        .into_response())
+}
+
+/// Let the request through if it carries `Authorization: Bearer <key>` with an active API
+/// key allowed `scope`, making the key available to the handler as an extension
+async fn authorize<P>(
+    State((state, scope)): State<(AppState<P>, Scope)>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError>
+where
+    P: Provider + Clone + 'static,
+{
+    let key = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| {
+            ApiError::Unauthorized(
+                "Missing API key; send it as Authorization: Bearer <key>".to_string(),
+            )
+        })?;
+    let api_key = state
+        .service
+        .authenticate(key)
+        .await
+        .map_err(|e| ApiError::internal(e.context("Failed to check API key")))?
+        .ok_or_else(|| ApiError::Unauthorized("Invalid or revoked API key".to_string()))?;
+    if !api_key.allows(scope) {
+        return Err(ApiError::Forbidden(format!(
+            "API key {} does not have the {} scope",
+            api_key.id, scope
+        )));
+    }
+
+    request.extensions_mut().insert(api_key);
+    Ok(next.run(request).await)
+}
+
+/// Current values of the settings that can change at runtime
+///
+/// Needs the `admin` scope.
+#[utoipa::path(
+    get,
+    path = "/admin/settings",
+    tag = "admin",
+    responses(
+        (status = 200, description = "The settings", body = RuntimeSettings),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the admin scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn get_settings<P>(State(state): State<AppState<P>>) -> Json<RuntimeSettings>
+where
+    P: Provider + Clone + 'static,
+{
+    Json(state.service.settings())
+}
+
+/// Change settings at runtime, recording each change in the audit log
+///
+/// Needs the `admin` scope.
+#[utoipa::path(
+    patch,
+    path = "/admin/settings",
+    tag = "admin",
+    request_body = SettingsUpdate,
+    responses(
+        (status = 200, description = "The settings after the update", body = SettingsUpdateResponse),
+        (status = 422, description = "A value is invalid; nothing changed", body = ErrorResponse),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the admin scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn update_settings<P>(
+    State(state): State<AppState<P>>,
+    Extension(api_key): Extension<ApiKey>,
+    ApiJson(payload): ApiJson<SettingsUpdate>,
+) -> Result<Json<SettingsUpdateResponse>, ApiError>
+where
+    P: Provider + Clone + 'static,
+{
+    // The audit log names the key that made the change
+    let source = format!("api:{}", api_key.id);
+    let changes = state.service.update_settings(&payload, &source).await?;
+    Ok(Json(SettingsUpdateResponse {
+        settings: state.service.settings(),
+        changes,
+    }))
+}
+
+/// The most recent settings changes, newest first
+///
+/// Needs the `admin` scope.
+#[utoipa::path(
+    get,
+    path = "/admin/settings/audit",
+    tag = "admin",
+    params(AuditLogQuery),
+    responses(
+        (status = 200, description = "The changes", body = Vec<SettingChange>),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the admin scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn settings_audit_log<P>(
+    State(state): State<AppState<P>>,
+    ApiQuery(query): ApiQuery<AuditLogQuery>,
+) -> Result<Json<Vec<SettingChange>>, ApiError>
+where
+    P: Provider + Clone + 'static,
+{
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    state
+        .service
+        .settings_audit_log(limit)
+        .await
+        .map(Json)
+        .map_err(|e| ApiError::internal(e.context("Failed to read the audit log")))
+}
+
+/// What the faucet has spent funding addresses, from the funding ledger
+///
+/// Needs the `admin` scope.
+#[utoipa::path(
+    get,
+    path = "/admin/faucet/spend",
+    tag = "admin",
+    params(FaucetSpendQuery),
+    responses(
+        (status = 200, description = "The spend per chain", body = FaucetSpendReport),
+        (status = 400, description = "Malformed chain id or address", body = ErrorResponse),
+        (status = 422, description = "The chain is not configured", body = ErrorResponse),
+        (status = 401, description = "Missing, unknown, or revoked API key", body = ErrorResponse),
+        (status = 403, description = "The API key lacks the admin scope", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+async fn faucet_spend<P>(
+    State(state): State<AppState<P>>,
+    ApiQuery(query): ApiQuery<FaucetSpendQuery>,
+) -> Result<Json<FaucetSpendReport>, ApiError>
+where
+    P: Provider + Clone + 'static,
+{
+    Ok(Json(state.service.faucet_spend(&query).await?))
+}
+
+/// Prometheus metrics
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", body = String, content_type = "text/plain"),
+    ),
+)]
+async fn metrics() -> Result<Response, ApiError> {
+    let body = evm_hot_wallet::metrics::metrics()
+        .render()
+        .map_err(|e| ApiError::internal(e.context("Failed to render metrics")))?;
+
+    Ok(([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response())
+}
+
+/// This document
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    tag = "health",
+    responses(
+        (status = 200, description = "The OpenAPI 3 document of the API", content_type = "application/json"),
+    ),
+)]
+async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+async fn route_not_found(request: Request) -> ApiError {
+    ApiError::Rejected {
+        status: StatusCode::NOT_FOUND,
+        code: "route_not_found",
+        message: format!("No route for {} {}", request.method(), request.uri().path()),
+    }
+}
+
+/// [`Json`] that reports a body it can't parse as an [`ApiError`]
+struct ApiJson<T>(T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, ApiError> {
+        match Json::<T>::from_request(request, state).await {
+            Ok(Json(value)) => Ok(Self(value)),
+            Err(rejection) => {
+                let code = match &rejection {
+                    JsonRejection::JsonDataError(_) => "invalid_body",
+                    JsonRejection::MissingJsonContentType(_) => "unsupported_media_type",
+                    _ => "invalid_json",
+                };
+                Err(ApiError::Rejected {
+                    status: rejection.status(),
+                    code,
+                    message: rejection.body_text(),
+                })
+            }
+        }
+    }
+}
+
+/// [`Query`] that reports a query string it can't parse as an [`ApiError`]
+struct ApiQuery<T>(T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ApiQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, ApiError> {
+        match Query::<T>::from_request_parts(parts, state).await {
+            Ok(Query(value)) => Ok(Self(value)),
+            Err(rejection) => Err(ApiError::Rejected {
+                status: rejection.status(),
+                code: "invalid_query",
+                message: rejection.body_text(),
+            }),
+        }
+    }
+}
+
 #[derive(Debug)]
 enum ApiError {
-    Internal(String),
+    /// A request the service rejected or failed to handle
+    Service(ServiceError),
+    Unauthorized(String),
+    Forbidden(String),
+    /// A request that never reached a handler, e.g. an unparsable body
+    Rejected {
+        status: StatusCode,
+        code: &'static str,
+        message: String,
+    },
+}
+
+impl ApiError {
+    fn internal(error: anyhow::Error) -> Self {
+        ApiError::Service(ServiceError::Internal(error))
+    }
+}
+
+impl From<ServiceError> for ApiError {
+    fn from(error: ServiceError) -> Self {
+        ApiError::Service(error)
+    }
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            ApiError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+        let (status, code, message) = match self {
+            ApiError::Service(error) => {
+                let status = match error.kind() {
+                    ErrorKind::InvalidRequest => StatusCode::BAD_REQUEST,
+                    ErrorKind::NotFound => StatusCode::NOT_FOUND,
+                    ErrorKind::Conflict => StatusCode::CONFLICT,
+                    ErrorKind::Unprocessable => StatusCode::UNPROCESSABLE_ENTITY,
+                    ErrorKind::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+                    ErrorKind::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                let message = match &error {
+                    // The details stay in the log rather than reaching clients
+                    ServiceError::Internal(e) => {
+                        tracing::error!("Request failed: {:#}", e);
+                        "Internal error".to_string()
+                    }
+                    error => error.to_string(),
+                };
+                (status, error.code(), message)
+            }
+            ApiError::Unauthorized(message) => (StatusCode::UNAUTHORIZED, "unauthorized", message),
+            ApiError::Forbidden(message) => (StatusCode::FORBIDDEN, "forbidden", message),
+            ApiError::Rejected {
+                status,
+                code,
+                message,
+            } => (status, code, message),
         };
 
-        (status, message).into_response()
+        let body = Json(ErrorResponse {
+            error: ErrorDetail {
+                code: code.to_string(),
+                message,
+            },
+        });
+        if status == StatusCode::UNAUTHORIZED {
+            return (status, [(header::WWW_AUTHENTICATE, "Bearer")], body).into_response();
+        }
+        (status, body).into_response()
     }
 }
@@ -0,0 +1,139 @@
+use crate::deposit::now_secs;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+/// Every key starts with this, so leaked keys are easy to search for
+const KEY_PREFIX: &str = "ehw";
+
+/// What an API key may do
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Register accounts and update or deactivate them
+    Register,
+    /// Look up accounts, deposits, and the last processed block, and verify transfers
+    Read,
+    /// Everything, including rewinding the monitor, exports, and runtime settings
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Register => "register",
+            Scope::Read => "read",
+            Scope::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "register" => Ok(Scope::Register),
+            "read" => Ok(Scope::Read),
+            "admin" => Ok(Scope::Admin),
+            other => Err(anyhow::anyhow!(
+                "Unknown scope {:?} (expected register, read, or admin)",
+                other
+            )),
+        }
+    }
+}
+
+/// Parse a comma-separated list of scopes such as `register,read`
+pub fn parse_scopes(scopes: &str) -> Result<Vec<Scope>> {
+    let mut parsed = Vec::new();
+    for scope in scopes.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let scope = scope.parse()?;
+        if !parsed.contains(&scope) {
+            parsed.push(scope);
+        }
+    }
+    if parsed.is_empty() {
+        return Err(anyhow::anyhow!("At least one scope is required"));
+    }
+    Ok(parsed)
+}
+
+/// A stored API key. Only a hash of the key is kept; the key itself is shown once, when
+/// it is created.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ApiKey {
+    /// Public identifier, also embedded in the key
+    pub id: String,
+    /// Who or what the key was issued to
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    /// Hex-encoded SHA-256 of the full key
+    pub key_hash: String,
+    pub created_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<u64>,
+}
+
+impl ApiKey {
+    /// A new key with random id and secret. Returns the record to store and the key to
+    /// hand out, of the form `ehw_<id>_<secret>`.
+    pub fn generate(name: &str, scopes: Vec<Scope>) -> (Self, String) {
+        let id = hex::encode(rand::random::<[u8; 6]>());
+        let key = format!(
+            "{}_{}_{}",
+            KEY_PREFIX,
+            id,
+            hex::encode(rand::random::<[u8; 32]>())
+        );
+        let record = Self {
+            id,
+            name: name.to_string(),
+            scopes,
+            key_hash: hash_key(&key),
+            created_at: now_secs(),
+            revoked_at: None,
+        };
+        (record, key)
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    /// Admin keys are allowed everything
+    pub fn allows(&self, scope: Scope) -> bool {
+        !self.is_revoked() && (self.scopes.contains(&scope) || self.scopes.contains(&Scope::Admin))
+    }
+
+    /// Whether `key` is this key, compared without returning early so response times
+    /// don't reveal how much of the hash matched
+    pub fn matches(&self, key: &str) -> bool {
+        let given = hash_key(key);
+        given.len() == self.key_hash.len()
+            && given
+                .bytes()
+                .zip(self.key_hash.bytes())
+                .fold(0, |acc, (a, b)| acc | (a ^ b))
+                == 0
+    }
+}
+
+/// The id embedded in a key, used to look up its record
+pub fn key_id(key: &str) -> Option<&str> {
+    let rest = key.strip_prefix(KEY_PREFIX)?.strip_prefix('_')?;
+    let (id, secret) = rest.split_once('_')?;
+    (!id.is_empty() && !secret.is_empty()).then_some(id)
+}
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
@@ -0,0 +1,280 @@
+use crate::error::ErrorResponse;
+use crate::settings::{RuntimeSettings, SettingChange, SettingsUpdate, SettingsUpdateResponse};
+use crate::{
+    AccountResponse, BlockNumberResponse, DepositListResponse, DepositQuery, DepositResponse,
+    FaucetSpendQuery, FaucetSpendReport, RegisterBatchRequest, RegisterBatchResponse,
+    RegisterRequest, RegisterResponse, SetBlockNumberRequest, UpdateAccountRequest,
+    VerifyTransferRequest, VerifyTransferResponse,
+};
+use reqwest::{Method, RequestBuilder, Response, Url};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// Why a [`Client`] call failed
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The API answered with an error response
+    #[error("{status} {code}: {message}")]
+    Api {
+        status: u16,
+        /// The stable code of the error, e.g. `account_not_found`; `unexpected_response`
+        /// if the body was not an API error, e.g. one from a proxy
+        code: String,
+        message: String,
+    },
+    #[error("invalid base URL {0}")]
+    InvalidUrl(String),
+    /// The API could not be reached, or its response could not be read
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+}
+
+impl ClientError {
+    /// The code of an error response, if the API sent one
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            ClientError::Api { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+}
+
+pub type ClientResult<T> = std::result::Result<T, ClientError>;
+
+/// Typed client of the HTTP API, for callers that talk to a running service instead of
+/// embedding [`HotWalletService`](crate::HotWalletService)
+#[derive(Clone, Debug)]
+pub struct Client {
+    base_url: Url,
+    api_key: Option<String>,
+    http: reqwest::Client,
+}
+
+impl Client {
+    /// A client of the API at `base_url`, e.g. `http://localhost:3000`
+    pub fn new(base_url: &str) -> ClientResult<Self> {
+        let base_url =
+            Url::parse(base_url).map_err(|_| ClientError::InvalidUrl(base_url.into()))?;
+        if base_url.cannot_be_a_base() {
+            return Err(ClientError::InvalidUrl(base_url.into()));
+        }
+        Ok(Self {
+            base_url,
+            api_key: None,
+            http: reqwest::Client::new(),
+        })
+    }
+
+    /// Send `key` as `Authorization: Bearer <key>`; every route but the health checks
+    /// needs one
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /// Send requests with `http`, e.g. to set timeouts or a proxy
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    pub async fn register(&self, request: &RegisterRequest) -> ClientResult<RegisterResponse> {
+        self.json(self.request(Method::POST, &["register"]).json(request))
+            .await
+    }
+
+    /// Register with an `Idempotency-Key`, so the call can be retried safely: a retry with
+    /// the same key and request gets the original response instead of registering again
+    pub async fn register_with_idempotency_key(
+        &self,
+        request: &RegisterRequest,
+        idempotency_key: &str,
+    ) -> ClientResult<RegisterResponse> {
+        self.json(
+            self.request(Method::POST, &["register"])
+                .header("Idempotency-Key", idempotency_key)
+                .json(request),
+        )
+        .await
+    }
+
+    /// Register up to [`MAX_BATCH_SIZE`](crate::MAX_BATCH_SIZE) accounts at once; either all
+    /// are registered or none
+    pub async fn register_batch(
+        &self,
+        request: &RegisterBatchRequest,
+    ) -> ClientResult<RegisterBatchResponse> {
+        self.json(
+            self.request(Method::POST, &["register", "batch"])
+                .json(request),
+        )
+        .await
+    }
+
+    /// A transaction that does not match is `Ok` with [`VerifyTransferResponse::Error`]
+    pub async fn verify_transfer(
+        &self,
+        request: &VerifyTransferRequest,
+    ) -> ClientResult<VerifyTransferResponse> {
+        self.json(
+            self.request(Method::POST, &["verify_transfer"])
+                .json(request),
+        )
+        .await
+    }
+
+    pub async fn get_account(&self, id: &str) -> ClientResult<AccountResponse> {
+        self.json(self.request(Method::GET, &["accounts", id]))
+            .await
+    }
+
+    pub async fn update_account(
+        &self,
+        id: &str,
+        request: &UpdateAccountRequest,
+    ) -> ClientResult<AccountResponse> {
+        self.json(self.request(Method::PATCH, &["accounts", id]).json(request))
+            .await
+    }
+
+    pub async fn deactivate_account(&self, id: &str) -> ClientResult<AccountResponse> {
+        self.json(self.request(Method::DELETE, &["accounts", id]))
+            .await
+    }
+
+    pub async fn list_deposits(&self, query: &DepositQuery) -> ClientResult<DepositListResponse> {
+        self.json(self.request(Method::GET, &["deposits"]).query(query))
+            .await
+    }
+
+    pub async fn list_account_deposits(
+        &self,
+        id: &str,
+        query: &DepositQuery,
+    ) -> ClientResult<DepositListResponse> {
+        self.json(
+            self.request(Method::GET, &["accounts", id, "deposits"])
+                .query(query),
+        )
+        .await
+    }
+
+    /// The deposits of a transaction on a chain (every chain if None)
+    pub async fn get_deposits_by_tx_hash(
+        &self,
+        chain_id: Option<u64>,
+        tx_hash: &str,
+    ) -> ClientResult<Vec<DepositResponse>> {
+        let mut request = self.request(Method::GET, &["deposits", tx_hash]);
+        if let Some(chain_id) = chain_id {
+            request = request.query(&[("chain_id", chain_id)]);
+        }
+        self.json(request).await
+    }
+
+    /// The last processed block of a chain (the default chain if None)
+    pub async fn get_block_number(
+        &self,
+        chain_id: Option<u64>,
+    ) -> ClientResult<BlockNumberResponse> {
+        let mut request = self.request(Method::GET, &["block_number"]);
+        if let Some(chain_id) = chain_id {
+            request = request.query(&[("chain_id", chain_id)]);
+        }
+        self.json(request).await
+    }
+
+    pub async fn set_block_number(
+        &self,
+        request: &SetBlockNumberRequest,
+    ) -> ClientResult<BlockNumberResponse> {
+        self.json(self.request(Method::POST, &["block_number"]).json(request))
+            .await
+    }
+
+    pub async fn settings(&self) -> ClientResult<RuntimeSettings> {
+        self.json(self.request(Method::GET, &["admin", "settings"]))
+            .await
+    }
+
+    pub async fn update_settings(
+        &self,
+        update: &SettingsUpdate,
+    ) -> ClientResult<SettingsUpdateResponse> {
+        self.json(
+            self.request(Method::PATCH, &["admin", "settings"])
+                .json(update),
+        )
+        .await
+    }
+
+    /// The most recent settings changes, newest first (50 if `limit` is None)
+    pub async fn settings_audit_log(
+        &self,
+        limit: Option<usize>,
+    ) -> ClientResult<Vec<SettingChange>> {
+        let mut request = self.request(Method::GET, &["admin", "settings", "audit"]);
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)]);
+        }
+        self.json(request).await
+    }
+
+    pub async fn faucet_spend(&self, query: &FaucetSpendQuery) -> ClientResult<FaucetSpendReport> {
+        self.json(
+            self.request(Method::GET, &["admin", "faucet", "spend"])
+                .query(query),
+        )
+        .await
+    }
+
+    /// A snapshot of the database as JSON lines, readable by `--import`
+    pub async fn export(&self) -> ClientResult<Vec<u8>> {
+        let response = checked(
+            self.request(Method::GET, &["admin", "export"])
+                .send()
+                .await?,
+        )
+        .await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// A request to the route made of `segments`, each percent-encoded
+    fn request(&self, method: Method, segments: &[&str]) -> RequestBuilder {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .expect("checked in Client::new")
+            .pop_if_empty()
+            .extend(segments);
+        let request = self.http.request(method, url);
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    async fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> ClientResult<T> {
+        Ok(checked(request.send().await?).await?.json().await?)
+    }
+}
+
+/// `response` if it succeeded, or the error it carries
+async fn checked(response: Response) -> ClientResult<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.bytes().await?;
+    let (code, message) = match serde_json::from_slice::<ErrorResponse>(&body) {
+        Ok(ErrorResponse { error }) => (error.code, error.message),
+        Err(_) => (
+            "unexpected_response".to_string(),
+            String::from_utf8_lossy(&body).into_owned(),
+        ),
+    };
+    Err(ClientError::Api {
+        status: status.as_u16(),
+        code,
+        message,
+    })
+}
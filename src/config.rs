@@ -1,83 +1,1010 @@
+use alloy::primitives::{Address, U256};
 use anyhow::Result;
 use dotenvy::dotenv;
+use serde::Deserialize;
 use std::env;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
 
 #[derive(Clone, Debug)]
 pub enum ProviderUrl {
     Http(String),
     Ws(String),
+    /// Unix domain socket of a co-located node, e.g. `/var/run/geth.ipc`
+    Ipc(String),
+}
+
+impl ProviderUrl {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ProviderUrl::Http(url) | ProviderUrl::Ws(url) | ProviderUrl::Ipc(url) => url,
+        }
+    }
+
+    /// Whether new blocks can be streamed instead of polled
+    pub fn is_pubsub(&self) -> bool {
+        matches!(self, ProviderUrl::Ws(_) | ProviderUrl::Ipc(_))
+    }
+}
+
+/// When new addresses get their existential deposit from the faucet
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FundingPolicy {
+    /// On registration, whether or not the address ever receives a deposit
+    #[default]
+    Eager,
+    /// When the first deposit to the address on a chain is detected
+    OnFirstDeposit,
+    /// Only when a sweep finds too little gas on the address
+    OnSweep,
+}
+
+impl FundingPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FundingPolicy::Eager => "eager",
+            FundingPolicy::OnFirstDeposit => "on_first_deposit",
+            FundingPolicy::OnSweep => "on_sweep",
+        }
+    }
+}
+
+impl FromStr for FundingPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "eager" => Ok(FundingPolicy::Eager),
+            "on_first_deposit" => Ok(FundingPolicy::OnFirstDeposit),
+            "on_sweep" => Ok(FundingPolicy::OnSweep),
+            _ => Err(format!(
+                "{:?} is not one of eager, on_first_deposit, on_sweep",
+                value
+            )),
+        }
+    }
+}
+
+/// Where the Sweeper sends the native balance left on swept addresses
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DustReclaim {
+    /// Leave it on the addresses
+    #[default]
+    Off,
+    /// Back to the faucet that provided most of it
+    Faucet,
+    /// To the chain's treasury, like the deposits
+    Treasury,
+}
+
+impl DustReclaim {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DustReclaim::Off => "off",
+            DustReclaim::Faucet => "faucet",
+            DustReclaim::Treasury => "treasury",
+        }
+    }
+}
+
+impl FromStr for DustReclaim {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "off" => Ok(DustReclaim::Off),
+            "faucet" => Ok(DustReclaim::Faucet),
+            "treasury" => Ok(DustReclaim::Treasury),
+            _ => Err(format!("{:?} is not one of off, faucet, treasury", value)),
+        }
+    }
+}
+
+/// Settings of one chain the service watches. Accounts get the same address on every
+/// chain, so the mnemonics, the faucet address, and webhooks are shared across chains.
+#[derive(Clone, Debug)]
+pub struct ChainConfig {
+    /// Label used in logs, metrics, and service names, e.g. "polygon"
+    pub name: String,
+    /// EIP-155 chain id; 0 means "whatever the node reports", resolved at startup
+    pub chain_id: u64,
+    pub provider_url: ProviderUrl,
+    pub treasury_address: String,
+    pub existential_deposit: String,
+    pub block_offset_from_head: u64,
+    /// Readiness reports degraded, and the watchdog alerts, when the faucet balance (in wei)
+    /// drops below this
+    pub faucet_min_balance: String,
+    /// Most wei the faucet sends one address over its lifetime; unlimited if unset
+    pub funding_cap: Option<String>,
+    /// When set, the faucet watchdog refills the faucet from the refill key
+    pub faucet_refill: Option<FaucetRefill>,
+    /// Wei a dust reclaim must move, after its gas, to be worth sending
+    pub dust_reclaim_min: String,
+    /// When set, the faucet tops sweeps up with the gas they lack instead of sending the
+    /// existential deposit
+    pub gas_top_up: Option<GasTopUp>,
+}
+
+/// How the faucet watchdog refills a faucet whose balance fell below `faucet_min_balance`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FaucetRefill {
+    /// Balance in wei a refill brings the faucet up to
+    pub target: String,
+    /// Most wei refilled in any 24 hours
+    pub daily_cap: String,
+}
+
+/// How much the faucet sends to an address that lacks gas for its sweep: the shortfall
+/// against the Sweeper's gas estimate plus `margin_percent`, at least `min` wei; top-ups above
+/// `max` wei are refused
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GasTopUp {
+    pub margin_percent: u32,
+    pub min: String,
+    pub max: String,
 }
 
 #[derive(Clone, Debug)]
 pub struct Config {
     pub database_url: String,
-    pub provider_url: ProviderUrl,
     pub mnemonic: String,
-    pub treasury_address: String,
     pub port: u16,
     pub poll_interval: u64,
     pub faucet_mnemonic: String,
-    pub existential_deposit: String,
     pub faucet_address: String,
-    pub block_offset_from_head: u64,
     pub get_logs_max_retries: u32,
     pub get_logs_delay_ms: u64,
     /// Optional JWT token for webhook authorization
     pub webhook_jwt_token: Option<String>,
+    /// Readiness fails when the monitor falls this many blocks behind head minus the offset
+    pub health_max_block_lag: u64,
+    /// Readiness fails when the sweeper has not completed a cycle for this many seconds
+    pub health_max_sweeper_idle_secs: u64,
+    /// How long shutdown waits for in-flight blocks and sweeps to finish
+    pub shutdown_timeout_secs: u64,
+    /// Hosts webhook URLs may point at (`*.example.com` matches subdomains). When empty,
+    /// any host is allowed except internal addresses such as localhost or 10.0.0.0/8.
+    pub webhook_allowed_hosts: Vec<String>,
+    /// Faucet fundings sent per second on each chain; registrations queue beyond that.
+    /// Each funding waits for its receipt, so this never exceeds about one per block.
+    pub funding_rate_per_sec: u32,
+    /// When new addresses are funded; the sweeper funds addresses that lack gas under
+    /// every policy
+    pub funding_policy: FundingPolicy,
+    /// Where operator alerts such as `faucet_low_balance` are posted
+    pub alert_webhook_url: Option<String>,
+    /// How often the faucet watchdog checks each faucet's balance
+    pub faucet_watchdog_interval_secs: u64,
+    /// Mnemonic of the hot treasury key (index 0) that refills faucets
+    pub refill_mnemonic: Option<String>,
+    /// Where the Sweeper reclaims the native balance left on swept addresses, if anywhere
+    pub dust_reclaim: DustReclaim,
+    /// How often the Sweeper looks for dust to reclaim
+    pub dust_reclaim_interval_secs: u64,
+    /// Chains to watch; the first one is the default for requests that don't name a chain
+    pub chains: Vec<ChainConfig>,
 }
 
 impl Config {
+    /// Load the configuration from the TOML file named by `CONFIG_FILE` (if set), with
+    /// environment variables taking precedence, and validate it
     pub fn from_env() -> Result<Self> {
         dotenv().ok();
+        let config_file = env::var("CONFIG_FILE").ok();
+        Self::load(config_file.as_deref().map(Path::new))
+    }
+
+    /// Load the configuration from the TOML file at `config_file` (if any), with environment
+    /// variables taking precedence, and validate it. Every problem found is reported at once
+    /// in a [`ConfigErrors`].
+    pub fn load(config_file: Option<&Path>) -> Result<Self> {
+        dotenv().ok();
+        let mut problems = Vec::new();
+        let mut raw = match config_file {
+            Some(path) => RawConfig::read(path)?,
+            None => RawConfig::default(),
+        };
+        raw.apply_env(&mut problems);
+        let config = raw.resolve(&mut problems);
+        if let Err(ConfigErrors(invalid)) = config.validate() {
+            problems.extend(invalid);
+        }
+
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigErrors(problems).into())
+        }
+    }
+
+    /// The database URL on its own, for commands that only touch the database
+    pub fn database_url(config_file: Option<&Path>) -> Result<String> {
+        dotenv().ok();
+        let from_file = match config_file {
+            Some(path) => RawConfig::read(path)?.database_url,
+            None => None,
+        };
+        Ok(env::var("DATABASE_URL")
+            .ok()
+            .or(from_file)
+            .unwrap_or_else(default_database_url))
+    }
+
+    /// Environment variables that set a runtime setting of this configuration. The
+    /// environment wins over the file and is fixed for the life of the process, so a
+    /// reload (SIGHUP) can't change these settings.
+    pub fn runtime_env_overrides(&self) -> Vec<String> {
+        let mut keys = vec!["POLL_INTERVAL".to_string()];
+        for chain in &self.chains {
+            // The unprefixed variables only win over the file for the unnamed default chain
+            let prefix = if self.chains.len() == 1 && chain.name == DEFAULT_CHAIN {
+                String::new()
+            } else {
+                env_prefix(&chain.name)
+            };
+            for name in [
+                "TREASURY_ADDRESS",
+                "EXISTENTIAL_DEPOSIT",
+                "BLOCK_OFFSET_FROM_HEAD",
+            ] {
+                keys.push(format!("{}{}", prefix, name));
+            }
+        }
+        keys.retain(|key| env::var_os(key).is_some());
+        keys
+    }
+
+    /// Check address formats, mnemonics, that `faucet_address` belongs to the faucet
+    /// mnemonic, and numeric ranges, collecting every problem
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut problems = Vec::new();
+
+        if let Err(e) = crate::storage::StorageUrl::parse(&self.database_url) {
+            problems.push(format!("database_url: {}", e));
+        }
+
+        check_mnemonic("mnemonic", &self.mnemonic, &mut problems);
+        let faucet_mnemonic_ok =
+            check_mnemonic("faucet_mnemonic", &self.faucet_mnemonic, &mut problems);
+        if let Some(faucet_address) =
+            check_address("faucet_address", &self.faucet_address, &mut problems)
+        {
+            if faucet_mnemonic_ok {
+                match crate::wallet::Wallet::new(self.faucet_mnemonic.clone()).derive_address(0) {
+                    Ok(derived) if derived == faucet_address => {}
+                    Ok(derived) => problems.push(format!(
+                        "faucet_address: {} is not index 0 of faucet_mnemonic, which is {}",
+                        self.faucet_address, derived
+                    )),
+                    Err(e) => problems.push(format!("faucet_mnemonic: {}", e)),
+                }
+            }
+        }
+        if self.port == 0 {
+            problems.push("port: must be between 1 and 65535".to_string());
+        }
+        check_range("poll_interval", self.poll_interval, 1, 3600, &mut problems);
+        check_range(
+            "get_logs_max_retries",
+            self.get_logs_max_retries.into(),
+            1,
+            1000,
+            &mut problems,
+        );
+        check_range(
+            "get_logs_delay_ms",
+            self.get_logs_delay_ms,
+            0,
+            60_000,
+            &mut problems,
+        );
+        check_range(
+            "health_max_block_lag",
+            self.health_max_block_lag,
+            1,
+            u64::MAX,
+            &mut problems,
+        );
+        check_range(
+            "health_max_sweeper_idle_secs",
+            self.health_max_sweeper_idle_secs,
+            self.poll_interval.max(1),
+            u64::MAX,
+            &mut problems,
+        );
+        check_range(
+            "shutdown_timeout_secs",
+            self.shutdown_timeout_secs,
+            1,
+            3600,
+            &mut problems,
+        );
+        if self.webhook_jwt_token.as_deref() == Some("") {
+            problems.push("webhook_jwt_token: must not be empty when set".to_string());
+        }
+        check_range(
+            "funding_rate_per_sec",
+            self.funding_rate_per_sec.into(),
+            1,
+            1000,
+            &mut problems,
+        );
+        for pattern in &self.webhook_allowed_hosts {
+            if let Some(problem) = crate::validation::check_host_pattern(pattern) {
+                problems.push(format!("webhook_allowed_hosts: {}", problem));
+            }
+        }
+        if let Some(url) = &self.alert_webhook_url {
+            match reqwest::Url::parse(url) {
+                Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+                _ => problems.push(format!(
+                    "alert_webhook_url: {:?} is not an http(s) URL",
+                    url
+                )),
+            }
+        }
+        check_range(
+            "dust_reclaim_interval_secs",
+            self.dust_reclaim_interval_secs,
+            60,
+            604_800,
+            &mut problems,
+        );
+        check_range(
+            "faucet_watchdog_interval_secs",
+            self.faucet_watchdog_interval_secs,
+            5,
+            86_400,
+            &mut problems,
+        );
+        if let Some(refill_mnemonic) = &self.refill_mnemonic {
+            if check_mnemonic("refill_mnemonic", refill_mnemonic, &mut problems)
+                && refill_mnemonic.trim() == self.faucet_mnemonic.trim()
+            {
+                problems.push(
+                    "refill_mnemonic: must not be the faucet mnemonic; a faucet can't refill itself"
+                        .to_string(),
+                );
+            }
+        } else if self.chains.iter().any(|c| c.faucet_refill.is_some()) {
+            problems.push(
+                "refill_mnemonic: must be set when a chain has a faucet_refill_target".to_string(),
+            );
+        }
+
+        if self.chains.is_empty() {
+            problems.push("chains: at least one chain must be configured".to_string());
+        }
+        for (i, chain) in self.chains.iter().enumerate() {
+            let field = |name: &str| {
+                if self.chains.len() == 1 && chain.name == DEFAULT_CHAIN {
+                    name.to_string()
+                } else {
+                    format!("chains.{}.{}", chain.name, name)
+                }
+            };
+
+            if chain.name.trim().is_empty() {
+                problems.push("chains: every chain needs a name".to_string());
+            } else if self.chains[..i].iter().any(|c| c.name == chain.name) {
+                problems.push(format!(
+                    "chains: {} is configured more than once",
+                    chain.name
+                ));
+            }
+            if chain.chain_id != 0
+                && self.chains[..i]
+                    .iter()
+                    .any(|c| c.chain_id == chain.chain_id)
+            {
+                problems.push(format!(
+                    "{}: chain id {} is configured more than once",
+                    field("chain_id"),
+                    chain.chain_id
+                ));
+            }
+
+            check_provider_url(&field, &chain.provider_url, &mut problems);
+            check_address(
+                &field("treasury_address"),
+                &chain.treasury_address,
+                &mut problems,
+            );
+            let existential_deposit = check_wei(
+                &field("existential_deposit"),
+                &chain.existential_deposit,
+                &mut problems,
+            );
+            if existential_deposit == Some(U256::ZERO) {
+                problems.push(format!(
+                    "{}: must be greater than 0",
+                    field("existential_deposit")
+                ));
+            }
+            // Defaults to the existential deposit, which was just checked
+            if chain.faucet_min_balance != chain.existential_deposit {
+                check_wei(
+                    &field("faucet_min_balance"),
+                    &chain.faucet_min_balance,
+                    &mut problems,
+                );
+            }
+            check_range(
+                &field("block_offset_from_head"),
+                chain.block_offset_from_head,
+                0,
+                10_000,
+                &mut problems,
+            );
+            if let Some(cap) = &chain.funding_cap {
+                check_wei(&field("funding_cap"), cap, &mut problems);
+            }
+            check_wei(
+                &field("dust_reclaim_min"),
+                &chain.dust_reclaim_min,
+                &mut problems,
+            );
+            if let Some(refill) = &chain.faucet_refill {
+                let target = check_wei(
+                    &field("faucet_refill_target"),
+                    &refill.target,
+                    &mut problems,
+                );
+                let min_balance = U256::from_str_radix(&chain.faucet_min_balance, 10).ok();
+                if let (Some(target), Some(min_balance)) = (target, min_balance) {
+                    if target <= min_balance {
+                        problems.push(format!(
+                            "{}: {} must be more than faucet_min_balance {}",
+                            field("faucet_refill_target"),
+                            target,
+                            min_balance
+                        ));
+                    }
+                }
+                let daily_cap = check_wei(
+                    &field("faucet_refill_daily_cap"),
+                    &refill.daily_cap,
+                    &mut problems,
+                );
+                if daily_cap == Some(U256::ZERO) {
+                    problems.push(format!(
+                        "{}: must be greater than 0",
+                        field("faucet_refill_daily_cap")
+                    ));
+                }
+            }
+            if let Some(top_up) = &chain.gas_top_up {
+                check_range(
+                    &field("gas_top_up_margin_percent"),
+                    top_up.margin_percent.into(),
+                    1,
+                    1000,
+                    &mut problems,
+                );
+                let min = check_wei(&field("gas_top_up_min"), &top_up.min, &mut problems);
+                let max = check_wei(&field("gas_top_up_max"), &top_up.max, &mut problems);
+                if max == Some(U256::ZERO) {
+                    problems.push(format!(
+                        "{}: must be greater than 0",
+                        field("gas_top_up_max")
+                    ));
+                }
+                if let (Some(min), Some(max)) = (min, max) {
+                    if min > max {
+                        problems.push(format!(
+                            "{}: {} is more than gas_top_up_max {}",
+                            field("gas_top_up_min"),
+                            min,
+                            max
+                        ));
+                    }
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigErrors(problems))
+        }
+    }
+
+    /// The chain requests fall back to when they don't name one
+    pub fn default_chain(&self) -> &ChainConfig {
+        &self.chains[0]
+    }
+
+    pub fn chain(&self, chain_id: u64) -> Option<&ChainConfig> {
+        self.chains.iter().find(|c| c.chain_id == chain_id)
+    }
+}
+
+/// Every problem found in a configuration, reported together so one run of
+/// `config check` shows them all
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigErrors(pub Vec<String>);
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid configuration:")?;
+        for problem in &self.0 {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
 
-        let database_url =
-            env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:wallet.db".to_string());
+impl std::error::Error for ConfigErrors {}
+
+/// Name of the chain of a configuration that does not list chains
+pub(crate) const DEFAULT_CHAIN: &str = "default";
+
+fn default_database_url() -> String {
+    // Earlier releases defaulted to "sqlite:wallet.db", which redb created under that
+    // literal file name; keep using it so upgrades don't start from an empty database
+    if Path::new("sqlite:wallet.db").exists() {
+        "sqlite:wallet.db".to_string()
+    } else {
+        "redb:wallet.db".to_string()
+    }
+}
+
+fn check_mnemonic(field: &str, mnemonic: &str, problems: &mut Vec<String>) -> bool {
+    if mnemonic.trim().is_empty() {
+        problems.push(format!("{}: must be set", field));
+        return false;
+    }
+    match bip39::Mnemonic::parse_normalized(mnemonic) {
+        Ok(_) => true,
+        Err(e) => {
+            problems.push(format!("{}: not a valid BIP-39 mnemonic ({})", field, e));
+            false
+        }
+    }
+}
 
-        let provider_url = if let Ok(ws_url) = env::var("WS_URL") {
-            ProviderUrl::Ws(ws_url)
-        } else if let Ok(rpc_url) = env::var("RPC_URL") {
-            ProviderUrl::Http(rpc_url)
+/// Mixed-case addresses must carry a valid EIP-55 checksum
+pub(crate) fn check_address(
+    field: &str,
+    address: &str,
+    problems: &mut Vec<String>,
+) -> Option<Address> {
+    if address.is_empty() {
+        problems.push(format!("{}: must be set", field));
+        return None;
+    }
+    let hex = address.strip_prefix("0x").unwrap_or(address);
+    let mixed_case =
+        hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+    let parsed = if mixed_case {
+        Address::parse_checksummed(address, None).map_err(|e| e.to_string())
+    } else {
+        Address::from_str(address).map_err(|e| e.to_string())
+    };
+    match parsed {
+        Ok(address) => Some(address),
+        Err(e) => {
+            problems.push(format!(
+                "{}: {} is not a valid address ({})",
+                field, address, e
+            ));
+            None
+        }
+    }
+}
+
+/// An amount of wei as a decimal integer
+pub(crate) fn check_wei(field: &str, amount: &str, problems: &mut Vec<String>) -> Option<U256> {
+    match U256::from_str_radix(amount, 10) {
+        Ok(amount) => Some(amount),
+        Err(_) => {
+            problems.push(format!(
+                "{}: {:?} is not an amount of wei (a decimal integer)",
+                field, amount
+            ));
+            None
+        }
+    }
+}
+
+pub(crate) fn check_range(field: &str, value: u64, min: u64, max: u64, problems: &mut Vec<String>) {
+    if value < min || value > max {
+        let range = if max == u64::MAX {
+            format!("at least {}", min)
         } else {
-            return Err(anyhow::anyhow!("Either RPC_URL or WS_URL must be set"));
+            format!("between {} and {}", min, max)
         };
+        problems.push(format!(
+            "{}: {} is out of range, must be {}",
+            field, value, range
+        ));
+    }
+}
+
+fn check_provider_url(
+    field: &dyn Fn(&str) -> String,
+    provider_url: &ProviderUrl,
+    problems: &mut Vec<String>,
+) {
+    match provider_url {
+        ProviderUrl::Http(url) if url.is_empty() => problems.push(format!(
+            "{}: one of rpc_url, ws_url, or ipc_path must be set",
+            field("rpc_url")
+        )),
+        ProviderUrl::Http(url) if !url.starts_with("http://") && !url.starts_with("https://") => {
+            problems.push(format!(
+                "{}: {} must start with http:// or https://",
+                field("rpc_url"),
+                url
+            ))
+        }
+        ProviderUrl::Ws(url) if !url.starts_with("ws://") && !url.starts_with("wss://") => problems
+            .push(format!(
+                "{}: {} must start with ws:// or wss://",
+                field("ws_url"),
+                url
+            )),
+        ProviderUrl::Ipc(path) if path.is_empty() => {
+            problems.push(format!("{}: must not be empty", field("ipc_path")))
+        }
+        _ => {}
+    }
+}
 
-        let mnemonic = env::var("MNEMONIC").expect("MNEMONIC must be set");
-        let treasury_address = env::var("TREASURY_ADDRESS").expect("TREASURY_ADDRESS must be set");
-        let faucet_mnemonic = env::var("FAUCET_MNEMONIC").expect("FAUCET_MNEMONIC must be set");
-        let faucet_address = env::var("FAUCET_ADDRESS").expect("FAUCET_ADDRESS must be set");
-        let existential_deposit =
-            env::var("EXISTENTIAL_DEPOSIT").unwrap_or_else(|_| "10000000000000000".to_string()); // Default: 0.01 ETH
-        let port = env::var("PORT")
-            .unwrap_or_else(|_| "3000".to_string())
-            .parse()?;
-        let poll_interval = env::var("POLL_INTERVAL")
-            .unwrap_or_else(|_| "10".to_string())
-            .parse()?;
-        let block_offset_from_head = env::var("BLOCK_OFFSET_FROM_HEAD")
-            .unwrap_or_else(|_| "20".to_string())
-            .parse()?;
-        let get_logs_max_retries = env::var("GET_LOGS_MAX_RETRIES")
-            .unwrap_or_else(|_| "30".to_string())
-            .parse()?;
-        let get_logs_delay_ms = env::var("GET_LOGS_DELAY_MS")
-            .unwrap_or_else(|_| "50".to_string())
-            .parse()?;
-        let webhook_jwt_token = env::var("WEBHOOK_JWT_TOKEN").ok();
-
-        Ok(Self {
-            database_url,
+/// A configuration as written in a TOML file. Every field is optional so that environment
+/// variables can be layered on top before defaults are applied; the keys are the lower-cased
+/// environment variable names.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    database_url: Option<String>,
+    mnemonic: Option<String>,
+    faucet_mnemonic: Option<String>,
+    faucet_address: Option<String>,
+    port: Option<u16>,
+    poll_interval: Option<u64>,
+    get_logs_max_retries: Option<u32>,
+    get_logs_delay_ms: Option<u64>,
+    webhook_jwt_token: Option<String>,
+    health_max_block_lag: Option<u64>,
+    health_max_sweeper_idle_secs: Option<u64>,
+    shutdown_timeout_secs: Option<u64>,
+    webhook_allowed_hosts: Option<Vec<String>>,
+    funding_rate_per_sec: Option<u32>,
+    funding_policy: Option<String>,
+    alert_webhook_url: Option<String>,
+    faucet_watchdog_interval_secs: Option<u64>,
+    refill_mnemonic: Option<String>,
+    dust_reclaim: Option<String>,
+    dust_reclaim_interval_secs: Option<u64>,
+    /// The single chain when `chains` is empty, otherwise defaults for every chain
+    #[serde(flatten)]
+    chain: RawChain,
+    #[serde(default)]
+    chains: Vec<RawNamedChain>,
+}
+
+/// Per-chain settings; in `[[chains]]` tables, all but the transport and chain id fall
+/// back to the top-level values
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawChain {
+    chain_id: Option<u64>,
+    rpc_url: Option<String>,
+    ws_url: Option<String>,
+    ipc_path: Option<String>,
+    treasury_address: Option<String>,
+    existential_deposit: Option<String>,
+    block_offset_from_head: Option<u64>,
+    faucet_min_balance: Option<String>,
+    funding_cap: Option<String>,
+    faucet_refill_target: Option<String>,
+    faucet_refill_daily_cap: Option<String>,
+    dust_reclaim_min: Option<String>,
+    gas_top_up: Option<bool>,
+    gas_top_up_margin_percent: Option<u32>,
+    gas_top_up_min: Option<String>,
+    gas_top_up_max: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawNamedChain {
+    name: String,
+    #[serde(flatten)]
+    chain: RawChain,
+}
+
+impl RawConfig {
+    fn read(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| anyhow::anyhow!("Invalid {}: {}", path.display(), e))
+    }
+
+    /// Override the file's values with the environment. `CHAINS=polygon,base` selects the
+    /// chains (keeping their `[[chains]]` settings) and reads `POLYGON_RPC_URL`, ...
+    fn apply_env(&mut self, problems: &mut Vec<String>) {
+        env_string("DATABASE_URL", &mut self.database_url);
+        env_string("MNEMONIC", &mut self.mnemonic);
+        env_string("FAUCET_MNEMONIC", &mut self.faucet_mnemonic);
+        env_string("FAUCET_ADDRESS", &mut self.faucet_address);
+        env_string("WEBHOOK_JWT_TOKEN", &mut self.webhook_jwt_token);
+        if let Ok(hosts) = env::var("WEBHOOK_ALLOWED_HOSTS") {
+            self.webhook_allowed_hosts = Some(
+                hosts
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|host| !host.is_empty())
+                    .map(String::from)
+                    .collect(),
+            );
+        }
+        env_number("PORT", &mut self.port, problems);
+        env_number("POLL_INTERVAL", &mut self.poll_interval, problems);
+        env_number(
+            "GET_LOGS_MAX_RETRIES",
+            &mut self.get_logs_max_retries,
+            problems,
+        );
+        env_number("GET_LOGS_DELAY_MS", &mut self.get_logs_delay_ms, problems);
+        env_number(
+            "HEALTH_MAX_BLOCK_LAG",
+            &mut self.health_max_block_lag,
+            problems,
+        );
+        env_number(
+            "HEALTH_MAX_SWEEPER_IDLE_SECS",
+            &mut self.health_max_sweeper_idle_secs,
+            problems,
+        );
+        env_number(
+            "SHUTDOWN_TIMEOUT_SECS",
+            &mut self.shutdown_timeout_secs,
+            problems,
+        );
+        env_string("FUNDING_POLICY", &mut self.funding_policy);
+        env_string("ALERT_WEBHOOK_URL", &mut self.alert_webhook_url);
+        env_number(
+            "FAUCET_WATCHDOG_INTERVAL_SECS",
+            &mut self.faucet_watchdog_interval_secs,
+            problems,
+        );
+        env_string("REFILL_MNEMONIC", &mut self.refill_mnemonic);
+        env_string("DUST_RECLAIM", &mut self.dust_reclaim);
+        env_number(
+            "DUST_RECLAIM_INTERVAL_SECS",
+            &mut self.dust_reclaim_interval_secs,
+            problems,
+        );
+        env_number(
+            "FUNDING_RATE_PER_SEC",
+            &mut self.funding_rate_per_sec,
+            problems,
+        );
+        self.chain.apply_env("", problems);
+
+        if let Ok(names) = env::var("CHAINS") {
+            let mut from_file = std::mem::take(&mut self.chains);
+            self.chains = names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| match from_file.iter().position(|c| c.name == name) {
+                    Some(i) => from_file.swap_remove(i),
+                    None => RawNamedChain {
+                        name: name.to_string(),
+                        ..Default::default()
+                    },
+                })
+                .collect();
+            if self.chains.is_empty() {
+                problems.push("CHAINS: must name at least one chain".to_string());
+            }
+        }
+        for chain in &mut self.chains {
+            let prefix = env_prefix(&chain.name);
+            chain.chain.apply_env(&prefix, problems);
+        }
+    }
+
+    /// Apply defaults. Missing required values are left empty for [`Config::validate`]
+    /// to report.
+    fn resolve(self, problems: &mut Vec<String>) -> Config {
+        let defaults = &self.chain;
+        let chains = if self.chains.is_empty() {
+            // Without a chain list the node's chain id is used unless CHAIN_ID is set
+            vec![defaults.resolve(DEFAULT_CHAIN, defaults)]
+        } else {
+            self.chains
+                .iter()
+                .map(|chain| {
+                    if chain.chain.chain_id.is_none() {
+                        problems.push(format!("chains.{}.chain_id: must be set", chain.name));
+                    }
+                    chain.chain.resolve(&chain.name, defaults)
+                })
+                .collect()
+        };
+
+        let funding_policy = match self.funding_policy.as_deref().map(str::parse) {
+            Some(Ok(policy)) => policy,
+            Some(Err(e)) => {
+                problems.push(format!("funding_policy: {}", e));
+                FundingPolicy::default()
+            }
+            None => FundingPolicy::default(),
+        };
+        let dust_reclaim = match self.dust_reclaim.as_deref().map(str::parse) {
+            Some(Ok(destination)) => destination,
+            Some(Err(e)) => {
+                problems.push(format!("dust_reclaim: {}", e));
+                DustReclaim::default()
+            }
+            None => DustReclaim::default(),
+        };
+
+        Config {
+            database_url: self.database_url.unwrap_or_else(default_database_url),
+            mnemonic: self.mnemonic.unwrap_or_default(),
+            port: self.port.unwrap_or(3000),
+            poll_interval: self.poll_interval.unwrap_or(10),
+            faucet_mnemonic: self.faucet_mnemonic.unwrap_or_default(),
+            faucet_address: self.faucet_address.unwrap_or_default(),
+            get_logs_max_retries: self.get_logs_max_retries.unwrap_or(30),
+            get_logs_delay_ms: self.get_logs_delay_ms.unwrap_or(50),
+            webhook_jwt_token: self.webhook_jwt_token,
+            health_max_block_lag: self.health_max_block_lag.unwrap_or(50),
+            health_max_sweeper_idle_secs: self.health_max_sweeper_idle_secs.unwrap_or(300),
+            shutdown_timeout_secs: self.shutdown_timeout_secs.unwrap_or(30),
+            webhook_allowed_hosts: self.webhook_allowed_hosts.unwrap_or_default(),
+            funding_rate_per_sec: self.funding_rate_per_sec.unwrap_or(5),
+            funding_policy,
+            alert_webhook_url: self.alert_webhook_url,
+            faucet_watchdog_interval_secs: self.faucet_watchdog_interval_secs.unwrap_or(60),
+            refill_mnemonic: self.refill_mnemonic,
+            dust_reclaim,
+            dust_reclaim_interval_secs: self.dust_reclaim_interval_secs.unwrap_or(3600),
+            chains,
+        }
+    }
+}
+
+impl RawChain {
+    fn apply_env(&mut self, prefix: &str, problems: &mut Vec<String>) {
+        let key = |name: &str| format!("{}{}", prefix, name);
+        env_number(&key("CHAIN_ID"), &mut self.chain_id, problems);
+        env_string(&key("RPC_URL"), &mut self.rpc_url);
+        env_string(&key("WS_URL"), &mut self.ws_url);
+        env_string(&key("IPC_PATH"), &mut self.ipc_path);
+        env_string(&key("TREASURY_ADDRESS"), &mut self.treasury_address);
+        env_string(&key("EXISTENTIAL_DEPOSIT"), &mut self.existential_deposit);
+        env_number(
+            &key("BLOCK_OFFSET_FROM_HEAD"),
+            &mut self.block_offset_from_head,
+            problems,
+        );
+        env_string(&key("FAUCET_MIN_BALANCE"), &mut self.faucet_min_balance);
+        env_string(&key("FUNDING_CAP"), &mut self.funding_cap);
+        env_string(&key("DUST_RECLAIM_MIN"), &mut self.dust_reclaim_min);
+        env_string(&key("FAUCET_REFILL_TARGET"), &mut self.faucet_refill_target);
+        env_string(
+            &key("FAUCET_REFILL_DAILY_CAP"),
+            &mut self.faucet_refill_daily_cap,
+        );
+        env_number(&key("GAS_TOP_UP"), &mut self.gas_top_up, problems);
+        env_number(
+            &key("GAS_TOP_UP_MARGIN_PERCENT"),
+            &mut self.gas_top_up_margin_percent,
+            problems,
+        );
+        env_string(&key("GAS_TOP_UP_MIN"), &mut self.gas_top_up_min);
+        env_string(&key("GAS_TOP_UP_MAX"), &mut self.gas_top_up_max);
+    }
+
+    /// The transport and chain id are never inherited: falling back to the top-level
+    /// RPC_URL would silently point every chain at the same node
+    fn resolve(&self, name: &str, defaults: &RawChain) -> ChainConfig {
+        let provider_url = if let Some(ipc_path) = &self.ipc_path {
+            ProviderUrl::Ipc(ipc_path.clone())
+        } else if let Some(ws_url) = &self.ws_url {
+            ProviderUrl::Ws(ws_url.clone())
+        } else {
+            ProviderUrl::Http(self.rpc_url.clone().unwrap_or_default())
+        };
+        let existential_deposit = self
+            .existential_deposit
+            .clone()
+            .or_else(|| defaults.existential_deposit.clone())
+            .unwrap_or_else(|| "10000000000000000".to_string()); // Default: 0.01 ETH
+        let faucet_refill = self
+            .faucet_refill_target
+            .clone()
+            .or_else(|| defaults.faucet_refill_target.clone())
+            .map(|target| FaucetRefill {
+                // Default: one full refill a day
+                daily_cap: self
+                    .faucet_refill_daily_cap
+                    .clone()
+                    .or_else(|| defaults.faucet_refill_daily_cap.clone())
+                    .unwrap_or_else(|| target.clone()),
+                target,
+            });
+        let gas_top_up = self
+            .gas_top_up
+            .or(defaults.gas_top_up)
+            .unwrap_or(false)
+            .then(|| GasTopUp {
+                margin_percent: self
+                    .gas_top_up_margin_percent
+                    .or(defaults.gas_top_up_margin_percent)
+                    .unwrap_or(20),
+                min: self
+                    .gas_top_up_min
+                    .clone()
+                    .or_else(|| defaults.gas_top_up_min.clone())
+                    .unwrap_or_else(|| "0".to_string()),
+                // Default: never more than the fixed funding would send
+                max: self
+                    .gas_top_up_max
+                    .clone()
+                    .or_else(|| defaults.gas_top_up_max.clone())
+                    .unwrap_or_else(|| existential_deposit.clone()),
+            });
+
+        ChainConfig {
+            name: name.to_string(),
+            chain_id: self.chain_id.unwrap_or(0),
             provider_url,
-            mnemonic,
-            treasury_address,
-            port,
-            poll_interval,
-            faucet_mnemonic,
+            treasury_address: self
+                .treasury_address
+                .clone()
+                .or_else(|| defaults.treasury_address.clone())
+                .unwrap_or_default(),
+            block_offset_from_head: self
+                .block_offset_from_head
+                .or(defaults.block_offset_from_head)
+                .unwrap_or(20),
+            // Default: enough for one more funding
+            faucet_min_balance: self
+                .faucet_min_balance
+                .clone()
+                .or_else(|| defaults.faucet_min_balance.clone())
+                .unwrap_or_else(|| existential_deposit.clone()),
             existential_deposit,
-            faucet_address,
-            block_offset_from_head,
-            get_logs_max_retries,
-            get_logs_delay_ms,
-            webhook_jwt_token,
-        })
+            funding_cap: self
+                .funding_cap
+                .clone()
+                .or_else(|| defaults.funding_cap.clone()),
+            faucet_refill,
+            dust_reclaim_min: self
+                .dust_reclaim_min
+                .clone()
+                .or_else(|| defaults.dust_reclaim_min.clone())
+                .unwrap_or_else(|| "0".to_string()),
+            gas_top_up,
+        }
+    }
+}
+
+/// Prefix of the variables of the chain `name`, e.g. `POLYGON_` for `polygon`
+fn env_prefix(name: &str) -> String {
+    format!("{}_", name.to_uppercase().replace('-', "_"))
+}
+
+fn env_string(key: &str, value: &mut Option<String>) {
+    if let Ok(v) = env::var(key) {
+        *value = Some(v);
+    }
+}
+
+fn env_number<T: FromStr>(key: &str, value: &mut Option<T>, problems: &mut Vec<String>) {
+    if let Ok(v) = env::var(key) {
+        match v.trim().parse() {
+            Ok(n) => *value = Some(n),
+            Err(_) => problems.push(format!("{}: {:?} is not a valid number", key, v)),
+        }
     }
 }
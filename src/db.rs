@@ -1,23 +1,462 @@
+use crate::auth::ApiKey;
+use crate::deposit::{now_secs, Deposit, DepositStatus, StatusChange};
+use crate::export::{summarize, AccountRecord, ExportRecord, ExportSummary, ExportWriter};
+use crate::settings::SettingChange;
+use crate::storage::Storage;
+use alloy::primitives::U256;
 use anyhow::Result;
-use redb::{Database, ReadableTable, TableDefinition};
+use async_trait::async_trait;
+use redb::{
+    Database, ReadTransaction, ReadableTable, ReadableTableMetadata, TableDefinition, TableError,
+    TableHandle, WriteTransaction,
+};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::ops::Bound;
+use std::str::FromStr;
 use std::sync::Arc;
+use utoipa::ToSchema;
 
 const ACCOUNTS: TableDefinition<&str, (u32, &str, &str)> = TableDefinition::new("accounts"); // account_id -> (index, address, webhook_url)
 const ADDRESS_TO_ID: TableDefinition<&str, &str> = TableDefinition::new("address_to_id");
-const DEPOSITS: TableDefinition<&str, (&str, &str, &str)> = TableDefinition::new("deposits"); // tx_hash -> (account_id, amount, status)
+const DEPOSITS: TableDefinition<&str, Deposit> = TableDefinition::new("deposits"); // chain_id:tx_hash -> native deposit
 const STATE: TableDefinition<&str, &str> = TableDefinition::new("state");
 const TOKEN_METADATA: TableDefinition<&str, (&str, u64, &str)> =
     TableDefinition::new("token_metadata"); // token_address -> (symbol, decimals, name)
-const ERC20_DEPOSITS: TableDefinition<&str, (&str, &str, &str, &str, &str)> =
+const ERC20_DEPOSITS: TableDefinition<&str, Deposit> = TableDefinition::new("erc20_deposits"); // chain_id:tx_hash:log_index -> ERC20 deposit
+const ACCOUNT_STATUS: TableDefinition<&str, (&str, u64)> = TableDefinition::new("account_status"); // account_id -> (status, updated_at)
+const ACCOUNT_METADATA: TableDefinition<&str, &str> = TableDefinition::new("account_metadata"); // account_id -> metadata JSON
+const DEPOSITS_BY_STATUS: TableDefinition<(&str, u64, &str), &str> =
+    TableDefinition::new("deposits_by_status_chain"); // (status, chain_id, deposit_key) -> kind
+/// The status index before it was keyed by chain, dropped by the migration that replaced it
+const LEGACY_DEPOSITS_BY_STATUS: TableDefinition<(&str, &str), &str> =
+    TableDefinition::new("deposits_by_status");
+const DEPOSITS_BY_ACCOUNT: TableDefinition<(&str, u64, &str), &str> =
+    TableDefinition::new("deposits_by_account"); // (account_id, block_number, deposit_key) -> kind
+const ACCOUNT_CHAINS: TableDefinition<(&str, u64), &str> = TableDefinition::new("account_chains"); // (account_id, chain_id) -> address
+const SETTINGS_AUDIT: TableDefinition<u64, &str> = TableDefinition::new("settings_audit"); // sequence number -> SettingChange JSON
+const API_KEYS: TableDefinition<&str, &str> = TableDefinition::new("api_keys"); // key id -> ApiKey JSON
+const IDEMPOTENCY_KEYS: TableDefinition<&str, &str> = TableDefinition::new("idempotency_keys"); // key -> IdempotencyRecord JSON
+const IDEMPOTENCY_BY_TIME: TableDefinition<(u64, &str), ()> =
+    TableDefinition::new("idempotency_by_time"); // (created_at, key), to drop expired keys
+const FUNDINGS: TableDefinition<u64, &str> = TableDefinition::new("faucet_fundings"); // funding id -> FundingRecord JSON
+const FUNDINGS_BY_ADDRESS: TableDefinition<(u64, &str, u64), ()> =
+    TableDefinition::new("faucet_fundings_by_address"); // (chain_id, lower-cased address, funding id)
+const DUST_RECLAIMS: TableDefinition<u64, &str> = TableDefinition::new("dust_reclaims"); // reclaim id -> DustReclaimRecord JSON
+const FUNDING_QUEUE: TableDefinition<u64, &str> = TableDefinition::new("funding_queue"); // queue id -> QueuedFunding JSON
+
+// Deposit layouts used before deposits were stored as typed records
+const LEGACY_DEPOSITS: TableDefinition<&str, (&str, &str, &str)> = TableDefinition::new("deposits"); // tx_hash -> (account_id, amount, status)
+const LEGACY_ERC20_DEPOSITS: TableDefinition<&str, (&str, &str, &str, &str, &str)> =
     TableDefinition::new("erc20_deposits"); // tx_hash:log_index -> (account_id, amount, token_address, token_symbol, status)
 
-#[derive(Clone, Debug)]
-pub struct Erc20Deposit {
+// Deposit kinds stored in the secondary indexes, telling which table holds the deposit
+const NATIVE: &str = "native";
+const ERC20: &str = "erc20";
+
+fn deposit_table(kind: &str) -> TableDefinition<'static, &'static str, Deposit> {
+    if kind == ERC20 {
+        ERC20_DEPOSITS
+    } else {
+        DEPOSITS
+    }
+}
+
+/// ERC20 deposit keys are chain_id:tx_hash:log_index, native deposit keys chain_id:tx_hash
+fn kind_of_key(key: &str) -> &'static str {
+    if key.matches(':').count() > 1 {
+        ERC20
+    } else {
+        NATIVE
+    }
+}
+
+/// Chain a deposit key starts with
+fn chain_of_key(key: &str) -> Option<u64> {
+    key.split_once(':')?.0.parse().ok()
+}
+
+fn kind_of(deposit: &Deposit) -> &'static str {
+    if deposit.is_native() {
+        NATIVE
+    } else {
+        ERC20
+    }
+}
+
+/// Lifecycle state of a registered account
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountStatus {
+    /// Deposits are credited and webhooks are sent
+    Active,
+    /// Deposits are still swept, but no longer credited to the account
+    Deactivated,
+}
+
+impl AccountStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountStatus::Active => "active",
+            AccountStatus::Deactivated => "deactivated",
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Self {
+        match value {
+            "deactivated" => AccountStatus::Deactivated,
+            _ => AccountStatus::Active,
+        }
+    }
+}
+
+/// Outcome of registering an account with [`Storage::register_accounts`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Registration {
+    /// The account was created and watched on every requested chain
+    Created,
+    /// The id was registered already, with `address`; `added` are the requested chains
+    /// it was not watched on yet
+    Existing { address: String, added: Vec<u64> },
+    /// The id belongs to a deactivated account, which was left unchanged
+    Deactivated,
+}
+
+/// An account to register with [`Storage::register_accounts`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewAccount {
+    pub id: String,
+    pub index: u32,
+    pub address: String,
+    pub webhook_url: String,
+    pub chain_ids: Vec<u64>,
+}
+
+/// The response to a request made with an `Idempotency-Key`, replayed when the request
+/// is retried with the same key
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct IdempotencyRecord {
     pub key: String,
+    /// Hex-encoded SHA-256 of the request, to catch a key reused for another request
+    pub request_hash: String,
+    /// The JSON response body
+    pub response: String,
+    pub created_at: u64,
+}
+
+/// Why the faucet sent funds to an address
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FundingReason {
+    /// The account was registered, under the eager funding policy
+    Registration,
+    /// The address received its first deposit, under the on-first-deposit policy
+    FirstDeposit,
+    /// A sweep lacked gas
+    SweepGas,
+    /// The faucet watchdog refilled a low faucet from the refill key
+    FaucetRefill,
+}
+
+impl FundingReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FundingReason::Registration => "registration",
+            FundingReason::FirstDeposit => "first_deposit",
+            FundingReason::SweepGas => "sweep_gas",
+            FundingReason::FaucetRefill => "faucet_refill",
+        }
+    }
+}
+
+impl FromStr for FundingReason {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "registration" => Ok(FundingReason::Registration),
+            "first_deposit" => Ok(FundingReason::FirstDeposit),
+            "sweep_gas" => Ok(FundingReason::SweepGas),
+            "faucet_refill" => Ok(FundingReason::FaucetRefill),
+            _ => Err(anyhow::anyhow!("Unknown funding reason: {}", s)),
+        }
+    }
+}
+
+/// How long a funding or dust reclaim may stay pending, or a deposit sweeping, before it
+/// is taken for abandoned by a stopped process and marked interrupted or requeued.
+/// Instances sharing a database only take over each other's records past it, so a
+/// transfer still waiting for its receipt after that long no longer holds off other
+/// transfers from or to its address.
+pub const PENDING_LEASE_SECS: u64 = 600;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FundingStatus {
+    /// Recorded before sending; the transfer has not been confirmed yet
+    Pending,
+    Sent,
+    /// Not sent, or not confirmed; see the error
+    Failed,
+    /// Pending for longer than [`PENDING_LEASE_SECS`], e.g. because its process stopped;
+    /// the transfer may have been sent
+    Interrupted,
+}
+
+impl FundingStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FundingStatus::Pending => "pending",
+            FundingStatus::Sent => "sent",
+            FundingStatus::Failed => "failed",
+            FundingStatus::Interrupted => "interrupted",
+        }
+    }
+}
+
+impl FromStr for FundingStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pending" => Ok(FundingStatus::Pending),
+            "sent" => Ok(FundingStatus::Sent),
+            "failed" => Ok(FundingStatus::Failed),
+            "interrupted" => Ok(FundingStatus::Interrupted),
+            _ => Err(anyhow::anyhow!("Unknown funding status: {}", s)),
+        }
+    }
+}
+
+/// A faucet transfer in the funding ledger
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ToSchema)]
+pub struct FundingRecord {
+    pub id: u64,
+    pub chain_id: u64,
+    pub address: String,
+    /// Wei
+    pub amount: String,
+    pub reason: FundingReason,
+    pub status: FundingStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Unix timestamp (seconds)
+    pub created_at: u64,
+    /// Unix timestamp (seconds)
+    pub updated_at: u64,
+}
+
+impl FundingRecord {
+    /// Whether the amount counts against the address's lifetime cap: everything that may
+    /// have reached the chain
+    fn counts_against_cap(&self) -> bool {
+        self.status != FundingStatus::Failed
+    }
+}
+
+/// A faucet transfer about to be sent, for [`Storage::begin_funding`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewFunding {
+    pub chain_id: u64,
+    pub address: String,
+    /// Wei
+    pub amount: U256,
+    pub reason: FundingReason,
+}
+
+/// Outcome of [`Storage::begin_funding`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FundingStart {
+    /// Recorded as pending; send it, then mark it sent or failed
+    Started(FundingRecord),
+    /// The address has a pending funding on the chain already
+    InFlight(FundingRecord),
+    /// The funding would take the address past its lifetime cap; `spent` is what it got
+    /// so far
+    CapReached { spent: U256 },
+}
+
+/// A faucet funding waiting in its chain's funding queue. Queued fundings are stored, so
+/// those a stopped process didn't get to are sent after the next start.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct QueuedFunding {
+    pub id: u64,
+    pub chain_id: u64,
+    /// Registration id of the account, for the funding webhook
     pub account_id: String,
+    pub address: String,
+    pub reason: FundingReason,
+    /// Unix timestamp (seconds)
+    pub queued_at: u64,
+}
+
+/// Filter for listing fundings; unset fields match everything
+#[derive(Clone, Debug, Default)]
+pub struct FundingFilter {
+    pub chain_id: Option<u64>,
+    pub address: Option<String>,
+}
+
+impl FundingFilter {
+    fn matches(&self, funding: &FundingRecord) -> bool {
+        self.chain_id
+            .is_none_or(|chain_id| funding.chain_id == chain_id)
+            && self
+                .address
+                .as_ref()
+                .is_none_or(|address| funding.address.eq_ignore_ascii_case(address))
+    }
+}
+
+/// Sum of the fundings that count against a lifetime cap
+pub(crate) fn capped_spend<'a>(
+    fundings: impl IntoIterator<Item = &'a FundingRecord>,
+) -> Result<U256> {
+    let mut spent = U256::ZERO;
+    for funding in fundings {
+        if funding.counts_against_cap() {
+            spent = spent.saturating_add(U256::from_str_radix(&funding.amount, 10)?);
+        }
+    }
+    Ok(spent)
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DustReclaimStatus {
+    /// Recorded before sending; the transfer has not been confirmed yet
+    Pending,
+    Sent,
+    /// The balance was not worth reclaiming; see the error
+    Skipped,
+    /// Not sent, or not confirmed; see the error
+    Failed,
+    /// Pending for longer than [`PENDING_LEASE_SECS`], e.g. because its process stopped;
+    /// the transfer may have been sent
+    Interrupted,
+}
+
+impl DustReclaimStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DustReclaimStatus::Pending => "pending",
+            DustReclaimStatus::Sent => "sent",
+            DustReclaimStatus::Skipped => "skipped",
+            DustReclaimStatus::Failed => "failed",
+            DustReclaimStatus::Interrupted => "interrupted",
+        }
+    }
+
+    /// Whether the address was settled by this check: it needs another look only after
+    /// new activity
+    pub fn settles(&self) -> bool {
+        matches!(self, DustReclaimStatus::Sent | DustReclaimStatus::Skipped)
+    }
+}
+
+impl FromStr for DustReclaimStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pending" => Ok(DustReclaimStatus::Pending),
+            "sent" => Ok(DustReclaimStatus::Sent),
+            "skipped" => Ok(DustReclaimStatus::Skipped),
+            "failed" => Ok(DustReclaimStatus::Failed),
+            "interrupted" => Ok(DustReclaimStatus::Interrupted),
+            _ => Err(anyhow::anyhow!("Unknown dust reclaim status: {}", s)),
+        }
+    }
+}
+
+/// A check of a swept address for leftover native balance, and the transfer that
+/// reclaimed it, if any
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ToSchema)]
+pub struct DustReclaimRecord {
+    pub id: u64,
+    pub chain_id: u64,
+    /// The deposit address the dust was left on
+    pub address: String,
+    /// Where the dust went: the faucet or the treasury address
+    pub destination: String,
+    /// Wei sent, or that would have been sent after gas when skipped
     pub amount: String,
-    pub token_address: String,
-    pub token_symbol: String,
+    pub status: DustReclaimStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Unix timestamp (seconds)
+    pub created_at: u64,
+    /// Unix timestamp (seconds)
+    pub updated_at: u64,
+}
+
+/// A dust reclaim about to be sent or skipped, for [`Storage::begin_dust_reclaim`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewDustReclaim {
+    pub chain_id: u64,
+    pub address: String,
+    pub destination: String,
+    /// Wei
+    pub amount: U256,
+}
+
+/// Filter for listing deposits; unset fields match everything
+#[derive(Clone, Debug, Default)]
+pub struct DepositFilter {
+    pub chain_id: Option<u64>,
+    pub account_id: Option<String>,
+    pub status: Option<String>,
+    pub token: Option<String>,
+}
+
+impl DepositFilter {
+    fn matches(&self, deposit: &Deposit) -> bool {
+        if self
+            .chain_id
+            .is_some_and(|chain_id| deposit.chain_id != chain_id)
+        {
+            return false;
+        }
+        if let Some(ref account_id) = self.account_id {
+            if &deposit.account_id != account_id {
+                return false;
+            }
+        }
+        if let Some(ref status) = self.status {
+            if !deposit.status.as_str().eq_ignore_ascii_case(status) {
+                return false;
+            }
+        }
+        if let Some(ref token) = self.token {
+            // "native", a token contract address, or a token symbol
+            let matches_token = if token.eq_ignore_ascii_case("native") {
+                deposit.is_native()
+            } else {
+                deposit
+                    .token_address
+                    .as_deref()
+                    .is_some_and(|a| a.eq_ignore_ascii_case(token))
+                    || deposit
+                        .token_symbol
+                        .as_deref()
+                        .is_some_and(|s| s.eq_ignore_ascii_case(token))
+            };
+            if !matches_token {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// A page of deposits; pass `next_cursor` back to fetch the following page
+#[derive(Clone, Debug)]
+pub struct DepositPage {
+    pub deposits: Vec<Deposit>,
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Clone)]
@@ -26,24 +465,52 @@ pub struct Db {
 }
 
 impl Db {
+    /// Open (or create) the database and bring its schema up to date
     pub fn new(path: &str) -> Result<Self> {
         let db = Database::create(path)?;
 
-        // Initialize tables
         let write_txn = db.begin_write()?;
-        {
-            let _ = write_txn.open_table(ACCOUNTS)?;
-            let _ = write_txn.open_table(ADDRESS_TO_ID)?;
-            let _ = write_txn.open_table(DEPOSITS)?;
-            let _ = write_txn.open_table(STATE)?;
-            let _ = write_txn.open_table(TOKEN_METADATA)?;
-            let _ = write_txn.open_table(ERC20_DEPOSITS)?;
-        }
+        let report = run_migrations(&write_txn)?;
         write_txn.commit()?;
 
+        for (version, description) in &report.applied {
+            tracing::info!("🗄️ Applied migration {}: {}", version, description);
+        }
+
         Ok(Self { db: Arc::new(db) })
     }
 
+    /// Run pending migrations without starting the service.
+    /// With `dry_run` the migrations are executed but rolled back, and the database must already exist.
+    pub fn migrate(path: &str, dry_run: bool) -> Result<MigrationReport> {
+        let db = if dry_run {
+            Database::open(path)?
+        } else {
+            Database::create(path)?
+        };
+
+        let write_txn = db.begin_write()?;
+        let report = run_migrations(&write_txn)?;
+        if dry_run {
+            write_txn.abort()?;
+        } else {
+            write_txn.commit()?;
+        }
+
+        Ok(report)
+    }
+
+    /// Schema version the database was last migrated to
+    pub fn schema_version(&self) -> Result<u32> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(STATE)?;
+        parse_schema_version(
+            table
+                .get(SCHEMA_VERSION_KEY)?
+                .map(|v| v.value().to_string()),
+        )
+    }
+
     #[allow(dead_code)]
     pub fn get_next_derivation_index(&self) -> Result<u32> {
         let read_txn = self.db.begin_read()?;
@@ -58,30 +525,109 @@ impl Db {
         }
     }
 
-    pub fn register_account(
+    /// Register a new account and watch its address on `chain_ids`; fails if `id` is
+    /// registered already
+    #[cfg(test)]
+    pub(crate) fn register_account(
         &self,
         id: &str,
         index: u32,
         address: &str,
         webhook_url: &str,
+        chain_ids: &[u64],
     ) -> Result<()> {
+        let account = NewAccount {
+            id: id.to_string(),
+            index,
+            address: address.to_string(),
+            webhook_url: webhook_url.to_string(),
+            chain_ids: chain_ids.to_vec(),
+        };
+        match self.register_accounts(&[account])?.remove(0) {
+            Registration::Created => Ok(()),
+            _ => Err(anyhow::anyhow!("Account {} already registered", id)),
+        }
+    }
+
+    /// Register accounts, or for ids registered already, watch their address on the
+    /// account's chains too, all in one transaction. If any id belongs to a deactivated
+    /// account, nothing is written.
+    pub fn register_accounts(&self, new_accounts: &[NewAccount]) -> Result<Vec<Registration>> {
         let write_txn = self.db.begin_write()?;
-        {
+        let registrations = {
             let mut accounts = write_txn.open_table(ACCOUNTS)?;
-            accounts.insert(id, (index, address, webhook_url))?;
+            let mut address_to_id = write_txn.open_table(ADDRESS_TO_ID)?;
+            let mut chains = write_txn.open_table(ACCOUNT_CHAINS)?;
+            let statuses = write_txn.open_table(ACCOUNT_STATUS)?;
 
-            let mut addr_map = write_txn.open_table(ADDRESS_TO_ID)?;
-            addr_map.insert(address, id)?;
+            let mut registrations = Vec::with_capacity(new_accounts.len());
+            for account in new_accounts {
+                let id = account.id.as_str();
+                let existing = accounts.get(id)?.map(|v| v.value().1.to_string());
+                let registration = match existing {
+                    Some(existing) => {
+                        let status = statuses.get(id)?.map(|v| AccountStatus::parse(v.value().0));
+                        if status == Some(AccountStatus::Deactivated) {
+                            Registration::Deactivated
+                        } else {
+                            let mut added = Vec::new();
+                            for &chain_id in &account.chain_ids {
+                                if chains.insert((id, chain_id), existing.as_str())?.is_none() {
+                                    added.push(chain_id);
+                                }
+                            }
+                            Registration::Existing {
+                                address: existing,
+                                added,
+                            }
+                        }
+                    }
+                    None => {
+                        let address = account.address.as_str();
+                        accounts
+                            .insert(id, (account.index, address, account.webhook_url.as_str()))?;
+                        address_to_id.insert(address, id)?;
+                        for &chain_id in &account.chain_ids {
+                            chains.insert((id, chain_id), address)?;
+                        }
+                        Registration::Created
+                    }
+                };
+                registrations.push(registration);
+            }
+            registrations
+        };
+        if registrations.contains(&Registration::Deactivated) {
+            write_txn.abort()?;
+        } else {
+            write_txn.commit()?;
         }
-        write_txn.commit()?;
-        Ok(())
+        Ok(registrations)
     }
 
-    pub fn get_registration_id_by_address(&self, address: &str) -> Result<Option<String>> {
+    /// Chains an account's address is watched on, in ascending order
+    pub fn get_account_chains(&self, id: &str) -> Result<Vec<u64>> {
         let read_txn = self.db.begin_read()?;
-        let table = read_txn.open_table(ADDRESS_TO_ID)?;
-        let result = table.get(address)?;
-        Ok(result.map(|v| v.value().to_string()))
+        account_chains(&read_txn.open_table(ACCOUNT_CHAINS)?, id)
+    }
+
+    /// Registration id of the account owning `address`, if it is watched on `chain_id`
+    pub fn get_registration_id_by_address(
+        &self,
+        chain_id: u64,
+        address: &str,
+    ) -> Result<Option<String>> {
+        let read_txn = self.db.begin_read()?;
+        let Some(id) = read_txn
+            .open_table(ADDRESS_TO_ID)?
+            .get(address)?
+            .map(|v| v.value().to_string())
+        else {
+            return Ok(None);
+        };
+        let chains = read_txn.open_table(ACCOUNT_CHAINS)?;
+        let watched = chains.get((id.as_str(), chain_id))?.is_some();
+        Ok(watched.then_some(id))
     }
 
     pub fn get_account_by_address(&self, address: &str) -> Result<Option<String>> {
@@ -108,14 +654,88 @@ impl Db {
         Ok(result.map(|v| v.value().2.to_string()))
     }
 
+    /// Update the webhook URL and/or metadata of an account.
+    /// Returns false if the account does not exist.
+    pub fn update_account(
+        &self,
+        id: &str,
+        webhook_url: Option<&str>,
+        metadata: Option<&str>,
+    ) -> Result<bool> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut accounts = write_txn.open_table(ACCOUNTS)?;
+            let (index, address, current_webhook) = {
+                let Some(v) = accounts.get(id)? else {
+                    return Ok(false);
+                };
+                let val = v.value();
+                (val.0, val.1.to_string(), val.2.to_string())
+            };
+
+            if let Some(webhook_url) = webhook_url {
+                if webhook_url != current_webhook {
+                    accounts.insert(id, (index, address.as_str(), webhook_url))?;
+                }
+            }
+
+            if let Some(metadata) = metadata {
+                let mut metadata_table = write_txn.open_table(ACCOUNT_METADATA)?;
+                metadata_table.insert(id, metadata)?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(true)
+    }
+
+    /// Mark an account as deactivated. Returns false if the account does not exist.
+    pub fn deactivate_account(&self, id: &str) -> Result<bool> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let accounts = write_txn.open_table(ACCOUNTS)?;
+            if accounts.get(id)?.is_none() {
+                return Ok(false);
+            }
+
+            let mut status = write_txn.open_table(ACCOUNT_STATUS)?;
+            status.insert(id, (AccountStatus::Deactivated.as_str(), now_secs()))?;
+        }
+        write_txn.commit()?;
+        Ok(true)
+    }
+
+    /// Accounts without an explicit lifecycle entry are considered active
+    pub fn get_account_status(&self, id: &str) -> Result<AccountStatus> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(ACCOUNT_STATUS)?;
+        let result = table.get(id)?;
+        Ok(result
+            .map(|v| AccountStatus::parse(v.value().0))
+            .unwrap_or(AccountStatus::Active))
+    }
+
+    pub fn is_account_active(&self, id: &str) -> Result<bool> {
+        Ok(self.get_account_status(id)? == AccountStatus::Active)
+    }
+
+    pub fn get_account_metadata(&self, id: &str) -> Result<Option<String>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(ACCOUNT_METADATA)?;
+        let result = table.get(id)?;
+        Ok(result.map(|v| v.value().to_string()))
+    }
+
+    // ========== Deposits ==========
+
     /// Record a deposit and return true if it was newly recorded, false if it was a duplicate
-    pub fn record_deposit(&self, tx_hash: &str, account_id: &str, amount: &str) -> Result<bool> {
+    pub fn record_deposit(&self, deposit: &Deposit) -> Result<bool> {
         let write_txn = self.db.begin_write()?;
         let is_new = {
-            let mut deposits = write_txn.open_table(DEPOSITS)?;
+            let mut deposits = write_txn.open_table(deposit_table(kind_of(deposit)))?;
             // Check if exists to avoid overwrite and duplicates
-            if deposits.get(tx_hash)?.is_none() {
-                deposits.insert(tx_hash, (account_id, amount, "detected"))?;
+            if deposits.get(deposit.key.as_str())?.is_none() {
+                deposits.insert(deposit.key.as_str(), deposit)?;
+                index_deposit(&write_txn, deposit)?;
                 true
             } else {
                 false
@@ -125,61 +745,247 @@ impl Db {
         Ok(is_new)
     }
 
-    pub fn mark_deposit_swept(&self, tx_hash: &str) -> Result<()> {
-        let write_txn = self.db.begin_write()?;
-        {
-            let mut deposits = write_txn.open_table(DEPOSITS)?;
-            let (account_id, amount) = {
-                let current_val = deposits.get(tx_hash)?;
-                if let Some(v) = current_val {
-                    let val = v.value();
-                    (val.0.to_string(), val.1.to_string())
-                } else {
-                    return Ok(());
-                }
-            };
+    /// Get a native (chain_id:tx_hash) or ERC20 (chain_id:tx_hash:log_index) deposit by key
+    pub fn get_deposit(&self, key: &str) -> Result<Option<Deposit>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(deposit_table(kind_of_key(key)))?;
+        let result = table.get(key)?;
+        Ok(result.map(|v| v.value()))
+    }
+
+    /// Move a deposit to another status. Fails if the transition is not allowed.
+    pub fn transition_deposit(
+        &self,
+        key: &str,
+        next: DepositStatus,
+        note: Option<&str>,
+    ) -> Result<Deposit> {
+        self.update_deposit(key, |deposit| deposit.transition(next, note))
+    }
+
+    /// Claim a detected deposit for sweeping. Returns `None` if it is no longer detected,
+    /// e.g. because another instance claimed it first.
+    pub fn mark_deposit_sweeping(&self, key: &str) -> Result<Option<Deposit>> {
+        let mut claimed = false;
+        let deposit = self.update_deposit(key, |deposit| {
+            if deposit.status == DepositStatus::Detected {
+                deposit.transition(DepositStatus::Sweeping, None)?;
+                claimed = true;
+            }
+            Ok(())
+        })?;
+        Ok(claimed.then_some(deposit))
+    }
+
+    pub fn mark_deposit_swept(&self, key: &str, sweep_tx_hash: &str) -> Result<Deposit> {
+        self.update_deposit(key, |deposit| {
+            deposit.transition(DepositStatus::Swept, None)?;
+            deposit.sweep_tx_hash = Some(sweep_tx_hash.to_string());
+            deposit.last_error = None;
+            Ok(())
+        })
+    }
+
+    /// The sweep transaction was mined but reverted
+    pub fn mark_deposit_failed(
+        &self,
+        key: &str,
+        sweep_tx_hash: &str,
+        error: &str,
+    ) -> Result<Deposit> {
+        self.update_deposit(key, |deposit| {
+            deposit.transition(DepositStatus::Failed, Some(error))?;
+            deposit.sweep_tx_hash = Some(sweep_tx_hash.to_string());
+            deposit.last_error = Some(error.to_string());
+            Ok(())
+        })
+    }
+
+    /// Return a deposit that is still sweeping to detected so the next sweep cycle retries it.
+    /// Deposits in any other status are left untouched.
+    pub fn release_deposit(&self, key: &str, error: &str) -> Result<Deposit> {
+        self.update_deposit(key, |deposit| {
+            if deposit.status == DepositStatus::Sweeping {
+                deposit.transition(DepositStatus::Detected, Some(error))?;
+                deposit.last_error = Some(error.to_string());
+            }
+            Ok(())
+        })
+    }
 
-            deposits.insert(tx_hash, (account_id.as_str(), amount.as_str(), "swept"))?;
+    /// Set a deposit aside for manual review
+    pub fn quarantine_deposit(&self, key: &str, reason: &str) -> Result<Deposit> {
+        self.update_deposit(key, |deposit| {
+            deposit.transition(DepositStatus::Quarantined, Some(reason))?;
+            deposit.last_error = Some(reason.to_string());
+            Ok(())
+        })
+    }
+
+    /// Return deposits on `chain_id` claimed for sweeping before `started_before` (unix
+    /// seconds) to detected, as the process sweeping them has presumably stopped. Sweeps
+    /// claimed since, possibly by another instance sharing the database, are left alone.
+    /// Returns the number of deposits requeued.
+    pub fn requeue_interrupted_sweeps(&self, chain_id: u64, started_before: u64) -> Result<usize> {
+        let keys: Vec<String> = self
+            .get_deposits_by_status(chain_id, DepositStatus::Sweeping)?
+            .into_iter()
+            .map(|d| d.key)
+            .collect();
+
+        let mut requeued = 0;
+        for key in &keys {
+            let mut released = false;
+            self.update_deposit(key, |deposit| {
+                if deposit.status == DepositStatus::Sweeping
+                    && deposit.updated_at() < started_before
+                {
+                    deposit.transition(DepositStatus::Detected, Some("sweep interrupted"))?;
+                    deposit.last_error = Some("sweep interrupted".to_string());
+                    released = true;
+                }
+                Ok(())
+            })?;
+            requeued += released as usize;
         }
-        write_txn.commit()?;
-        Ok(())
+        Ok(requeued)
+    }
+
+    /// All deposits (native and ERC20) on `chain_id` currently in `status`
+    pub fn get_deposits_by_status(
+        &self,
+        chain_id: u64,
+        status: DepositStatus,
+    ) -> Result<Vec<Deposit>> {
+        let mut results = self.deposits_with_status(chain_id, status, NATIVE)?;
+        results.extend(self.deposits_with_status(chain_id, status, ERC20)?);
+        Ok(results)
+    }
+
+    pub fn get_detected_deposits(&self, chain_id: u64) -> Result<Vec<Deposit>> {
+        self.deposits_with_status(chain_id, DepositStatus::Detected, NATIVE)
     }
 
-    pub fn get_detected_deposits(&self) -> Result<Vec<(String, String, String)>> {
+    pub fn get_detected_erc20_deposits(&self, chain_id: u64) -> Result<Vec<Deposit>> {
+        self.deposits_with_status(chain_id, DepositStatus::Detected, ERC20)
+    }
+
+    fn deposits_with_status(
+        &self,
+        chain_id: u64,
+        status: DepositStatus,
+        kind: &str,
+    ) -> Result<Vec<Deposit>> {
         let read_txn = self.db.begin_read()?;
-        let table = read_txn.open_table(DEPOSITS)?;
+        let table = read_txn.open_table(deposit_table(kind))?;
         let mut results = Vec::new();
-        for item in table.iter()? {
-            let (tx_hash, value) = item?;
-            let (account_id, amount, status) = value.value();
-            if status == "detected" {
-                results.push((
-                    tx_hash.value().to_string(),
-                    account_id.to_string(),
-                    amount.to_string(),
-                ));
+        for key in keys_with_status(&read_txn, chain_id, status, kind)? {
+            if let Some(value) = table.get(key.as_str())? {
+                results.push(value.value());
             }
         }
         Ok(results)
     }
 
-    pub fn get_last_processed_block(&self) -> Result<u64> {
+    /// Apply `update` to a deposit and keep the status index in sync, in one write transaction
+    fn update_deposit(
+        &self,
+        key: &str,
+        update: impl FnOnce(&mut Deposit) -> Result<()>,
+    ) -> Result<Deposit> {
+        let write_txn = self.db.begin_write()?;
+        let deposit = {
+            let mut table = write_txn.open_table(deposit_table(kind_of_key(key)))?;
+            let mut deposit = table
+                .get(key)?
+                .map(|v| v.value())
+                .ok_or_else(|| anyhow::anyhow!("Deposit {} not found", key))?;
+
+            let previous = deposit.status;
+            update(&mut deposit)?;
+            table.insert(key, &deposit)?;
+            if previous != deposit.status {
+                reindex_deposit_status(&write_txn, &deposit, previous)?;
+            }
+            deposit
+        };
+        write_txn.commit()?;
+        Ok(deposit)
+    }
+
+    pub fn get_last_processed_block(&self, chain_id: u64) -> Result<u64> {
         let read_txn = self.db.begin_read()?;
         let table = read_txn.open_table(STATE)?;
-        let result = table.get("last_block")?;
+        let result = table.get(last_block_key(chain_id).as_str())?;
         Ok(result.map(|v| v.value().parse().unwrap_or(0)).unwrap_or(0))
     }
 
-    pub fn set_last_processed_block(&self, block: u64) -> Result<()> {
+    pub fn set_last_processed_block(&self, chain_id: u64, block: u64) -> Result<()> {
         let write_txn = self.db.begin_write()?;
         {
             let mut state = write_txn.open_table(STATE)?;
-            state.insert("last_block", block.to_string().as_str())?;
+            state.insert(
+                last_block_key(chain_id).as_str(),
+                block.to_string().as_str(),
+            )?;
         }
         write_txn.commit()?;
         Ok(())
     }
 
+    /// Assign accounts, deposits, and the last processed block written before chains were
+    /// tracked to `chain_id`. Only the first call has an effect; returns the number of
+    /// accounts and deposits it assigned.
+    pub fn assign_legacy_chain(&self, chain_id: u64) -> Result<usize> {
+        let write_txn = self.db.begin_write()?;
+        let assigned = {
+            let mut state = write_txn.open_table(STATE)?;
+            if state.get(LEGACY_CHAIN_KEY)?.is_some() {
+                return Ok(0);
+            }
+            let legacy_block = state
+                .remove(LEGACY_LAST_BLOCK_KEY)?
+                .map(|v| v.value().to_string());
+            if let Some(block) = legacy_block {
+                state.insert(last_block_key(chain_id).as_str(), block.as_str())?;
+            }
+            state.insert(LEGACY_CHAIN_KEY, chain_id.to_string().as_str())?;
+
+            let mut assigned = 0;
+            let mut chains = write_txn.open_table(ACCOUNT_CHAINS)?;
+            let mut unassigned = Vec::new();
+            for item in write_txn.open_table(ACCOUNTS)?.iter()? {
+                let (id, value) = item?;
+                let id = id.value().to_string();
+                if account_chains(&chains, &id)?.is_empty() {
+                    unassigned.push((id, value.value().1.to_string()));
+                }
+            }
+            for (id, address) in &unassigned {
+                chains.insert((id.as_str(), chain_id), address.as_str())?;
+            }
+            assigned += unassigned.len();
+
+            let mut deposits = Vec::new();
+            for definition in [DEPOSITS, ERC20_DEPOSITS] {
+                for item in write_txn.open_table(definition)?.iter()? {
+                    let deposit = item?.1.value();
+                    if deposit.chain_id == 0 {
+                        deposits.push(deposit);
+                    }
+                }
+            }
+            for deposit in &mut deposits {
+                deposit.chain_id = chain_id;
+                rekey_deposit(&write_txn, deposit, 0)?;
+            }
+            assigned += deposits.len();
+            assigned
+        };
+        write_txn.commit()?;
+        Ok(assigned)
+    }
+
     // ========== ERC20 Token Metadata ==========
 
     pub fn store_token_metadata(
@@ -208,87 +1014,1604 @@ impl Db {
         }))
     }
 
-    // ========== ERC20 Deposits ==========
+    // ========== API Keys ==========
 
-    /// Record an ERC20 deposit and return true if it was newly recorded, false if it was a duplicate
-    pub fn record_erc20_deposit(
-        &self,
-        tx_hash: &str,
-        log_index: u64,
-        account_id: &str,
-        amount: &str,
-        token_address: &str,
-        token_symbol: &str,
-    ) -> Result<bool> {
+    pub fn create_api_key(&self, key: &ApiKey) -> Result<()> {
         let write_txn = self.db.begin_write()?;
-        let is_new = {
-            let mut deposits = write_txn.open_table(ERC20_DEPOSITS)?;
-            let key = format!("{}:{}", tx_hash, log_index);
-            if deposits.get(key.as_str())?.is_none() {
-                deposits.insert(
-                    key.as_str(),
-                    (account_id, amount, token_address, token_symbol, "detected"),
-                )?;
-                true
-            } else {
-                false
+        {
+            let mut keys = write_txn.open_table(API_KEYS)?;
+            if keys.get(key.id.as_str())?.is_some() {
+                return Err(anyhow::anyhow!("API key {} already exists", key.id));
             }
-        };
+            keys.insert(key.id.as_str(), serde_json::to_string(key)?.as_str())?;
+        }
         write_txn.commit()?;
-        Ok(is_new)
+        Ok(())
     }
 
-    pub fn get_detected_erc20_deposits(&self) -> Result<Vec<Erc20Deposit>> {
+    pub fn get_api_key(&self, id: &str) -> Result<Option<ApiKey>> {
         let read_txn = self.db.begin_read()?;
-        let table = read_txn.open_table(ERC20_DEPOSITS)?;
-        let mut results = Vec::new();
-        for item in table.iter()? {
-            let (key, value) = item?;
-            let (account_id, amount, token_address, token_symbol, status) = value.value();
-            if status == "detected" {
-                results.push(Erc20Deposit {
-                    key: key.value().to_string(), // tx_hash:log_index
-                    account_id: account_id.to_string(),
-                    amount: amount.to_string(),
-                    token_address: token_address.to_string(),
-                    token_symbol: token_symbol.to_string(),
-                });
-            }
+        let keys = read_txn.open_table(API_KEYS)?;
+        match keys.get(id)? {
+            Some(key) => Ok(Some(serde_json::from_str(key.value())?)),
+            None => Ok(None),
         }
-        Ok(results)
     }
 
-    pub fn mark_erc20_deposit_swept(&self, key: &str) -> Result<()> {
-        let write_txn = self.db.begin_write()?;
+    pub fn list_api_keys(&self) -> Result<Vec<ApiKey>> {
+        let read_txn = self.db.begin_read()?;
+        let keys = read_txn.open_table(API_KEYS)?;
+        let mut result = Vec::new();
+        for entry in keys.iter()? {
+            let (_, key) = entry?;
+            result.push(serde_json::from_str(key.value())?);
+        }
+        Ok(result)
+    }
+
+    /// Revoking a key again keeps the time of the first revocation
+    pub fn revoke_api_key(&self, id: &str) -> Result<bool> {
+        let write_txn = self.db.begin_write()?;
         {
-            let mut deposits = write_txn.open_table(ERC20_DEPOSITS)?;
-            let (account_id, amount, token_address, token_symbol) = {
-                let current_val = deposits.get(key)?;
-                if let Some(v) = current_val {
-                    let val = v.value();
-                    (
-                        val.0.to_string(),
-                        val.1.to_string(),
-                        val.2.to_string(),
-                        val.3.to_string(),
-                    )
+            let mut keys = write_txn.open_table(API_KEYS)?;
+            let Some(mut key) = keys
+                .get(id)?
+                .map(|key| serde_json::from_str::<ApiKey>(key.value()))
+                .transpose()?
+            else {
+                return Ok(false);
+            };
+            key.revoked_at.get_or_insert_with(now_secs);
+            keys.insert(id, serde_json::to_string(&key)?.as_str())?;
+        }
+        write_txn.commit()?;
+        Ok(true)
+    }
+
+    // ========== Idempotency Keys ==========
+
+    pub fn get_idempotency_record(&self, key: &str) -> Result<Option<IdempotencyRecord>> {
+        let read_txn = self.db.begin_read()?;
+        let records = read_txn.open_table(IDEMPOTENCY_KEYS)?;
+        match records.get(key)? {
+            Some(record) => Ok(Some(serde_json::from_str(record.value())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store `record` unless its key has a record already, after dropping the records
+    /// created before `expired_before`. Returns the record stored for the key.
+    pub fn save_idempotency_record(
+        &self,
+        record: &IdempotencyRecord,
+        expired_before: u64,
+    ) -> Result<IdempotencyRecord> {
+        let write_txn = self.db.begin_write()?;
+        let stored = {
+            let mut records = write_txn.open_table(IDEMPOTENCY_KEYS)?;
+            let mut by_time = write_txn.open_table(IDEMPOTENCY_BY_TIME)?;
+
+            let mut expired = Vec::new();
+            for entry in by_time.range::<(u64, &str)>(..(expired_before, ""))? {
+                let (key, _) = entry?;
+                let (created_at, key) = key.value();
+                expired.push((created_at, key.to_string()));
+            }
+            for (created_at, key) in &expired {
+                by_time.remove((*created_at, key.as_str()))?;
+                records.remove(key.as_str())?;
+            }
+
+            let existing = records
+                .get(record.key.as_str())?
+                .map(|r| serde_json::from_str::<IdempotencyRecord>(r.value()))
+                .transpose()?;
+            match existing {
+                Some(existing) => existing,
+                None => {
+                    records.insert(record.key.as_str(), serde_json::to_string(record)?.as_str())?;
+                    by_time.insert((record.created_at, record.key.as_str()), ())?;
+                    record.clone()
+                }
+            }
+        };
+        write_txn.commit()?;
+        Ok(stored)
+    }
+
+    // ========== Faucet Funding Ledger ==========
+
+    /// Record `funding` as pending, unless the address has a pending funding on the chain
+    /// already or `cap` wei would be exceeded. Checked and recorded in one transaction.
+    pub fn begin_funding(&self, funding: &NewFunding, cap: Option<U256>) -> Result<FundingStart> {
+        let address_key = funding.address.to_ascii_lowercase();
+        let write_txn = self.db.begin_write()?;
+        let start = {
+            let mut fundings = write_txn.open_table(FUNDINGS)?;
+            let mut by_address = write_txn.open_table(FUNDINGS_BY_ADDRESS)?;
+
+            let mut previous = Vec::new();
+            let range = (funding.chain_id, address_key.as_str(), 0)
+                ..=(funding.chain_id, address_key.as_str(), u64::MAX);
+            for entry in by_address.range(range)? {
+                let (key, _) = entry?;
+                let (_, _, id) = key.value();
+                if let Some(record) = fundings.get(id)? {
+                    previous.push(serde_json::from_str::<FundingRecord>(record.value())?);
+                }
+            }
+            let started_before = now_secs().saturating_sub(PENDING_LEASE_SECS);
+            for record in previous.iter_mut() {
+                if record.status == FundingStatus::Pending && record.created_at < started_before {
+                    record.status = FundingStatus::Interrupted;
+                    record.updated_at = now_secs();
+                    fundings.insert(record.id, serde_json::to_string(&*record)?.as_str())?;
+                }
+            }
+
+            if let Some(in_flight) = previous.iter().find(|f| f.status == FundingStatus::Pending) {
+                FundingStart::InFlight(in_flight.clone())
+            } else {
+                let spent = capped_spend(&previous)?;
+                if cap.is_some_and(|cap| spent.saturating_add(funding.amount) > cap) {
+                    FundingStart::CapReached { spent }
                 } else {
-                    return Ok(());
+                    let id = match fundings.last()? {
+                        Some((id, _)) => id.value() + 1,
+                        None => 1,
+                    };
+                    let now = now_secs();
+                    let record = FundingRecord {
+                        id,
+                        chain_id: funding.chain_id,
+                        address: funding.address.clone(),
+                        amount: funding.amount.to_string(),
+                        reason: funding.reason,
+                        status: FundingStatus::Pending,
+                        tx_hash: None,
+                        error: None,
+                        created_at: now,
+                        updated_at: now,
+                    };
+                    fundings.insert(id, serde_json::to_string(&record)?.as_str())?;
+                    by_address.insert((funding.chain_id, address_key.as_str(), id), ())?;
+                    FundingStart::Started(record)
                 }
+            }
+        };
+        write_txn.commit()?;
+        Ok(start)
+    }
+
+    pub fn mark_funding_sent(&self, id: u64, tx_hash: &str) -> Result<FundingRecord> {
+        self.finish_funding(id, FundingStatus::Sent, Some(tx_hash), None)
+    }
+
+    pub fn mark_funding_failed(&self, id: u64, error: &str) -> Result<FundingRecord> {
+        self.finish_funding(id, FundingStatus::Failed, None, Some(error))
+    }
+
+    fn finish_funding(
+        &self,
+        id: u64,
+        status: FundingStatus,
+        tx_hash: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<FundingRecord> {
+        let write_txn = self.db.begin_write()?;
+        let record = {
+            let mut fundings = write_txn.open_table(FUNDINGS)?;
+            let mut record: FundingRecord = match fundings.get(id)? {
+                Some(record) => serde_json::from_str(record.value())?,
+                None => return Err(anyhow::anyhow!("Funding {} not found", id)),
             };
+            // An interrupted funding is settled when its sender learns the outcome after all
+            if !matches!(
+                record.status,
+                FundingStatus::Pending | FundingStatus::Interrupted
+            ) {
+                return Err(anyhow::anyhow!(
+                    "Funding {} is {}, not pending",
+                    id,
+                    record.status.as_str()
+                ));
+            }
+            record.status = status;
+            record.tx_hash = tx_hash.map(str::to_string);
+            record.error = error.map(str::to_string);
+            record.updated_at = now_secs();
+            fundings.insert(id, serde_json::to_string(&record)?.as_str())?;
+            record
+        };
+        write_txn.commit()?;
+        Ok(record)
+    }
 
-            deposits.insert(
-                key,
-                (
-                    account_id.as_str(),
-                    amount.as_str(),
-                    token_address.as_str(),
-                    token_symbol.as_str(),
-                    "swept",
-                ),
-            )?;
+    /// Mark the fundings of `chain_id` pending since before `started_before` as
+    /// interrupted: their process likely stopped before learning the outcome. They keep
+    /// counting against the lifetime cap, as the transfer may have been sent.
+    pub fn interrupt_pending_fundings(&self, chain_id: u64, started_before: u64) -> Result<usize> {
+        let write_txn = self.db.begin_write()?;
+        let count = {
+            let mut fundings = write_txn.open_table(FUNDINGS)?;
+            let mut interrupted = Vec::new();
+            for entry in fundings.iter()? {
+                let (_, record) = entry?;
+                let record: FundingRecord = serde_json::from_str(record.value())?;
+                if record.chain_id == chain_id
+                    && record.status == FundingStatus::Pending
+                    && record.created_at < started_before
+                {
+                    interrupted.push(record);
+                }
+            }
+            for mut record in interrupted.iter().cloned() {
+                record.status = FundingStatus::Interrupted;
+                record.updated_at = now_secs();
+                fundings.insert(record.id, serde_json::to_string(&record)?.as_str())?;
+            }
+            interrupted.len()
+        };
+        write_txn.commit()?;
+        Ok(count)
+    }
+
+    /// Fundings matching `filter`, oldest first
+    pub fn list_fundings(&self, filter: &FundingFilter) -> Result<Vec<FundingRecord>> {
+        let read_txn = self.db.begin_read()?;
+        let fundings = read_txn.open_table(FUNDINGS)?;
+        let mut result = Vec::new();
+        for entry in fundings.iter()? {
+            let (_, record) = entry?;
+            let record: FundingRecord = serde_json::from_str(record.value())?;
+            if filter.matches(&record) {
+                result.push(record);
+            }
+        }
+        Ok(result)
+    }
+
+    // ========== Funding Queue ==========
+
+    /// Store a funding waiting in the queue of `chain_id`
+    pub fn queue_funding(
+        &self,
+        chain_id: u64,
+        account_id: &str,
+        address: &str,
+        reason: FundingReason,
+    ) -> Result<QueuedFunding> {
+        let write_txn = self.db.begin_write()?;
+        let queued = {
+            let mut queue = write_txn.open_table(FUNDING_QUEUE)?;
+            let id = match queue.last()? {
+                Some((id, _)) => id.value() + 1,
+                None => 1,
+            };
+            let queued = QueuedFunding {
+                id,
+                chain_id,
+                account_id: account_id.to_string(),
+                address: address.to_string(),
+                reason,
+                queued_at: now_secs(),
+            };
+            queue.insert(id, serde_json::to_string(&queued)?.as_str())?;
+            queued
+        };
+        write_txn.commit()?;
+        Ok(queued)
+    }
+
+    /// Remove a queued funding to send it. Returns false if it was taken already.
+    pub fn take_queued_funding(&self, id: u64) -> Result<bool> {
+        let write_txn = self.db.begin_write()?;
+        let taken = write_txn.open_table(FUNDING_QUEUE)?.remove(id)?.is_some();
+        write_txn.commit()?;
+        Ok(taken)
+    }
+
+    /// Fundings still queued on `chain_id`, oldest first
+    pub fn list_queued_fundings(&self, chain_id: u64) -> Result<Vec<QueuedFunding>> {
+        let read_txn = self.db.begin_read()?;
+        let queue = read_txn.open_table(FUNDING_QUEUE)?;
+        let mut result = Vec::new();
+        for entry in queue.iter()? {
+            let (_, queued) = entry?;
+            let queued: QueuedFunding = serde_json::from_str(queued.value())?;
+            if queued.chain_id == chain_id {
+                result.push(queued);
+            }
+        }
+        Ok(result)
+    }
+
+    // ========== Dust Reclaims ==========
+
+    /// Record `reclaim` as pending before sending it
+    pub fn begin_dust_reclaim(&self, reclaim: &NewDustReclaim) -> Result<DustReclaimRecord> {
+        self.insert_dust_reclaim(reclaim, DustReclaimStatus::Pending, None)
+    }
+
+    /// Record that `reclaim` was not worth sending, and why
+    pub fn skip_dust_reclaim(
+        &self,
+        reclaim: &NewDustReclaim,
+        reason: &str,
+    ) -> Result<DustReclaimRecord> {
+        self.insert_dust_reclaim(reclaim, DustReclaimStatus::Skipped, Some(reason))
+    }
+
+    fn insert_dust_reclaim(
+        &self,
+        reclaim: &NewDustReclaim,
+        status: DustReclaimStatus,
+        error: Option<&str>,
+    ) -> Result<DustReclaimRecord> {
+        let write_txn = self.db.begin_write()?;
+        let record = {
+            let mut reclaims = write_txn.open_table(DUST_RECLAIMS)?;
+            let id = match reclaims.last()? {
+                Some((id, _)) => id.value() + 1,
+                None => 1,
+            };
+            let now = now_secs();
+            let record = DustReclaimRecord {
+                id,
+                chain_id: reclaim.chain_id,
+                address: reclaim.address.clone(),
+                destination: reclaim.destination.clone(),
+                amount: reclaim.amount.to_string(),
+                status,
+                tx_hash: None,
+                error: error.map(str::to_string),
+                created_at: now,
+                updated_at: now,
+            };
+            reclaims.insert(id, serde_json::to_string(&record)?.as_str())?;
+            record
+        };
+        write_txn.commit()?;
+        Ok(record)
+    }
+
+    pub fn mark_dust_reclaim_sent(&self, id: u64, tx_hash: &str) -> Result<DustReclaimRecord> {
+        self.finish_dust_reclaim(id, DustReclaimStatus::Sent, Some(tx_hash), None)
+    }
+
+    pub fn mark_dust_reclaim_failed(&self, id: u64, error: &str) -> Result<DustReclaimRecord> {
+        self.finish_dust_reclaim(id, DustReclaimStatus::Failed, None, Some(error))
+    }
+
+    fn finish_dust_reclaim(
+        &self,
+        id: u64,
+        status: DustReclaimStatus,
+        tx_hash: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<DustReclaimRecord> {
+        let write_txn = self.db.begin_write()?;
+        let record = {
+            let mut reclaims = write_txn.open_table(DUST_RECLAIMS)?;
+            let mut record: DustReclaimRecord = match reclaims.get(id)? {
+                Some(record) => serde_json::from_str(record.value())?,
+                None => return Err(anyhow::anyhow!("Dust reclaim {} not found", id)),
+            };
+            // An interrupted reclaim is settled when its sender learns the outcome after all
+            if !matches!(
+                record.status,
+                DustReclaimStatus::Pending | DustReclaimStatus::Interrupted
+            ) {
+                return Err(anyhow::anyhow!(
+                    "Dust reclaim {} is {}, not pending",
+                    id,
+                    record.status.as_str()
+                ));
+            }
+            record.status = status;
+            record.tx_hash = tx_hash.map(str::to_string);
+            record.error = error.map(str::to_string);
+            record.updated_at = now_secs();
+            reclaims.insert(id, serde_json::to_string(&record)?.as_str())?;
+            record
+        };
+        write_txn.commit()?;
+        Ok(record)
+    }
+
+    /// Mark the dust reclaims of `chain_id` pending since before `started_before` as
+    /// interrupted: their process likely stopped before learning the outcome
+    pub fn interrupt_pending_dust_reclaims(
+        &self,
+        chain_id: u64,
+        started_before: u64,
+    ) -> Result<usize> {
+        let write_txn = self.db.begin_write()?;
+        let count = {
+            let mut reclaims = write_txn.open_table(DUST_RECLAIMS)?;
+            let mut interrupted = Vec::new();
+            for entry in reclaims.iter()? {
+                let (_, record) = entry?;
+                let record: DustReclaimRecord = serde_json::from_str(record.value())?;
+                if record.chain_id == chain_id
+                    && record.status == DustReclaimStatus::Pending
+                    && record.created_at < started_before
+                {
+                    interrupted.push(record);
+                }
+            }
+            for mut record in interrupted.iter().cloned() {
+                record.status = DustReclaimStatus::Interrupted;
+                record.updated_at = now_secs();
+                reclaims.insert(record.id, serde_json::to_string(&record)?.as_str())?;
+            }
+            interrupted.len()
+        };
+        write_txn.commit()?;
+        Ok(count)
+    }
+
+    /// Dust reclaims on `chain_id`, or on every chain, oldest first
+    pub fn list_dust_reclaims(&self, chain_id: Option<u64>) -> Result<Vec<DustReclaimRecord>> {
+        let read_txn = self.db.begin_read()?;
+        let reclaims = read_txn.open_table(DUST_RECLAIMS)?;
+        let mut result = Vec::new();
+        for entry in reclaims.iter()? {
+            let (_, record) = entry?;
+            let record: DustReclaimRecord = serde_json::from_str(record.value())?;
+            if chain_id.is_none_or(|chain_id| record.chain_id == chain_id) {
+                result.push(record);
+            }
+        }
+        Ok(result)
+    }
+
+    // ========== Settings Audit Log ==========
+
+    pub fn record_setting_changes(&self, changes: &[SettingChange]) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut audit = write_txn.open_table(SETTINGS_AUDIT)?;
+            let next = match audit.last()? {
+                Some((seq, _)) => seq.value() + 1,
+                None => 0,
+            };
+            for (seq, change) in (next..).zip(changes) {
+                audit.insert(seq, serde_json::to_string(change)?.as_str())?;
+            }
         }
         write_txn.commit()?;
         Ok(())
     }
+
+    pub fn list_setting_changes(&self, limit: usize) -> Result<Vec<SettingChange>> {
+        let read_txn = self.db.begin_read()?;
+        let audit = read_txn.open_table(SETTINGS_AUDIT)?;
+        let mut changes = Vec::new();
+        for entry in audit.iter()?.rev().take(limit) {
+            let (_, change) = entry?;
+            changes.push(serde_json::from_str(change.value())?);
+        }
+        Ok(changes)
+    }
+
+    // ========== Deposit Queries ==========
+
+    /// Get all deposits (native and ERC20) belonging to a transaction on `chain_id`
+    pub fn get_deposits_by_tx_hash(&self, chain_id: u64, tx_hash: &str) -> Result<Vec<Deposit>> {
+        let read_txn = self.db.begin_read()?;
+        let mut results = Vec::new();
+
+        let native_key = Deposit::key_for(chain_id, tx_hash, None);
+        let deposits = read_txn.open_table(DEPOSITS)?;
+        if let Some(v) = deposits.get(native_key.as_str())? {
+            results.push(v.value());
+        }
+
+        // ERC20 keys are chain_id:tx_hash:log_index, so they sort right after the
+        // "chain_id:tx_hash:" prefix
+        let erc20_deposits = read_txn.open_table(ERC20_DEPOSITS)?;
+        let prefix = format!("{}:", native_key);
+        for item in erc20_deposits.range::<&str>(prefix.as_str()..)? {
+            let (key, value) = item?;
+            if !key.value().starts_with(&prefix) {
+                break;
+            }
+            results.push(value.value());
+        }
+
+        Ok(results)
+    }
+
+    /// List deposits matching `filter`.
+    ///
+    /// Account queries walk the account index in block order and status queries walk the
+    /// status index; anything else scans native deposits first, then ERC20 deposits.
+    /// The cursor is opaque to callers: it is the `next_cursor` of the previous page.
+    pub fn list_deposits(
+        &self,
+        filter: &DepositFilter,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<DepositPage> {
+        let read_txn = self.db.begin_read()?;
+        let native = read_txn.open_table(DEPOSITS)?;
+        let erc20 = read_txn.open_table(ERC20_DEPOSITS)?;
+
+        let load = |kind: &str, key: &str| -> Result<Option<Deposit>> {
+            let table = if kind == ERC20 { &erc20 } else { &native };
+            Ok(table.get(key)?.map(|v| v.value()))
+        };
+
+        let mut deposits: Vec<Deposit> = Vec::new();
+        let mut next_cursor = None;
+
+        if let Some(ref account_id) = filter.account_id {
+            // Cursor: block_number:deposit_key
+            let start = match cursor {
+                Some(c) => {
+                    let (block, key) = c
+                        .split_once(':')
+                        .and_then(|(b, k)| Some((b.parse::<u64>().ok()?, k)))
+                        .ok_or_else(|| anyhow::anyhow!("Invalid cursor"))?;
+                    Bound::Excluded((account_id.as_str(), block, key))
+                }
+                None => Bound::Included((account_id.as_str(), 0, "")),
+            };
+
+            let index = read_txn.open_table(DEPOSITS_BY_ACCOUNT)?;
+            for item in index.range::<(&str, u64, &str)>((start, Bound::Unbounded))? {
+                let (index_key, kind) = item?;
+                let (indexed_account, _block, key) = index_key.value();
+                if indexed_account != account_id {
+                    break;
+                }
+                let Some(deposit) = load(kind.value(), key)? else {
+                    continue;
+                };
+                if !filter.matches(&deposit) {
+                    continue;
+                }
+                if deposits.len() == limit {
+                    next_cursor = deposits
+                        .last()
+                        .map(|d| format!("{}:{}", d.block_number, d.key));
+                    break;
+                }
+                deposits.push(deposit);
+            }
+        } else if let Some(ref status) = filter.status {
+            // Cursor: deposit_key, which starts with its chain
+            let status = DepositStatus::from_str(status)?;
+            let start = match cursor {
+                Some(key) => {
+                    let chain_id =
+                        chain_of_key(key).ok_or_else(|| anyhow::anyhow!("Invalid cursor"))?;
+                    Bound::Excluded((status.as_str(), chain_id, key))
+                }
+                None => Bound::Included((status.as_str(), filter.chain_id.unwrap_or(0), "")),
+            };
+
+            let index = read_txn.open_table(DEPOSITS_BY_STATUS)?;
+            for item in index.range::<(&str, u64, &str)>((start, Bound::Unbounded))? {
+                let (index_key, kind) = item?;
+                let (indexed_status, chain_id, key) = index_key.value();
+                if indexed_status != status.as_str()
+                    || filter.chain_id.is_some_and(|c| c != chain_id)
+                {
+                    break;
+                }
+                let Some(deposit) = load(kind.value(), key)? else {
+                    continue;
+                };
+                if !filter.matches(&deposit) {
+                    continue;
+                }
+                if deposits.len() == limit {
+                    next_cursor = deposits.last().map(|d| d.key.clone());
+                    break;
+                }
+                deposits.push(deposit);
+            }
+        } else {
+            // Cursor: kind:deposit_key
+            let (cursor_kind, cursor_key) = match cursor {
+                Some(c) => {
+                    let (kind, key) = c
+                        .split_once(':')
+                        .filter(|(kind, _)| *kind == NATIVE || *kind == ERC20)
+                        .ok_or_else(|| anyhow::anyhow!("Invalid cursor"))?;
+                    (Some(kind), Some(key))
+                }
+                None => (None, None),
+            };
+
+            // Native deposits are skipped entirely once the cursor points into the ERC20 table
+            let mut has_more = false;
+            for kind in [NATIVE, ERC20] {
+                if has_more || (kind == NATIVE && cursor_kind == Some(ERC20)) {
+                    continue;
+                }
+                let start = match cursor_key {
+                    Some(key) if cursor_kind == Some(kind) => Bound::Excluded(key),
+                    _ => Bound::Unbounded,
+                };
+                let table = if kind == ERC20 { &erc20 } else { &native };
+                for item in table.range::<&str>((start, Bound::Unbounded))? {
+                    let deposit = item?.1.value();
+                    if filter.matches(&deposit) {
+                        if deposits.len() == limit {
+                            has_more = true;
+                            break;
+                        }
+                        deposits.push(deposit);
+                    }
+                }
+            }
+
+            if has_more {
+                next_cursor = deposits.last().map(|d| format!("{}:{}", kind_of(d), d.key));
+            }
+        }
+
+        Ok(DepositPage {
+            deposits,
+            next_cursor,
+        })
+    }
+    // ========== Backup ==========
+
+    /// Write every record the database holds as JSON lines from one read transaction, so the snapshot is consistent while the service keeps writing
+    pub fn export(&self, out: &mut (dyn Write + Send)) -> Result<ExportSummary> {
+        let read_txn = self.db.begin_read()?;
+        let mut writer = ExportWriter::new(out)?;
+
+        let statuses = read_txn.open_table(ACCOUNT_STATUS)?;
+        let metadata = read_txn.open_table(ACCOUNT_METADATA)?;
+        let chains = read_txn.open_table(ACCOUNT_CHAINS)?;
+        for item in read_txn.open_table(ACCOUNTS)?.iter()? {
+            let (id, value) = item?;
+            let id = id.value();
+            let (derivation_index, address, webhook_url) = value.value();
+            let (status, status_updated_at) = match statuses.get(id)? {
+                Some(v) => {
+                    let (status, updated_at) = v.value();
+                    (status.to_string(), Some(updated_at))
+                }
+                None => (AccountStatus::Active.as_str().to_string(), None),
+            };
+            let metadata = metadata
+                .get(id)?
+                .map(|v| serde_json::from_str(v.value()))
+                .transpose()?;
+
+            writer.write(&ExportRecord::Account(AccountRecord {
+                id: id.to_string(),
+                derivation_index,
+                address: address.to_string(),
+                webhook_url: webhook_url.to_string(),
+                status,
+                status_updated_at,
+                metadata,
+                chains: account_chains(&chains, id)?,
+            }))?;
+        }
+
+        for definition in [DEPOSITS, ERC20_DEPOSITS] {
+            for item in read_txn.open_table(definition)?.iter()? {
+                writer.write(&ExportRecord::Deposit(item?.1.value()))?;
+            }
+        }
+
+        for item in read_txn.open_table(TOKEN_METADATA)?.iter()? {
+            let (address, value) = item?;
+            let (symbol, decimals, name) = value.value();
+            writer.write(&ExportRecord::TokenMetadata {
+                address: address.value().to_string(),
+                symbol: symbol.to_string(),
+                decimals: decimals as u8,
+                name: name.to_string(),
+            })?;
+        }
+
+        // The schema version belongs to the database, not to the exported data
+        for item in read_txn.open_table(STATE)?.iter()? {
+            let (key, value) = item?;
+            if key.value() == SCHEMA_VERSION_KEY {
+                continue;
+            }
+            writer.write(&ExportRecord::State {
+                key: key.value().to_string(),
+                value: value.value().to_string(),
+            })?;
+        }
+
+        for item in read_txn.open_table(FUNDINGS)?.iter()? {
+            writer.write(&ExportRecord::Funding(serde_json::from_str(
+                item?.1.value(),
+            )?))?;
+        }
+        for item in read_txn.open_table(API_KEYS)?.iter()? {
+            writer.write(&ExportRecord::ApiKey(serde_json::from_str(
+                item?.1.value(),
+            )?))?;
+        }
+        for item in read_txn.open_table(SETTINGS_AUDIT)?.iter()? {
+            writer.write(&ExportRecord::SettingChange(serde_json::from_str(
+                item?.1.value(),
+            )?))?;
+        }
+        for item in read_txn.open_table(IDEMPOTENCY_KEYS)?.iter()? {
+            writer.write(&ExportRecord::IdempotencyKey(serde_json::from_str(
+                item?.1.value(),
+            )?))?;
+        }
+        for item in read_txn.open_table(DUST_RECLAIMS)?.iter()? {
+            writer.write(&ExportRecord::DustReclaim(serde_json::from_str(
+                item?.1.value(),
+            )?))?;
+        }
+        for item in read_txn.open_table(FUNDING_QUEUE)?.iter()? {
+            writer.write(&ExportRecord::QueuedFunding(serde_json::from_str(
+                item?.1.value(),
+            )?))?;
+        }
+
+        writer.finish()
+    }
+
+    /// Load exported records in one write transaction.
+    /// Fails without writing anything if the database already holds accounts, deposits, or
+    /// fundings.
+    pub fn import(&self, records: &[ExportRecord]) -> Result<ExportSummary> {
+        let write_txn = self.db.begin_write()?;
+        {
+            if !write_txn.open_table(ACCOUNTS)?.is_empty()?
+                || !write_txn.open_table(DEPOSITS)?.is_empty()?
+                || !write_txn.open_table(ERC20_DEPOSITS)?.is_empty()?
+                || !write_txn.open_table(FUNDINGS)?.is_empty()?
+            {
+                return Err(anyhow::anyhow!(
+                    "Refusing to import into a database that already holds accounts, deposits, or fundings"
+                ));
+            }
+
+            let mut accounts = write_txn.open_table(ACCOUNTS)?;
+            let mut address_to_id = write_txn.open_table(ADDRESS_TO_ID)?;
+            let mut statuses = write_txn.open_table(ACCOUNT_STATUS)?;
+            let mut metadata = write_txn.open_table(ACCOUNT_METADATA)?;
+            let mut chains = write_txn.open_table(ACCOUNT_CHAINS)?;
+            let mut tokens = write_txn.open_table(TOKEN_METADATA)?;
+            let mut state = write_txn.open_table(STATE)?;
+            let mut fundings = write_txn.open_table(FUNDINGS)?;
+            let mut fundings_by_address = write_txn.open_table(FUNDINGS_BY_ADDRESS)?;
+            let mut api_keys = write_txn.open_table(API_KEYS)?;
+            let mut audit = write_txn.open_table(SETTINGS_AUDIT)?;
+            let mut next_change = match audit.last()? {
+                Some((seq, _)) => seq.value() + 1,
+                None => 0,
+            };
+            let mut idempotency = write_txn.open_table(IDEMPOTENCY_KEYS)?;
+            let mut idempotency_by_time = write_txn.open_table(IDEMPOTENCY_BY_TIME)?;
+            let mut reclaims = write_txn.open_table(DUST_RECLAIMS)?;
+            let mut queue = write_txn.open_table(FUNDING_QUEUE)?;
+            // Exports from before chains were tracked carry unassigned records; let the next
+            // start assign them unless the export says they already were
+            state.remove(LEGACY_CHAIN_KEY)?;
+
+            for record in records {
+                match record {
+                    ExportRecord::Header { .. } => {}
+                    ExportRecord::Account(account) => {
+                        let id = account.id.as_str();
+                        accounts.insert(
+                            id,
+                            (
+                                account.derivation_index,
+                                account.address.as_str(),
+                                account.webhook_url.as_str(),
+                            ),
+                        )?;
+                        address_to_id.insert(account.address.as_str(), id)?;
+                        for &chain_id in &account.chains {
+                            chains.insert((id, chain_id), account.address.as_str())?;
+                        }
+                        if let Some(updated_at) = account.status_updated_at {
+                            statuses.insert(id, (account.status.as_str(), updated_at))?;
+                        }
+                        if let Some(ref value) = account.metadata {
+                            metadata.insert(id, serde_json::to_string(value)?.as_str())?;
+                        }
+                    }
+                    ExportRecord::Deposit(deposit) => {
+                        // Exports written before deposits were keyed by chain carry older keys
+                        let mut deposit = deposit.clone();
+                        deposit.key =
+                            Deposit::key_for(deposit.chain_id, &deposit.tx_hash, deposit.log_index);
+                        let mut table = write_txn.open_table(deposit_table(kind_of(&deposit)))?;
+                        table.insert(deposit.key.as_str(), &deposit)?;
+                        index_deposit(&write_txn, &deposit)?;
+                    }
+                    ExportRecord::TokenMetadata {
+                        address,
+                        symbol,
+                        decimals,
+                        name,
+                    } => {
+                        tokens.insert(
+                            address.as_str(),
+                            (symbol.as_str(), *decimals as u64, name.as_str()),
+                        )?;
+                    }
+                    ExportRecord::State { key, value } => {
+                        if key != SCHEMA_VERSION_KEY {
+                            state.insert(key.as_str(), value.as_str())?;
+                        }
+                    }
+                    ExportRecord::Funding(funding) => {
+                        fundings.insert(funding.id, serde_json::to_string(funding)?.as_str())?;
+                        fundings_by_address.insert(
+                            (
+                                funding.chain_id,
+                                funding.address.to_ascii_lowercase().as_str(),
+                                funding.id,
+                            ),
+                            (),
+                        )?;
+                    }
+                    ExportRecord::ApiKey(key) => {
+                        api_keys.insert(key.id.as_str(), serde_json::to_string(key)?.as_str())?;
+                    }
+                    ExportRecord::SettingChange(change) => {
+                        audit.insert(next_change, serde_json::to_string(change)?.as_str())?;
+                        next_change += 1;
+                    }
+                    ExportRecord::IdempotencyKey(record) => {
+                        idempotency
+                            .insert(record.key.as_str(), serde_json::to_string(record)?.as_str())?;
+                        idempotency_by_time.insert((record.created_at, record.key.as_str()), ())?;
+                    }
+                    ExportRecord::DustReclaim(reclaim) => {
+                        reclaims.insert(reclaim.id, serde_json::to_string(reclaim)?.as_str())?;
+                    }
+                    ExportRecord::QueuedFunding(queued) => {
+                        queue.insert(queued.id, serde_json::to_string(queued)?.as_str())?;
+                    }
+                }
+            }
+        }
+        write_txn.commit()?;
+        Ok(summarize(records))
+    }
+}
+
+/// Add a new deposit to the status and account indexes
+fn index_deposit(txn: &WriteTransaction, deposit: &Deposit) -> Result<()> {
+    let kind = kind_of(deposit);
+    let key = deposit.key.as_str();
+    txn.open_table(DEPOSITS_BY_STATUS)?
+        .insert((deposit.status.as_str(), deposit.chain_id, key), kind)?;
+    txn.open_table(DEPOSITS_BY_ACCOUNT)?.insert(
+        (deposit.account_id.as_str(), deposit.block_number, key),
+        kind,
+    )?;
+    Ok(())
+}
+
+/// Move a deposit from its previous status bucket of the status index to its current one
+fn reindex_deposit_status(
+    txn: &WriteTransaction,
+    deposit: &Deposit,
+    previous: DepositStatus,
+) -> Result<()> {
+    let key = deposit.key.as_str();
+    let mut by_status = txn.open_table(DEPOSITS_BY_STATUS)?;
+    by_status.remove((previous.as_str(), deposit.chain_id, key))?;
+    by_status.insert(
+        (deposit.status.as_str(), deposit.chain_id, key),
+        kind_of(deposit),
+    )?;
+    Ok(())
+}
+
+/// Chain ids an account is watched on, read from the account chain table
+fn account_chains(
+    table: &impl ReadableTable<(&'static str, u64), &'static str>,
+    id: &str,
+) -> Result<Vec<u64>> {
+    let mut chain_ids = Vec::new();
+    for item in table.range::<(&str, u64)>((id, 0)..=(id, u64::MAX))? {
+        chain_ids.push(item?.0.value().1);
+    }
+    Ok(chain_ids)
+}
+
+/// Keys of all deposits of the given kind on `chain_id` with the given status, read from
+/// the status index
+fn keys_with_status(
+    txn: &ReadTransaction,
+    chain_id: u64,
+    status: DepositStatus,
+    kind: &str,
+) -> Result<Vec<String>> {
+    let index = txn.open_table(DEPOSITS_BY_STATUS)?;
+    let mut keys = Vec::new();
+    for item in index.range::<(&str, u64, &str)>((status.as_str(), chain_id, "")..)? {
+        let (index_key, indexed_kind) = item?;
+        let (indexed_status, indexed_chain, key) = index_key.value();
+        if indexed_status != status.as_str() || indexed_chain != chain_id {
+            break;
+        }
+        if indexed_kind.value() == kind {
+            keys.push(key.to_string());
+        }
+    }
+    Ok(keys)
+}
+
+/// Store `deposit` under the key its chain and transaction give it, moving it and its
+/// index entries, indexed on `indexed_chain`, from the key it is stored under if that differs
+fn rekey_deposit(txn: &WriteTransaction, deposit: &mut Deposit, indexed_chain: u64) -> Result<()> {
+    let key = Deposit::key_for(deposit.chain_id, &deposit.tx_hash, deposit.log_index);
+    let kind = kind_of(deposit);
+    let mut table = txn.open_table(deposit_table(kind))?;
+    if key == deposit.key {
+        table.insert(key.as_str(), &*deposit)?;
+        return Ok(());
+    }
+
+    table.remove(deposit.key.as_str())?;
+    txn.open_table(DEPOSITS_BY_STATUS)?.remove((
+        deposit.status.as_str(),
+        indexed_chain,
+        deposit.key.as_str(),
+    ))?;
+    txn.open_table(DEPOSITS_BY_ACCOUNT)?.remove((
+        deposit.account_id.as_str(),
+        deposit.block_number,
+        deposit.key.as_str(),
+    ))?;
+    deposit.key = key;
+    table.insert(deposit.key.as_str(), &*deposit)?;
+    index_deposit(txn, deposit)?;
+    Ok(())
+}
+
+/// Prefix deposit keys with their chain id, so a transaction replayed on another chain is
+/// no longer taken for a duplicate. Deposits not yet assigned to a chain are keyed by chain
+/// 0 until they are.
+fn key_deposits_by_chain(txn: &WriteTransaction) -> Result<()> {
+    let mut deposits = Vec::new();
+    for definition in [DEPOSITS, ERC20_DEPOSITS] {
+        for item in txn.open_table(definition)?.iter()? {
+            deposits.push(item?.1.value());
+        }
+    }
+    for deposit in &mut deposits {
+        let chain_id = deposit.chain_id;
+        rekey_deposit(txn, deposit, chain_id)?;
+    }
+    Ok(())
+}
+
+/// Build the secondary indexes for databases created before they existed
+fn backfill_deposit_indexes(txn: &WriteTransaction) -> Result<()> {
+    let _ = txn.open_table(DEPOSITS_BY_ACCOUNT)?;
+    if !txn.open_table(DEPOSITS_BY_STATUS)?.is_empty()? {
+        return Ok(());
+    }
+
+    let mut deposits = Vec::new();
+    for definition in [DEPOSITS, ERC20_DEPOSITS] {
+        for item in txn.open_table(definition)?.iter()? {
+            deposits.push(item?.1.value());
+        }
+    }
+
+    for deposit in &deposits {
+        index_deposit(txn, deposit)?;
+    }
+    Ok(())
+}
+
+/// Whether `name` exists but was written with a different value layout than `definition`
+fn has_legacy_layout(
+    txn: &WriteTransaction,
+    name: &str,
+    definition: TableDefinition<&str, Deposit>,
+) -> Result<bool> {
+    if !txn.list_tables()?.any(|table| table.name() == name) {
+        return Ok(false);
+    }
+    match txn.open_table(definition) {
+        Ok(_) => Ok(false),
+        Err(TableError::TableTypeMismatch { .. }) => Ok(true),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// A typed record for a deposit stored before sender and block were tracked
+fn legacy_deposit(
+    key: &str,
+    account_id: &str,
+    amount: &str,
+    token: Option<(&str, &str)>,
+    status: &str,
+) -> Deposit {
+    let (tx_hash, log_index) = match key.split_once(':') {
+        Some((tx_hash, log_index)) => (tx_hash, log_index.parse().ok()),
+        None => (key, None),
+    };
+    let status = DepositStatus::from_str(status).unwrap_or(DepositStatus::Detected);
+
+    Deposit {
+        key: key.to_string(),
+        chain_id: 0,
+        tx_hash: tx_hash.to_string(),
+        log_index,
+        account_id: account_id.to_string(),
+        from_address: None,
+        amount: amount.to_string(),
+        token_address: token.map(|(address, _)| address.to_string()),
+        token_symbol: token.map(|(_, symbol)| symbol.to_string()),
+        block_number: 0,
+        block_hash: None,
+        status,
+        history: vec![StatusChange {
+            status,
+            at: now_secs(),
+            note: Some("migrated from legacy record".to_string()),
+        }],
+        sweep_tx_hash: None,
+        last_error: None,
+    }
+}
+
+/// Convert deposit tables stored as positional string tuples into typed Deposit records.
+/// Sender, block number, and block hash were not recorded, so they are left unknown.
+fn upgrade_legacy_deposit_tables(txn: &WriteTransaction) -> Result<()> {
+    if has_legacy_layout(txn, "deposits", DEPOSITS)? {
+        let mut deposits = Vec::new();
+        for item in txn.open_table(LEGACY_DEPOSITS)?.iter()? {
+            let (key, value) = item?;
+            let (account_id, amount, status) = value.value();
+            deposits.push(legacy_deposit(
+                key.value(),
+                account_id,
+                amount,
+                None,
+                status,
+            ));
+        }
+        txn.delete_table(LEGACY_DEPOSITS)?;
+
+        let mut table = txn.open_table(DEPOSITS)?;
+        for deposit in &deposits {
+            table.insert(deposit.key.as_str(), deposit)?;
+        }
+    }
+
+    if has_legacy_layout(txn, "erc20_deposits", ERC20_DEPOSITS)? {
+        let mut deposits = Vec::new();
+        for item in txn.open_table(LEGACY_ERC20_DEPOSITS)?.iter()? {
+            let (key, value) = item?;
+            let (account_id, amount, token_address, token_symbol, status) = value.value();
+            deposits.push(legacy_deposit(
+                key.value(),
+                account_id,
+                amount,
+                Some((token_address, token_symbol)),
+                status,
+            ));
+        }
+        txn.delete_table(LEGACY_ERC20_DEPOSITS)?;
+
+        let mut table = txn.open_table(ERC20_DEPOSITS)?;
+        for deposit in &deposits {
+            table.insert(deposit.key.as_str(), deposit)?;
+        }
+    }
+
+    let _ = txn.open_table(DEPOSITS)?;
+    let _ = txn.open_table(ERC20_DEPOSITS)?;
+    Ok(())
+}
+
+// redb transactions are short and local, so the trait methods call the blocking ones directly
+#[async_trait]
+impl Storage for Db {
+    async fn register_accounts(&self, accounts: &[NewAccount]) -> Result<Vec<Registration>> {
+        Db::register_accounts(self, accounts)
+    }
+
+    async fn get_account_chains(&self, id: &str) -> Result<Vec<u64>> {
+        Db::get_account_chains(self, id)
+    }
+
+    async fn get_registration_id_by_address(
+        &self,
+        chain_id: u64,
+        address: &str,
+    ) -> Result<Option<String>> {
+        Db::get_registration_id_by_address(self, chain_id, address)
+    }
+
+    async fn get_account_by_id(&self, id: &str) -> Result<Option<(u32, String, String)>> {
+        Db::get_account_by_id(self, id)
+    }
+
+    async fn get_webhook_url(&self, account_id: &str) -> Result<Option<String>> {
+        Db::get_webhook_url(self, account_id)
+    }
+
+    async fn update_account(
+        &self,
+        id: &str,
+        webhook_url: Option<&str>,
+        metadata: Option<&str>,
+    ) -> Result<bool> {
+        Db::update_account(self, id, webhook_url, metadata)
+    }
+
+    async fn deactivate_account(&self, id: &str) -> Result<bool> {
+        Db::deactivate_account(self, id)
+    }
+
+    async fn get_account_status(&self, id: &str) -> Result<AccountStatus> {
+        Db::get_account_status(self, id)
+    }
+
+    async fn get_account_metadata(&self, id: &str) -> Result<Option<String>> {
+        Db::get_account_metadata(self, id)
+    }
+
+    async fn record_deposit(&self, deposit: &Deposit) -> Result<bool> {
+        Db::record_deposit(self, deposit)
+    }
+
+    async fn get_deposit(&self, key: &str) -> Result<Option<Deposit>> {
+        Db::get_deposit(self, key)
+    }
+
+    async fn mark_deposit_sweeping(&self, key: &str) -> Result<Option<Deposit>> {
+        Db::mark_deposit_sweeping(self, key)
+    }
+
+    async fn mark_deposit_swept(&self, key: &str, sweep_tx_hash: &str) -> Result<Deposit> {
+        Db::mark_deposit_swept(self, key, sweep_tx_hash)
+    }
+
+    async fn mark_deposit_failed(
+        &self,
+        key: &str,
+        sweep_tx_hash: &str,
+        error: &str,
+    ) -> Result<Deposit> {
+        Db::mark_deposit_failed(self, key, sweep_tx_hash, error)
+    }
+
+    async fn release_deposit(&self, key: &str, error: &str) -> Result<Deposit> {
+        Db::release_deposit(self, key, error)
+    }
+
+    async fn quarantine_deposit(&self, key: &str, reason: &str) -> Result<Deposit> {
+        Db::quarantine_deposit(self, key, reason)
+    }
+
+    async fn requeue_interrupted_sweeps(
+        &self,
+        chain_id: u64,
+        started_before: u64,
+    ) -> Result<usize> {
+        Db::requeue_interrupted_sweeps(self, chain_id, started_before)
+    }
+
+    async fn get_deposits_by_status(
+        &self,
+        chain_id: u64,
+        status: DepositStatus,
+    ) -> Result<Vec<Deposit>> {
+        Db::get_deposits_by_status(self, chain_id, status)
+    }
+
+    async fn get_detected_deposits(&self, chain_id: u64) -> Result<Vec<Deposit>> {
+        Db::get_detected_deposits(self, chain_id)
+    }
+
+    async fn get_detected_erc20_deposits(&self, chain_id: u64) -> Result<Vec<Deposit>> {
+        Db::get_detected_erc20_deposits(self, chain_id)
+    }
+
+    async fn get_deposits_by_tx_hash(&self, chain_id: u64, tx_hash: &str) -> Result<Vec<Deposit>> {
+        Db::get_deposits_by_tx_hash(self, chain_id, tx_hash)
+    }
+
+    async fn list_deposits(
+        &self,
+        filter: &DepositFilter,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<DepositPage> {
+        Db::list_deposits(self, filter, cursor, limit)
+    }
+
+    async fn get_last_processed_block(&self, chain_id: u64) -> Result<u64> {
+        Db::get_last_processed_block(self, chain_id)
+    }
+
+    async fn set_last_processed_block(&self, chain_id: u64, block: u64) -> Result<()> {
+        Db::set_last_processed_block(self, chain_id, block)
+    }
+
+    async fn assign_legacy_chain(&self, chain_id: u64) -> Result<usize> {
+        Db::assign_legacy_chain(self, chain_id)
+    }
+
+    async fn store_token_metadata(
+        &self,
+        address: &str,
+        symbol: &str,
+        decimals: u8,
+        name: &str,
+    ) -> Result<()> {
+        Db::store_token_metadata(self, address, symbol, decimals, name)
+    }
+
+    async fn get_token_metadata(&self, address: &str) -> Result<Option<(String, u8, String)>> {
+        Db::get_token_metadata(self, address)
+    }
+
+    async fn create_api_key(&self, key: &ApiKey) -> Result<()> {
+        Db::create_api_key(self, key)
+    }
+
+    async fn get_api_key(&self, id: &str) -> Result<Option<ApiKey>> {
+        Db::get_api_key(self, id)
+    }
+
+    async fn list_api_keys(&self) -> Result<Vec<ApiKey>> {
+        Db::list_api_keys(self)
+    }
+
+    async fn revoke_api_key(&self, id: &str) -> Result<bool> {
+        Db::revoke_api_key(self, id)
+    }
+
+    async fn get_idempotency_record(&self, key: &str) -> Result<Option<IdempotencyRecord>> {
+        Db::get_idempotency_record(self, key)
+    }
+
+    async fn save_idempotency_record(
+        &self,
+        record: &IdempotencyRecord,
+        expired_before: u64,
+    ) -> Result<IdempotencyRecord> {
+        Db::save_idempotency_record(self, record, expired_before)
+    }
+
+    async fn begin_funding(&self, funding: &NewFunding, cap: Option<U256>) -> Result<FundingStart> {
+        Db::begin_funding(self, funding, cap)
+    }
+
+    async fn mark_funding_sent(&self, id: u64, tx_hash: &str) -> Result<FundingRecord> {
+        Db::mark_funding_sent(self, id, tx_hash)
+    }
+
+    async fn mark_funding_failed(&self, id: u64, error: &str) -> Result<FundingRecord> {
+        Db::mark_funding_failed(self, id, error)
+    }
+
+    async fn interrupt_pending_fundings(
+        &self,
+        chain_id: u64,
+        started_before: u64,
+    ) -> Result<usize> {
+        Db::interrupt_pending_fundings(self, chain_id, started_before)
+    }
+
+    async fn list_fundings(&self, filter: &FundingFilter) -> Result<Vec<FundingRecord>> {
+        Db::list_fundings(self, filter)
+    }
+
+    async fn queue_funding(
+        &self,
+        chain_id: u64,
+        account_id: &str,
+        address: &str,
+        reason: FundingReason,
+    ) -> Result<QueuedFunding> {
+        Db::queue_funding(self, chain_id, account_id, address, reason)
+    }
+
+    async fn take_queued_funding(&self, id: u64) -> Result<bool> {
+        Db::take_queued_funding(self, id)
+    }
+
+    async fn list_queued_fundings(&self, chain_id: u64) -> Result<Vec<QueuedFunding>> {
+        Db::list_queued_fundings(self, chain_id)
+    }
+
+    async fn begin_dust_reclaim(&self, reclaim: &NewDustReclaim) -> Result<DustReclaimRecord> {
+        Db::begin_dust_reclaim(self, reclaim)
+    }
+
+    async fn skip_dust_reclaim(
+        &self,
+        reclaim: &NewDustReclaim,
+        reason: &str,
+    ) -> Result<DustReclaimRecord> {
+        Db::skip_dust_reclaim(self, reclaim, reason)
+    }
+
+    async fn mark_dust_reclaim_sent(&self, id: u64, tx_hash: &str) -> Result<DustReclaimRecord> {
+        Db::mark_dust_reclaim_sent(self, id, tx_hash)
+    }
+
+    async fn mark_dust_reclaim_failed(&self, id: u64, error: &str) -> Result<DustReclaimRecord> {
+        Db::mark_dust_reclaim_failed(self, id, error)
+    }
+
+    async fn interrupt_pending_dust_reclaims(
+        &self,
+        chain_id: u64,
+        started_before: u64,
+    ) -> Result<usize> {
+        Db::interrupt_pending_dust_reclaims(self, chain_id, started_before)
+    }
+
+    async fn list_dust_reclaims(&self, chain_id: Option<u64>) -> Result<Vec<DustReclaimRecord>> {
+        Db::list_dust_reclaims(self, chain_id)
+    }
+
+    async fn record_setting_changes(&self, changes: &[SettingChange]) -> Result<()> {
+        Db::record_setting_changes(self, changes)
+    }
+
+    async fn list_setting_changes(&self, limit: usize) -> Result<Vec<SettingChange>> {
+        Db::list_setting_changes(self, limit)
+    }
+
+    async fn export(&self, out: &mut (dyn Write + Send)) -> Result<ExportSummary> {
+        Db::export(self, out)
+    }
+
+    async fn import(&self, records: &[ExportRecord]) -> Result<ExportSummary> {
+        Db::import(self, records)
+    }
+}
+
+// ========== Migrations ==========
+
+/// Latest schema version known to this build
+pub const SCHEMA_VERSION: u32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Last processed block of a database written before chains were tracked
+const LEGACY_LAST_BLOCK_KEY: &str = "last_block";
+
+/// Set once records without a chain have been assigned to a chain
+const LEGACY_CHAIN_KEY: &str = "legacy_chain_id";
+
+fn last_block_key(chain_id: u64) -> String {
+    format!("{}:{}", LEGACY_LAST_BLOCK_KEY, chain_id)
+}
+
+/// A single schema change. Migrations run in order inside one write transaction,
+/// so a failing migration leaves the database untouched.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&WriteTransaction) -> Result<()>,
+}
+
+/// Databases created before versioning report version 0 and run every migration,
+/// so each migration must also cope with tables that already have its layout.
+/// Append new migrations to the end; never reorder or edit released ones.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create account, state, and token metadata tables",
+        apply: create_initial_tables,
+    },
+    Migration {
+        version: 2,
+        description: "create account status and metadata tables",
+        apply: create_account_status_tables,
+    },
+    Migration {
+        version: 3,
+        description: "store deposits as typed records",
+        apply: upgrade_legacy_deposit_tables,
+    },
+    Migration {
+        version: 4,
+        description: "index deposits by status and by account",
+        apply: backfill_deposit_indexes,
+    },
+    Migration {
+        version: 5,
+        description: "create account chain table",
+        apply: create_account_chains_table,
+    },
+    Migration {
+        version: 6,
+        description: "create settings audit log table",
+        apply: create_settings_audit_table,
+    },
+    Migration {
+        version: 7,
+        description: "create API key table",
+        apply: create_api_keys_table,
+    },
+    Migration {
+        version: 8,
+        description: "create idempotency key tables",
+        apply: create_idempotency_tables,
+    },
+    Migration {
+        version: 9,
+        description: "create faucet funding ledger tables",
+        apply: create_funding_tables,
+    },
+    Migration {
+        version: 10,
+        description: "create dust reclaim table",
+        apply: create_dust_reclaims_table,
+    },
+    Migration {
+        version: 11,
+        description: "key deposits by chain",
+        apply: key_deposits_by_chain,
+    },
+    Migration {
+        version: 12,
+        description: "create funding queue table",
+        apply: create_funding_queue_table,
+    },
+    Migration {
+        version: 13,
+        description: "index deposits by status and chain",
+        apply: index_deposit_status_by_chain,
+    },
+];
+
+/// Outcome of bringing a database up to [`SCHEMA_VERSION`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    /// (version, description) of every migration that ran, in order
+    pub applied: Vec<(u32, &'static str)>,
+}
+
+fn parse_schema_version(value: Option<String>) -> Result<u32> {
+    match value {
+        Some(v) => v
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid schema version in database: {}", v)),
+        None => Ok(0),
+    }
+}
+
+fn run_migrations(txn: &WriteTransaction) -> Result<MigrationReport> {
+    let from_version = {
+        let state = txn.open_table(STATE)?;
+        let value = state
+            .get(SCHEMA_VERSION_KEY)?
+            .map(|v| v.value().to_string());
+        parse_schema_version(value)?
+    };
+
+    if from_version > SCHEMA_VERSION {
+        return Err(anyhow::anyhow!(
+            "Database schema version {} is newer than the latest version {} supported by this build",
+            from_version,
+            SCHEMA_VERSION
+        ));
+    }
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.version > from_version) {
+        (migration.apply)(txn).map_err(|e| {
+            anyhow::anyhow!(
+                "Migration {} ({}) failed: {}",
+                migration.version,
+                migration.description,
+                e
+            )
+        })?;
+        applied.push((migration.version, migration.description));
+    }
+
+    if !applied.is_empty() {
+        let mut state = txn.open_table(STATE)?;
+        state.insert(SCHEMA_VERSION_KEY, SCHEMA_VERSION.to_string().as_str())?;
+    }
+
+    Ok(MigrationReport {
+        from_version,
+        to_version: SCHEMA_VERSION,
+        applied,
+    })
+}
+
+fn create_initial_tables(txn: &WriteTransaction) -> Result<()> {
+    let _ = txn.open_table(ACCOUNTS)?;
+    let _ = txn.open_table(ADDRESS_TO_ID)?;
+    let _ = txn.open_table(STATE)?;
+    let _ = txn.open_table(TOKEN_METADATA)?;
+    Ok(())
+}
+
+fn create_account_status_tables(txn: &WriteTransaction) -> Result<()> {
+    let _ = txn.open_table(ACCOUNT_STATUS)?;
+    let _ = txn.open_table(ACCOUNT_METADATA)?;
+    Ok(())
+}
+
+/// Existing accounts and deposits are assigned to a chain on the first start with a chain
+/// configured, see [`Db::assign_legacy_chain`]
+fn create_account_chains_table(txn: &WriteTransaction) -> Result<()> {
+    let _ = txn.open_table(ACCOUNT_CHAINS)?;
+    Ok(())
+}
+
+fn create_settings_audit_table(txn: &WriteTransaction) -> Result<()> {
+    let _ = txn.open_table(SETTINGS_AUDIT)?;
+    Ok(())
+}
+
+fn create_api_keys_table(txn: &WriteTransaction) -> Result<()> {
+    let _ = txn.open_table(API_KEYS)?;
+    Ok(())
+}
+
+fn create_idempotency_tables(txn: &WriteTransaction) -> Result<()> {
+    let _ = txn.open_table(IDEMPOTENCY_KEYS)?;
+    let _ = txn.open_table(IDEMPOTENCY_BY_TIME)?;
+    Ok(())
+}
+
+fn create_funding_tables(txn: &WriteTransaction) -> Result<()> {
+    let _ = txn.open_table(FUNDINGS)?;
+    let _ = txn.open_table(FUNDINGS_BY_ADDRESS)?;
+    Ok(())
+}
+
+fn create_dust_reclaims_table(txn: &WriteTransaction) -> Result<()> {
+    let _ = txn.open_table(DUST_RECLAIMS)?;
+    Ok(())
+}
+
+fn create_funding_queue_table(txn: &WriteTransaction) -> Result<()> {
+    let _ = txn.open_table(FUNDING_QUEUE)?;
+    Ok(())
+}
+
+/// Replace the status index with one keyed by chain as well, so each chain's services
+/// read only their own deposits. Rebuilt from the deposits, whatever earlier migrations
+/// left in either index.
+fn index_deposit_status_by_chain(txn: &WriteTransaction) -> Result<()> {
+    txn.delete_table(LEGACY_DEPOSITS_BY_STATUS)?;
+    txn.delete_table(DEPOSITS_BY_STATUS)?;
+
+    let mut deposits = Vec::new();
+    for definition in [DEPOSITS, ERC20_DEPOSITS] {
+        for item in txn.open_table(definition)?.iter()? {
+            deposits.push(item?.1.value());
+        }
+    }
+    let mut by_status = txn.open_table(DEPOSITS_BY_STATUS)?;
+    for deposit in &deposits {
+        by_status.insert(
+            (
+                deposit.status.as_str(),
+                deposit.chain_id,
+                deposit.key.as_str(),
+            ),
+            kind_of(deposit),
+        )?;
+    }
+    Ok(())
 }
@@ -0,0 +1,310 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// Lifecycle status of a deposit
+///
+/// ```text
+/// detected ──> confirming ──> detected
+///    │ ╰────────────────────> sweeping ──> swept
+///    │                           │ ╰─────> failed ──> detected
+///    │                           ╰───────> detected (retry)
+///    ├─> quarantined ──> detected
+///    ╰─> reorged
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DepositStatus {
+    /// Seen on chain and waiting to be swept
+    Detected,
+    /// Waiting for more confirmations before it can be swept
+    Confirming,
+    /// A sweep transaction is being built or is in flight
+    Sweeping,
+    /// Funds were moved to the treasury
+    Swept,
+    /// The sweep transaction was mined but reverted
+    Failed,
+    /// Set aside for manual review (e.g. unknown or suspicious token)
+    Quarantined,
+    /// The block containing the deposit was reorganized away
+    Reorged,
+}
+
+impl DepositStatus {
+    pub const ALL: [DepositStatus; 7] = [
+        DepositStatus::Detected,
+        DepositStatus::Confirming,
+        DepositStatus::Sweeping,
+        DepositStatus::Swept,
+        DepositStatus::Failed,
+        DepositStatus::Quarantined,
+        DepositStatus::Reorged,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DepositStatus::Detected => "detected",
+            DepositStatus::Confirming => "confirming",
+            DepositStatus::Sweeping => "sweeping",
+            DepositStatus::Swept => "swept",
+            DepositStatus::Failed => "failed",
+            DepositStatus::Quarantined => "quarantined",
+            DepositStatus::Reorged => "reorged",
+        }
+    }
+
+    /// Whether a deposit in this status may move to `next`
+    pub fn can_transition_to(&self, next: DepositStatus) -> bool {
+        use DepositStatus::*;
+        matches!(
+            (self, next),
+            (Detected, Confirming | Sweeping | Quarantined | Reorged)
+                | (Confirming, Detected | Quarantined | Reorged)
+                | (Sweeping, Swept | Failed | Detected)
+                | (Failed, Detected | Quarantined)
+                | (Quarantined, Detected)
+        )
+    }
+
+    /// Swept and reorged deposits never change again
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, DepositStatus::Swept | DepositStatus::Reorged)
+    }
+}
+
+impl fmt::Display for DepositStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DepositStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        DepositStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| anyhow::anyhow!("Unknown deposit status: {}", s))
+    }
+}
+
+/// A single status change of a deposit
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct StatusChange {
+    pub status: DepositStatus,
+    /// Unix timestamp (seconds) of the transition
+    pub at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// A native or ERC20 deposit to one of our addresses
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deposit {
+    /// chain_id:tx_hash for native deposits, chain_id:tx_hash:log_index for ERC20 deposits
+    pub key: String,
+    /// Chain the deposit was made on (0 for records written before chains were tracked)
+    #[serde(default)]
+    pub chain_id: u64,
+    pub tx_hash: String,
+    /// Log index of the Transfer event (ERC20 only)
+    #[serde(default)]
+    pub log_index: Option<u64>,
+    /// Registration id of the receiving account
+    pub account_id: String,
+    /// Sender of the funds (unknown for deposits recorded before it was tracked)
+    #[serde(default)]
+    pub from_address: Option<String>,
+    pub amount: String,
+    /// Token contract address (ERC20 only)
+    #[serde(default)]
+    pub token_address: Option<String>,
+    /// Token symbol (ERC20 only)
+    #[serde(default)]
+    pub token_symbol: Option<String>,
+    pub block_number: u64,
+    #[serde(default)]
+    pub block_hash: Option<String>,
+    pub status: DepositStatus,
+    /// Every status the deposit went through, oldest first
+    pub history: Vec<StatusChange>,
+    /// Hash of the transaction that swept the deposit to the treasury
+    #[serde(default)]
+    pub sweep_tx_hash: Option<String>,
+    /// Error of the last failed sweep attempt
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+impl Deposit {
+    /// A newly detected native deposit
+    pub fn native(
+        chain_id: u64,
+        tx_hash: &str,
+        account_id: &str,
+        from_address: &str,
+        amount: &str,
+        block_number: u64,
+        block_hash: Option<String>,
+    ) -> Self {
+        Self::detected(
+            chain_id,
+            tx_hash,
+            None,
+            account_id,
+            from_address,
+            amount,
+            None,
+            block_number,
+            block_hash,
+        )
+    }
+
+    /// A newly detected ERC20 deposit, keyed by chain_id:tx_hash:log_index
+    #[allow(clippy::too_many_arguments)]
+    pub fn erc20(
+        chain_id: u64,
+        tx_hash: &str,
+        log_index: u64,
+        account_id: &str,
+        from_address: &str,
+        amount: &str,
+        token_address: &str,
+        token_symbol: &str,
+        block_number: u64,
+        block_hash: Option<String>,
+    ) -> Self {
+        Self::detected(
+            chain_id,
+            tx_hash,
+            Some(log_index),
+            account_id,
+            from_address,
+            amount,
+            Some((token_address, token_symbol)),
+            block_number,
+            block_hash,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn detected(
+        chain_id: u64,
+        tx_hash: &str,
+        log_index: Option<u64>,
+        account_id: &str,
+        from_address: &str,
+        amount: &str,
+        token: Option<(&str, &str)>,
+        block_number: u64,
+        block_hash: Option<String>,
+    ) -> Self {
+        Self {
+            key: Self::key_for(chain_id, tx_hash, log_index),
+            chain_id,
+            tx_hash: tx_hash.to_string(),
+            log_index,
+            account_id: account_id.to_string(),
+            from_address: Some(from_address.to_string()),
+            amount: amount.to_string(),
+            token_address: token.map(|(address, _)| address.to_string()),
+            token_symbol: token.map(|(_, symbol)| symbol.to_string()),
+            block_number,
+            block_hash,
+            status: DepositStatus::Detected,
+            history: vec![StatusChange {
+                status: DepositStatus::Detected,
+                at: now_secs(),
+                note: None,
+            }],
+            sweep_tx_hash: None,
+            last_error: None,
+        }
+    }
+
+    /// The key of a deposit. It starts with the chain id, so a transaction replayed on
+    /// another chain is recorded there too instead of being taken for a duplicate.
+    pub fn key_for(chain_id: u64, tx_hash: &str, log_index: Option<u64>) -> String {
+        match log_index {
+            Some(log_index) => format!("{}:{}:{}", chain_id, tx_hash, log_index),
+            None => format!("{}:{}", chain_id, tx_hash),
+        }
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.token_address.is_none()
+    }
+
+    /// "native" or "erc20"
+    pub fn token_type(&self) -> &'static str {
+        if self.is_native() {
+            "native"
+        } else {
+            "erc20"
+        }
+    }
+
+    /// Unix timestamp of the latest status change
+    pub fn updated_at(&self) -> u64 {
+        self.history.last().map(|c| c.at).unwrap_or(0)
+    }
+
+    /// Move the deposit to `next`, recording when it happened.
+    /// Fails without modifying the deposit if the transition is not allowed.
+    pub fn transition(&mut self, next: DepositStatus, note: Option<&str>) -> Result<()> {
+        if !self.status.can_transition_to(next) {
+            return Err(anyhow::anyhow!(
+                "Invalid deposit status transition for {}: {} -> {}",
+                self.key,
+                self.status,
+                next
+            ));
+        }
+
+        self.status = next;
+        self.history.push(StatusChange {
+            status: next,
+            at: now_secs(),
+            note: note.map(str::to_string),
+        });
+        Ok(())
+    }
+}
+
+impl redb::Value for Deposit {
+    type SelfType<'a> = Deposit;
+    type AsBytes<'a> = Vec<u8>;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Deposit
+    where
+        Self: 'a,
+    {
+        serde_json::from_slice(data).expect("deposit record is not valid JSON")
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Deposit) -> Vec<u8>
+    where
+        Self: 'b,
+    {
+        serde_json::to_vec(value).expect("deposit record serializes to JSON")
+    }
+
+    fn type_name() -> redb::TypeName {
+        redb::TypeName::new("evm_hot_wallet::Deposit")
+    }
+}
+
+pub(crate) fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
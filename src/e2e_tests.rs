@@ -1,9 +1,10 @@
-use crate::config::{Config, ProviderUrl};
+use crate::config::{ChainConfig, Config, DustReclaim, FundingPolicy, ProviderUrl};
 use crate::db::Db;
 use crate::faucet::Faucet;
+use crate::funding::FundingQueue;
 use crate::monitor::Monitor;
 use crate::sweeper::Sweeper;
-use crate::traits::Service;
+use crate::traits::{Service, ServiceContext};
 use crate::wallet::Wallet;
 use alloy::providers::ProviderBuilder;
 use serde_json::json;
@@ -14,6 +15,9 @@ use tokio::time::sleep;
 use wiremock::matchers::method;
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
+/// Chain the test fixtures are on
+const CHAIN_ID: u64 = 1;
+
 #[tokio::test]
 async fn test_e2e_deposit_sweep_flow() {
     let _ = tracing_subscriber::fmt::try_init();
@@ -28,18 +32,38 @@ async fn test_e2e_deposit_sweep_flow() {
 
     let config = Config {
         database_url: db_path.to_string(),
-        provider_url: ProviderUrl::Http(rpc_server.uri()),
         mnemonic: "test test test test test test test test test test test junk".to_string(),
-        treasury_address: "0x9999999999999999999999999999999999999999".to_string(),
         port: 3001,
         poll_interval: 1,
         faucet_mnemonic: "test test test test test test test test test test test junk".to_string(),
-        existential_deposit: "10000000000000000".to_string(),
         faucet_address: "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266".to_string(),
-        block_offset_from_head: 0, // Use 0 for tests to avoid underflow with low block numbers
         get_logs_max_retries: 30,
         get_logs_delay_ms: 50,
         webhook_jwt_token: None,
+        health_max_block_lag: 50,
+        health_max_sweeper_idle_secs: 300,
+        shutdown_timeout_secs: 30,
+        webhook_allowed_hosts: vec![],
+        funding_rate_per_sec: 5,
+        funding_policy: FundingPolicy::Eager,
+        alert_webhook_url: None,
+        faucet_watchdog_interval_secs: 60,
+        refill_mnemonic: None,
+        dust_reclaim: DustReclaim::Off,
+        dust_reclaim_interval_secs: 3600,
+        chains: vec![ChainConfig {
+            name: "default".to_string(),
+            chain_id: CHAIN_ID,
+            provider_url: ProviderUrl::Http(rpc_server.uri()),
+            treasury_address: "0x9999999999999999999999999999999999999999".to_string(),
+            existential_deposit: "10000000000000000".to_string(),
+            block_offset_from_head: 0, // Use 0 for tests to avoid underflow with low block numbers
+            faucet_min_balance: "10000000000000000".to_string(),
+            funding_cap: None,
+            faucet_refill: None,
+            dust_reclaim_min: "0".to_string(),
+            gas_top_up: None,
+        }],
     };
 
     let wallet = Wallet::new(config.mnemonic.clone());
@@ -50,17 +74,19 @@ async fn test_e2e_deposit_sweep_flow() {
     let addr1 = wallet.derive_address(0).unwrap();
     let addr1_str = addr1.to_string();
     let webhook_url = webhook_server.uri();
-    db.register_account("user_1", 0, &addr1_str, &webhook_url)
+    db.register_account("user_1", 0, &addr1_str, &webhook_url, &[CHAIN_ID])
         .unwrap();
 
     // User 2 -> Index 1
     let addr2 = wallet.derive_address(1).unwrap();
     let addr2_str = addr2.to_string();
-    db.register_account("user_2", 1, &addr2_str, &webhook_url)
+    db.register_account("user_2", 1, &addr2_str, &webhook_url, &[CHAIN_ID])
         .unwrap();
 
     // 4. Initialize Provider
-    let provider = ProviderBuilder::new().on_http(rpc_server.uri().parse().unwrap());
+    let provider = ProviderBuilder::new()
+        .on_http(rpc_server.uri().parse().unwrap())
+        .boxed();
 
     // 5. Mock RPC Responses
 
@@ -216,7 +242,7 @@ async fn test_e2e_deposit_sweep_flow() {
                     "blockHash": block_hash,
                     "blockNumber": "0xB", // Next block
                     "from": addr1_str,
-                    "to": config.treasury_address,
+                    "to": config.chains[0].treasury_address,
                     "cumulativeGasUsed": "0x5208",
                     "gasUsed": "0x5208",
                     "contractAddress": null,
@@ -261,18 +287,28 @@ async fn test_e2e_deposit_sweep_flow() {
         .await;
 
     // 5. Run Monitor & Sweeper
-    let monitor = Monitor::new(config.clone(), db.clone(), provider.clone());
+    // The first deposit queues the address's funding, as under the on-first-deposit policy
+    let funding = FundingQueue::new(CHAIN_ID);
+    let monitor = Monitor::new(
+        config.clone(),
+        config.chains[0].clone(),
+        Arc::new(db.clone()),
+        provider.clone(),
+    )
+    .with_funding_queue(Some(funding.clone()));
     let faucet = Arc::new(
         Faucet::new(
+            CHAIN_ID,
             config.faucet_mnemonic.clone(),
             provider.clone(),
-            &config.existential_deposit,
+            crate::settings::Settings::new(&config),
         )
         .unwrap(),
     );
     let sweeper = Sweeper::new(
         config.clone(),
-        db.clone(),
+        config.chains[0].clone(),
+        Arc::new(db.clone()),
         wallet.clone(),
         provider.clone(),
         faucet,
@@ -282,19 +318,22 @@ async fn test_e2e_deposit_sweep_flow() {
     // We can't easily "run once" with the loop, but we can spawn and wait a bit.
     // For testability, it's better if Monitor/Sweeper had a `run_once` method, but we can just let them run.
 
-    let _monitor_handle = tokio::spawn(async move {
-        monitor.run().await;
+    let shutdown = tokio_util::sync::CancellationToken::new();
+    let _monitor_handle = tokio::spawn({
+        let ctx = ServiceContext::new(shutdown.clone());
+        async move { monitor.run(ctx).await }
     });
 
-    let _sweeper_handle = tokio::spawn(async move {
-        sweeper.run().await;
+    let _sweeper_handle = tokio::spawn({
+        let ctx = ServiceContext::new(shutdown.clone());
+        async move { sweeper.run(ctx).await }
     });
 
     // 6. Wait and Verify
     // Wait for deposit detection
     let mut detected = false;
     for _ in 0..10 {
-        let deposits = db.get_detected_deposits().unwrap();
+        let deposits = db.get_detected_deposits(CHAIN_ID).unwrap();
         if !deposits.is_empty() {
             detected = true;
             break;
@@ -302,6 +341,7 @@ async fn test_e2e_deposit_sweep_flow() {
         sleep(Duration::from_millis(500)).await;
     }
     assert!(detected, "Deposit should be detected");
+    assert_eq!(funding.pending(), 1);
 
     // Wait for sweep (status change in DB)
     let mut swept = false;
@@ -309,7 +349,7 @@ async fn test_e2e_deposit_sweep_flow() {
         // We don't have a direct "get_swept_deposits" but we can check if detected list is empty
         // assuming we only had one. Or check DB directly if we exposed a method.
         // Let's check if detected becomes empty.
-        let deposits = db.get_detected_deposits().unwrap();
+        let deposits = db.get_detected_deposits(CHAIN_ID).unwrap();
         if deposits.is_empty() {
             swept = true;
             break;
@@ -0,0 +1,118 @@
+use crate::config::ConfigErrors;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// Why a [`HotWalletService`](crate::HotWalletService) request failed. Every error has a
+/// stable [`code`](ServiceError::code) clients can match on; the message is for people
+/// and may change.
+#[derive(Debug, Error)]
+pub enum ServiceError {
+    /// A field of the request is missing or malformed
+    #[error("{0}")]
+    InvalidRequest(String),
+    #[error("chain {0} is not configured")]
+    UnknownChain(u64),
+    /// The webhook URL points at an internal address or a host outside the allowlist
+    #[error("webhook_url is not allowed: {0}")]
+    WebhookUrlNotAllowed(String),
+    /// An `Idempotency-Key` was sent again with a different request
+    #[error("Idempotency-Key {0} was already used for a different request")]
+    IdempotencyKeyReused(String),
+    #[error(transparent)]
+    InvalidSettings(#[from] ConfigErrors),
+    #[error("account {0} not found")]
+    AccountNotFound(String),
+    /// Deactivated accounts keep their address but can't be registered again
+    #[error("account {0} is deactivated")]
+    AccountDeactivated(String),
+    #[error("transaction {0} not found")]
+    TransactionNotFound(String),
+    #[error("no deposits found for transaction {0}")]
+    DepositsNotFound(String),
+    /// The node of a chain failed to answer, so the request may succeed when retried
+    #[error("node of chain {chain_id} is unavailable: {message}")]
+    NodeUnavailable { chain_id: u64, message: String },
+    /// Storage failures and other faults of the service itself
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+/// What kind of failure a [`ServiceError`] is, independent of how it is reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The request is malformed
+    InvalidRequest,
+    /// The request is well-formed, but names something that does not exist
+    NotFound,
+    /// The request conflicts with the current state of what it names
+    Conflict,
+    /// The request is well-formed, but its values are not acceptable
+    Unprocessable,
+    /// A dependency is unavailable; retrying later may succeed
+    Unavailable,
+    Internal,
+}
+
+impl ServiceError {
+    /// Machine-readable identifier of the error, stable across releases
+    pub fn code(&self) -> &'static str {
+        match self {
+            ServiceError::InvalidRequest(_) => "invalid_request",
+            ServiceError::UnknownChain(_) => "unknown_chain",
+            ServiceError::WebhookUrlNotAllowed(_) => "webhook_url_not_allowed",
+            ServiceError::IdempotencyKeyReused(_) => "idempotency_key_reused",
+            ServiceError::InvalidSettings(_) => "invalid_settings",
+            ServiceError::AccountNotFound(_) => "account_not_found",
+            ServiceError::AccountDeactivated(_) => "account_deactivated",
+            ServiceError::TransactionNotFound(_) => "transaction_not_found",
+            ServiceError::DepositsNotFound(_) => "deposits_not_found",
+            ServiceError::NodeUnavailable { .. } => "node_unavailable",
+            ServiceError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ServiceError::InvalidRequest(_) => ErrorKind::InvalidRequest,
+            ServiceError::UnknownChain(_)
+            | ServiceError::WebhookUrlNotAllowed(_)
+            | ServiceError::IdempotencyKeyReused(_)
+            | ServiceError::InvalidSettings(_) => ErrorKind::Unprocessable,
+            ServiceError::AccountNotFound(_)
+            | ServiceError::TransactionNotFound(_)
+            | ServiceError::DepositsNotFound(_) => ErrorKind::NotFound,
+            ServiceError::AccountDeactivated(_) => ErrorKind::Conflict,
+            ServiceError::NodeUnavailable { .. } => ErrorKind::Unavailable,
+            ServiceError::Internal(_) => ErrorKind::Internal,
+        }
+    }
+
+    pub(crate) fn invalid(message: impl Into<String>) -> Self {
+        ServiceError::InvalidRequest(message.into())
+    }
+
+    /// A failed call to the node of `chain_id`
+    pub(crate) fn node(chain_id: u64, error: impl std::fmt::Display) -> Self {
+        ServiceError::NodeUnavailable {
+            chain_id,
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Body of every error response of the API
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct ErrorResponse {
+    pub error: ErrorDetail,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct ErrorDetail {
+    /// Stable, machine-readable identifier such as `account_not_found`
+    pub code: String,
+    /// Human-readable description; may change between releases
+    pub message: String,
+}
+
+pub type ServiceResult<T> = std::result::Result<T, ServiceError>;
@@ -0,0 +1,213 @@
+use crate::auth::ApiKey;
+use crate::db::{DustReclaimRecord, FundingRecord, IdempotencyRecord, QueuedFunding};
+use crate::deposit::{now_secs, Deposit};
+use crate::settings::SettingChange;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{BufRead, Write};
+
+/// Identifies an export file in its header line
+pub const EXPORT_FORMAT: &str = "evm_hot_wallet_export";
+
+/// Bumped when the layout of export records changes incompatibly
+pub const EXPORT_VERSION: u32 = 1;
+
+/// One line of a JSON-lines export. The first line is always a header.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExportRecord {
+    Header {
+        format: String,
+        version: u32,
+        /// Unix timestamp (seconds) of the snapshot
+        exported_at: u64,
+    },
+    Account(AccountRecord),
+    Deposit(Deposit),
+    TokenMetadata {
+        address: String,
+        symbol: String,
+        decimals: u8,
+        name: String,
+    },
+    /// Service state such as the last processed block
+    State {
+        key: String,
+        value: String,
+    },
+    /// A faucet funding ledger entry, kept so lifetime caps survive a restore
+    Funding(FundingRecord),
+    /// A hashed API key, so clients keep their access after a restore
+    ApiKey(ApiKey),
+    /// An entry of the settings audit log, oldest first
+    SettingChange(SettingChange),
+    /// A stored response, so retries within the key's lifetime still replay it
+    IdempotencyKey(IdempotencyRecord),
+    /// A dust reclaim, so reclaimed addresses aren't checked all over again
+    DustReclaim(DustReclaimRecord),
+    /// A funding still waiting in its chain's queue, so it is sent after a restore
+    QueuedFunding(QueuedFunding),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AccountRecord {
+    pub id: String,
+    pub derivation_index: u32,
+    pub address: String,
+    pub webhook_url: String,
+    pub status: String,
+    #[serde(default)]
+    pub status_updated_at: Option<u64>,
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+    /// Chains the address is watched on; empty in exports from before chains were tracked
+    #[serde(default)]
+    pub chains: Vec<u64>,
+}
+
+/// Number of records of each kind written or read
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ExportSummary {
+    pub accounts: usize,
+    pub deposits: usize,
+    pub token_metadata: usize,
+    pub state: usize,
+    pub fundings: usize,
+    pub api_keys: usize,
+    pub setting_changes: usize,
+    pub idempotency_keys: usize,
+    pub dust_reclaims: usize,
+    pub queued_fundings: usize,
+}
+
+impl ExportSummary {
+    fn count(&mut self, record: &ExportRecord) {
+        match record {
+            ExportRecord::Header { .. } => {}
+            ExportRecord::Account(_) => self.accounts += 1,
+            ExportRecord::Deposit(_) => self.deposits += 1,
+            ExportRecord::TokenMetadata { .. } => self.token_metadata += 1,
+            ExportRecord::State { .. } => self.state += 1,
+            ExportRecord::Funding(_) => self.fundings += 1,
+            ExportRecord::ApiKey(_) => self.api_keys += 1,
+            ExportRecord::SettingChange(_) => self.setting_changes += 1,
+            ExportRecord::IdempotencyKey(_) => self.idempotency_keys += 1,
+            ExportRecord::DustReclaim(_) => self.dust_reclaims += 1,
+            ExportRecord::QueuedFunding(_) => self.queued_fundings += 1,
+        }
+    }
+}
+
+impl fmt::Display for ExportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} accounts, {} deposits, {} tokens, {} state entries, {} fundings, {} API keys, \
+             {} setting changes, {} idempotency keys, {} dust reclaims, {} queued fundings",
+            self.accounts,
+            self.deposits,
+            self.token_metadata,
+            self.state,
+            self.fundings,
+            self.api_keys,
+            self.setting_changes,
+            self.idempotency_keys,
+            self.dust_reclaims,
+            self.queued_fundings
+        )
+    }
+}
+
+/// Writes export records as JSON lines, starting with the header
+pub struct ExportWriter<'a> {
+    out: &'a mut (dyn Write + Send),
+    summary: ExportSummary,
+}
+
+impl<'a> ExportWriter<'a> {
+    pub fn new(out: &'a mut (dyn Write + Send)) -> Result<Self> {
+        let mut writer = Self {
+            out,
+            summary: ExportSummary::default(),
+        };
+        writer.write_line(&ExportRecord::Header {
+            format: EXPORT_FORMAT.to_string(),
+            version: EXPORT_VERSION,
+            exported_at: now_secs(),
+        })?;
+        Ok(writer)
+    }
+
+    pub fn write(&mut self, record: &ExportRecord) -> Result<()> {
+        if matches!(record, ExportRecord::Header { .. }) {
+            return Err(anyhow::anyhow!("The export header is written only once"));
+        }
+        self.summary.count(record);
+        self.write_line(record)
+    }
+
+    pub fn finish(self) -> Result<ExportSummary> {
+        self.out.flush()?;
+        Ok(self.summary)
+    }
+
+    fn write_line(&mut self, record: &ExportRecord) -> Result<()> {
+        serde_json::to_writer(&mut *self.out, record)?;
+        self.out.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+/// Parse and validate a whole export, returning every record after the header
+pub fn read_export(reader: impl BufRead) -> Result<Vec<ExportRecord>> {
+    let mut lines = reader.lines().enumerate();
+
+    let (_, header) = lines
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Export is empty"))?;
+    match serde_json::from_str(&header?) {
+        Ok(ExportRecord::Header {
+            format, version, ..
+        }) if format == EXPORT_FORMAT && version == EXPORT_VERSION => {}
+        Ok(ExportRecord::Header {
+            format, version, ..
+        }) => {
+            return Err(anyhow::anyhow!(
+                "Unsupported export {} version {} (expected {} version {})",
+                format,
+                version,
+                EXPORT_FORMAT,
+                EXPORT_VERSION
+            ))
+        }
+        _ => return Err(anyhow::anyhow!("Export does not start with a header line")),
+    }
+
+    let mut records = Vec::new();
+    for (index, line) in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: ExportRecord = serde_json::from_str(&line)
+            .map_err(|e| anyhow::anyhow!("Invalid export record on line {}: {}", index + 1, e))?;
+        if matches!(record, ExportRecord::Header { .. }) {
+            return Err(anyhow::anyhow!(
+                "Unexpected header on line {} of export",
+                index + 1
+            ));
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Count the records of each kind, e.g. to report what an import loaded
+pub fn summarize(records: &[ExportRecord]) -> ExportSummary {
+    let mut summary = ExportSummary::default();
+    for record in records {
+        summary.count(record);
+    }
+    summary
+}
@@ -6,41 +6,245 @@ use anyhow::Result;
 use std::str::FromStr;
 use tracing::{error, info};
 
+use crate::config::GasTopUp;
+use crate::db::{FundingReason, FundingStart, NewFunding};
+use crate::metrics::{metrics, observe_rpc};
+use crate::settings::Settings;
+use crate::storage::Storage;
 use crate::wallet::Wallet;
+use std::sync::Arc;
+
+/// A funding the faucet declined to send
+#[derive(Debug, thiserror::Error)]
+pub enum FundingRefused {
+    #[error("address {address} has faucet funding {id} in flight")]
+    InFlight { address: String, id: u64 },
+    #[error("funding {address} with {amount} wei would exceed its lifetime cap of {cap} wei ({spent} wei funded so far)")]
+    CapReached {
+        address: String,
+        amount: U256,
+        cap: U256,
+        spent: U256,
+    },
+    #[error("a gas top-up of {amount} wei is more than gas_top_up_max of {max} wei")]
+    TopUpAboveMax { amount: U256, max: U256 },
+}
 
 pub struct Faucet<P> {
+    chain_id: u64,
     wallet: Wallet,
     provider: P,
-    existential_deposit: U256,
+    /// Read on every funding for the existential deposit
+    settings: Settings,
+    /// Sweeps lacking gas get their shortfall rather than the existential deposit
+    gas_top_up: Option<GasTopUp>,
+    /// Where every transfer is recorded, deduplicated, and capped
+    ledger: Option<Arc<dyn Storage>>,
+    funding_cap: Option<String>,
+    /// Off for keys other than the faucet's, such as the refill key
+    balance_metric: bool,
 }
 
-impl<T> Faucet<alloy::providers::RootProvider<T>>
+impl<P> Faucet<P>
 where
-    T: alloy::transports::Transport + Clone,
+    P: Provider,
 {
     pub fn new(
+        chain_id: u64,
         faucet_mnemonic: String,
-        provider: alloy::providers::RootProvider<T>,
-        existential_deposit_str: &str,
+        provider: P,
+        settings: Settings,
     ) -> Result<Self> {
+        // Fail now rather than on the first funding
+        settings.chain(chain_id)?;
         let wallet = Wallet::new(faucet_mnemonic);
-        let existential_deposit = U256::from_str(existential_deposit_str)?;
 
         Ok(Self {
+            chain_id,
             wallet,
             provider,
-            existential_deposit,
+            settings,
+            gas_top_up: None,
+            ledger: None,
+            funding_cap: None,
+            balance_metric: true,
         })
     }
 
+    /// Record every transfer in `db`'s funding ledger, refusing to fund an address with a
+    /// funding in flight or past `funding_cap` wei over its lifetime
+    pub fn with_ledger(mut self, db: Arc<dyn Storage>, funding_cap: Option<String>) -> Self {
+        self.ledger = Some(db);
+        self.funding_cap = funding_cap;
+        self
+    }
+
+    /// Whether balances read by this faucet set the `faucet_balance_wei` gauge; turned off
+    /// when the key is not the chain's faucet
+    pub fn with_balance_metric(mut self, balance_metric: bool) -> Self {
+        self.balance_metric = balance_metric;
+        self
+    }
+
+    /// Fund sweeps lacking gas with their shortfall, within the bounds of `gas_top_up`
+    pub fn with_gas_top_up(mut self, gas_top_up: Option<GasTopUp>) -> Self {
+        self.gas_top_up = gas_top_up;
+        self
+    }
+
+    fn existential_deposit(&self) -> Result<U256> {
+        let amount = self.settings.chain(self.chain_id)?.existential_deposit;
+        Ok(U256::from_str(&amount)?)
+    }
+
+    /// Wei to send an address holding `balance` that needs `required` for gas: the
+    /// existential deposit, or in top-up mode the shortfall plus the margin, at least the
+    /// configured minimum. Refused with [`FundingRefused::TopUpAboveMax`] past the maximum,
+    /// since less than that would not cover the gas.
+    pub fn gas_funding_amount(&self, required: U256, balance: U256) -> Result<U256> {
+        let Some(top_up) = &self.gas_top_up else {
+            return self.existential_deposit();
+        };
+        let min = U256::from_str(&top_up.min)?;
+        let max = U256::from_str(&top_up.max)?;
+        // Enough to hold strictly more than `required`
+        let shortfall = (required + U256::from(1)).saturating_sub(balance);
+        let amount = shortfall + shortfall * U256::from(top_up.margin_percent) / U256::from(100);
+        if amount > max {
+            return Err(FundingRefused::TopUpAboveMax { amount, max }.into());
+        }
+        Ok(amount.max(min))
+    }
+
     /// Send existential deposit to a newly created address
-    pub async fn fund_new_address(&self, to_address: &str) -> Result<String> {
-        let to = Address::from_str(to_address)?;
+    pub async fn fund_new_address(
+        &self,
+        to_address: &str,
+        reason: FundingReason,
+    ) -> Result<String> {
+        let existential_deposit = self.existential_deposit()?;
+        info!(
+            "Funding new address {} with {} wei on chain {} ({})",
+            to_address,
+            existential_deposit,
+            self.chain_id,
+            reason.as_str()
+        );
+        self.transfer(to_address, existential_deposit, reason).await
+    }
 
+    /// Fund an address holding `balance` wei so it can pay the `required` wei of gas of
+    /// its sweep
+    pub async fn fund_gas(
+        &self,
+        to_address: &str,
+        required: U256,
+        balance: U256,
+    ) -> Result<String> {
+        let amount = match self.gas_funding_amount(required, balance) {
+            Ok(amount) => amount,
+            Err(e) => {
+                if let Some(FundingRefused::TopUpAboveMax { .. }) = e.downcast_ref() {
+                    error!(
+                        "Not funding gas of {} on chain {} (needs {} wei, holds {} wei): {}",
+                        to_address, self.chain_id, required, balance, e
+                    );
+                    metrics()
+                        .faucet_fundings
+                        .with_label_values(&[
+                            &self.chain_id.to_string(),
+                            FundingReason::SweepGas.as_str(),
+                            "above_max",
+                        ])
+                        .inc();
+                }
+                return Err(e);
+            }
+        };
         info!(
-            "Funding new address {} with {} wei",
-            to_address, self.existential_deposit
+            "Funding gas of {} with {} wei on chain {} (needs {} wei, holds {} wei)",
+            to_address, amount, self.chain_id, required, balance
         );
+        self.transfer(to_address, amount, FundingReason::SweepGas)
+            .await
+    }
+
+    /// Send `amount` through the ledger, if any: recorded as pending first, then as sent
+    /// or failed
+    pub async fn transfer(
+        &self,
+        to_address: &str,
+        amount: U256,
+        reason: FundingReason,
+    ) -> Result<String> {
+        let outcome = |outcome: &str| {
+            metrics()
+                .faucet_fundings
+                .with_label_values(&[&self.chain_id.to_string(), reason.as_str(), outcome])
+                .inc();
+        };
+        let Some(db) = &self.ledger else {
+            let result = self.send(to_address, amount).await;
+            outcome(if result.is_ok() { "sent" } else { "failed" });
+            return result;
+        };
+
+        let cap = self
+            .funding_cap
+            .as_deref()
+            .map(U256::from_str)
+            .transpose()?;
+        let funding = NewFunding {
+            chain_id: self.chain_id,
+            address: to_address.to_string(),
+            amount,
+            reason,
+        };
+        let record = match db.begin_funding(&funding, cap).await? {
+            FundingStart::Started(record) => record,
+            FundingStart::InFlight(in_flight) => {
+                outcome("in_flight");
+                return Err(FundingRefused::InFlight {
+                    address: to_address.to_string(),
+                    id: in_flight.id,
+                }
+                .into());
+            }
+            FundingStart::CapReached { spent } => {
+                outcome("cap_reached");
+                return Err(FundingRefused::CapReached {
+                    address: to_address.to_string(),
+                    amount,
+                    cap: cap.unwrap_or_default(),
+                    spent,
+                }
+                .into());
+            }
+        };
+
+        match self.send(to_address, amount).await {
+            Ok(tx_hash) => {
+                outcome("sent");
+                if let Err(e) = db.mark_funding_sent(record.id, &tx_hash).await {
+                    error!("Failed to record funding {} as sent: {:?}", record.id, e);
+                }
+                Ok(tx_hash)
+            }
+            Err(e) => {
+                outcome("failed");
+                if let Err(ledger_error) = db.mark_funding_failed(record.id, &e.to_string()).await {
+                    error!(
+                        "Failed to record funding {} as failed: {:?}",
+                        record.id, ledger_error
+                    );
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn send(&self, to_address: &str, amount: U256) -> Result<String> {
+        let to = Address::from_str(to_address)?;
 
         // Get the faucet signer (using index 0 from the faucet mnemonic)
         let signer = self.wallet.get_signer(0)?;
@@ -49,12 +253,11 @@ where
         info!("Faucet address: {}", faucet_address);
 
         // Check faucet balance
-        let balance = self.provider.get_balance(faucet_address).await?;
-        if balance < self.existential_deposit {
-            error!(
-                "Faucet has insufficient balance: {} < {}",
-                balance, self.existential_deposit
-            );
+        let balance =
+            observe_rpc("eth_getBalance", self.provider.get_balance(faucet_address)).await?;
+        self.observe_balance(balance);
+        if balance < amount {
+            error!("Faucet has insufficient balance: {} < {}", balance, amount);
             return Err(anyhow::anyhow!(
                 "Faucet has insufficient balance to fund new address"
             ));
@@ -68,12 +271,14 @@ where
             .on_provider(&self.provider);
 
         // Build and send transaction
-        let tx = TransactionRequest::default()
-            .with_to(to)
-            .with_value(self.existential_deposit);
+        let tx = TransactionRequest::default().with_to(to).with_value(amount);
 
-        let pending_tx = faucet_provider.send_transaction(tx).await?;
-        let receipt = pending_tx.get_receipt().await?;
+        let pending_tx = observe_rpc(
+            "eth_sendRawTransaction",
+            faucet_provider.send_transaction(tx),
+        )
+        .await?;
+        let receipt = observe_rpc("eth_getTransactionReceipt", pending_tx.get_receipt()).await?;
 
         let tx_hash = receipt.transaction_hash.to_string();
         info!(
@@ -84,13 +289,34 @@ where
         Ok(tx_hash)
     }
 
+    /// The faucet address (index 0 of the faucet mnemonic)
+    pub fn address(&self) -> Result<Address> {
+        Ok(self.wallet.get_signer(0)?.address())
+    }
+
+    /// Current balance of the faucet address
+    pub async fn balance(&self) -> Result<U256> {
+        let balance =
+            observe_rpc("eth_getBalance", self.provider.get_balance(self.address()?)).await?;
+        self.observe_balance(balance);
+        Ok(balance)
+    }
+
+    fn observe_balance(&self, balance: U256) {
+        if self.balance_metric {
+            metrics()
+                .faucet_balance_wei
+                .with_label_values(&[&self.chain_id.to_string()])
+                .set(f64::from(balance));
+        }
+    }
+
     /// Check if an address already has sufficient balance (skip funding if it does)
-    #[allow(dead_code)]
     pub async fn needs_funding(&self, address: &str) -> Result<bool> {
         let addr = Address::from_str(address)?;
-        let balance = self.provider.get_balance(addr).await?;
+        let balance = observe_rpc("eth_getBalance", self.provider.get_balance(addr)).await?;
 
         // If balance is less than existential deposit, it needs funding
-        Ok(balance < self.existential_deposit)
+        Ok(balance < self.existential_deposit()?)
     }
 }
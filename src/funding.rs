@@ -0,0 +1,390 @@
+use crate::db::FundingReason;
+use crate::faucet::{Faucet, FundingRefused};
+use crate::metrics::{self, metrics};
+use crate::storage::Storage;
+use crate::traits::{Service, ServiceContext, ServiceStatus};
+use alloy::providers::Provider;
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, warn};
+
+/// A new address waiting for its existential deposit
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FundingJob {
+    /// Registration id of the account, for the funding webhook
+    pub account_id: String,
+    pub address: String,
+    pub reason: FundingReason,
+}
+
+/// How long a job refused because its address has a funding in flight waits before it is
+/// tried again
+const IN_FLIGHT_RETRY: Duration = Duration::from_secs(30);
+
+/// A job in the queue, with its id in storage if it was stored
+struct Queued {
+    id: Option<u64>,
+    job: FundingJob,
+}
+
+/// Faucet fundings of one chain, waiting for its [`FundingWorker`]. Registrations only
+/// enqueue, so registering many accounts at once doesn't flood the node with transfers.
+///
+/// With storage, queued jobs are stored as well and only removed once handled, so fundings
+/// still queued or in progress at shutdown are sent after the next start (see
+/// [`restore`](Self::restore)).
+#[derive(Clone)]
+pub struct FundingQueue {
+    chain_id: u64,
+    sender: UnboundedSender<Queued>,
+    /// Shared so a restarted worker picks up where the crashed one stopped
+    receiver: Arc<Mutex<UnboundedReceiver<Queued>>>,
+    pending: Arc<AtomicUsize>,
+    db: Option<Arc<dyn Storage>>,
+}
+
+impl FundingQueue {
+    pub fn new(chain_id: u64) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            chain_id,
+            sender,
+            receiver: Arc::new(Mutex::new(receiver)),
+            pending: Arc::default(),
+            db: None,
+        }
+    }
+
+    /// Store queued jobs in `db` until they are sent
+    pub fn with_storage(mut self, db: Arc<dyn Storage>) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    /// Queue `job`, storing it first if the queue has storage
+    pub async fn push(&self, job: FundingJob) -> anyhow::Result<()> {
+        let id = match &self.db {
+            Some(db) => Some(
+                db.queue_funding(self.chain_id, &job.account_id, &job.address, job.reason)
+                    .await?
+                    .id,
+            ),
+            None => None,
+        };
+        self.send(Queued { id, job });
+        Ok(())
+    }
+
+    /// Queue the stored jobs of this chain, e.g. those a stopped process left queued.
+    /// Returns how many were queued.
+    pub async fn restore(&self) -> anyhow::Result<usize> {
+        let Some(db) = &self.db else {
+            return Ok(0);
+        };
+        let stored = db.list_queued_fundings(self.chain_id).await?;
+        let count = stored.len();
+        for queued in stored {
+            self.send(Queued {
+                id: Some(queued.id),
+                job: FundingJob {
+                    account_id: queued.account_id,
+                    address: queued.address,
+                    reason: queued.reason,
+                },
+            });
+        }
+        Ok(count)
+    }
+
+    fn send(&self, queued: Queued) {
+        // The queue holds the receiver, so the channel is never closed
+        if self.sender.send(queued).is_ok() {
+            self.pending.fetch_add(1, Ordering::Relaxed);
+            metrics()
+                .funding_queue_depth
+                .with_label_values(&[&self.chain_id.to_string()])
+                .inc();
+        }
+    }
+
+    /// Jobs not yet picked up by the worker
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::Relaxed)
+    }
+
+    async fn pop(&self, receiver: &mut UnboundedReceiver<Queued>) -> Option<Queued> {
+        let queued = receiver.recv().await?;
+        self.pending.fetch_sub(1, Ordering::Relaxed);
+        metrics()
+            .funding_queue_depth
+            .with_label_values(&[&self.chain_id.to_string()])
+            .dec();
+        Some(queued)
+    }
+
+    /// Remove a handled job from storage. Another instance sharing the database may have
+    /// handled and removed it already.
+    async fn complete(&self, queued: &Queued) {
+        let (Some(db), Some(id)) = (&self.db, queued.id) else {
+            return;
+        };
+        if let Err(e) = db.take_queued_funding(id).await {
+            error!(
+                "Failed to remove the handled funding of {} on chain {}: {:?}",
+                queued.job.address, self.chain_id, e
+            );
+        }
+    }
+
+    /// Queue a job again after [`IN_FLIGHT_RETRY`]. A stored job stays stored meanwhile, so
+    /// it is not lost if the process stops first.
+    fn retry_later(&self, queued: Queued) {
+        let queue = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(IN_FLIGHT_RETRY).await;
+            queue.send(queued);
+        });
+    }
+}
+
+/// Sends the fundings of a [`FundingQueue`] from the chain's faucet, at most
+/// `rate_per_sec` per second, and notifies each account's webhook of the outcome
+pub struct FundingWorker<P> {
+    chain_name: String,
+    queue: FundingQueue,
+    faucet: Arc<Faucet<P>>,
+    db: Arc<dyn Storage>,
+    rate_per_sec: u32,
+    webhook_jwt_token: Option<String>,
+}
+
+impl<P> FundingWorker<P>
+where
+    P: Provider,
+{
+    pub fn new(
+        chain_name: &str,
+        queue: FundingQueue,
+        faucet: Arc<Faucet<P>>,
+        db: Arc<dyn Storage>,
+        rate_per_sec: u32,
+    ) -> Self {
+        Self {
+            chain_name: chain_name.to_string(),
+            queue,
+            faucet,
+            db,
+            rate_per_sec: rate_per_sec.max(1),
+            webhook_jwt_token: None,
+        }
+    }
+
+    /// Send `token` as a bearer token with the funding webhooks
+    pub fn with_webhook_jwt_token(mut self, token: Option<String>) -> Self {
+        self.webhook_jwt_token = token;
+        self
+    }
+
+    /// Fund the address of `job` unless it already holds the existential deposit. False if
+    /// it has another funding in flight, so the job should be tried again.
+    async fn fund(&self, job: &FundingJob) -> bool {
+        let chain_id = self.queue.chain_id;
+        // A deposit, or an earlier funding of the same address, may already cover the gas
+        match self.faucet.needs_funding(&job.address).await {
+            Ok(false) => {
+                info!(
+                    "Address {} on chain {} already holds the existential deposit, not funding it",
+                    job.address, chain_id
+                );
+                return true;
+            }
+            Ok(true) => {}
+            Err(e) => warn!(
+                "Failed to check the balance of {} on chain {}: {:?}",
+                job.address, chain_id, e
+            ),
+        }
+        info!(
+            "Starting faucet funding for address {} on chain {}",
+            job.address, chain_id
+        );
+
+        let (tx_hash, error_message) =
+            match self.faucet.fund_new_address(&job.address, job.reason).await {
+                Ok(tx_hash) => {
+                    info!(
+                        "Successfully funded address {} with tx: {}",
+                        job.address, tx_hash
+                    );
+                    (tx_hash, None)
+                }
+                Err(e) => {
+                    // Tried again later, when the balance check sees the other funding
+                    // if it went through, or it has settled or outlived its lease if not
+                    if let Some(FundingRefused::InFlight { .. }) = e.downcast_ref() {
+                        info!(
+                            "Not funding {} yet: {}; trying again in {}s",
+                            job.address,
+                            e,
+                            IN_FLIGHT_RETRY.as_secs()
+                        );
+                        return false;
+                    }
+                    error!(
+                        "Failed to fund address {} on chain {}: {:?}",
+                        job.address, chain_id, e
+                    );
+                    (String::new(), Some(e.to_string()))
+                }
+            };
+
+        if let Err(e) = send_faucet_funding_webhook(
+            &self.db,
+            chain_id,
+            &job.account_id,
+            &job.address,
+            &tx_hash,
+            error_message.is_none(),
+            error_message.as_deref(),
+            self.webhook_jwt_token.as_deref(),
+        )
+        .await
+        {
+            error!(
+                "Failed to send faucet funding webhook for {}: {:?}",
+                job.account_id, e
+            );
+        }
+        true
+    }
+}
+
+#[async_trait]
+impl<P> Service for FundingWorker<P>
+where
+    P: Provider,
+{
+    fn name(&self) -> String {
+        format!("funding[{}]", self.chain_name)
+    }
+
+    async fn run(&self, ctx: ServiceContext) {
+        info!(
+            "Starting funding worker for {} ({} per second)",
+            self.chain_name, self.rate_per_sec
+        );
+        ctx.set_status(ServiceStatus::Running);
+
+        let mut receiver = self.queue.receiver.lock().await;
+        let mut ticker = interval(Duration::from_secs(1) / self.rate_per_sec);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        // A funding in progress is completed before stopping
+        while !ctx.is_cancelled() {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = ctx.cancelled() => break,
+            }
+            let queued = tokio::select! {
+                queued = self.queue.pop(&mut receiver) => queued,
+                _ = ctx.cancelled() => break,
+            };
+            let Some(queued) = queued else { break };
+            if self.fund(&queued.job).await {
+                self.queue.complete(&queued).await;
+            } else {
+                self.queue.retry_later(queued);
+            }
+        }
+
+        let left = self.queue.pending();
+        if left > 0 && self.queue.db.is_some() {
+            info!(
+                "Funding worker for {} stopped with {} fundings queued; they are sent after the next start",
+                self.chain_name, left
+            );
+        } else if left > 0 {
+            warn!(
+                "Funding worker for {} stopped with {} fundings queued",
+                self.chain_name, left
+            );
+        } else {
+            info!("Funding worker for {} stopped", self.chain_name);
+        }
+    }
+}
+
+/// Send webhook notification for faucet funding event
+/// registration_id: The original id used when registering the account
+/// address: The Polygon address (account_id in webhook)
+/// jwt_token: Optional JWT token for authorization header
+#[allow(clippy::too_many_arguments)]
+async fn send_faucet_funding_webhook(
+    db: &Arc<dyn Storage>,
+    chain_id: u64,
+    registration_id: &str,
+    address: &str,
+    tx_hash: &str,
+    success: bool,
+    error_message: Option<&str>,
+    jwt_token: Option<&str>,
+) -> anyhow::Result<()> {
+    // Get the webhook URL using registration_id (the key in ACCOUNTS table)
+    let Some(webhook_url) = db.get_webhook_url(registration_id).await? else {
+        error!(
+            "No webhook URL found for registration_id: {}",
+            registration_id
+        );
+        return Ok(());
+    };
+
+    let client = metrics::webhook_client();
+
+    let mut payload = serde_json::json!({
+        "event": "faucet_funding",
+        "chain_id": chain_id,
+        "account_id": address,
+        "registration_id": registration_id,
+        "success": success,
+        "id": format!("{}:{}:funding", registration_id, chain_id)
+    });
+
+    // Add tx_hash if funding was successful
+    if success && !tx_hash.is_empty() {
+        payload["tx_hash"] = serde_json::json!(tx_hash);
+    }
+
+    // Add error message if funding failed
+    if let Some(error) = error_message {
+        payload["error"] = serde_json::json!(error);
+    }
+
+    let mut request = client.post(&webhook_url).json(&payload);
+
+    // Add JWT authorization header if provided
+    if let Some(token) = jwt_token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let res = metrics::send_webhook("faucet_funding", request).await;
+
+    match res {
+        Ok(r) => info!(
+            "Faucet funding webhook sent to {}: status={}, registration_id={}",
+            webhook_url,
+            r.status(),
+            registration_id
+        ),
+        Err(e) => error!(
+            "Failed to send faucet funding webhook to {}: {:?}",
+            webhook_url, e
+        ),
+    }
+
+    Ok(())
+}
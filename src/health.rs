@@ -0,0 +1,118 @@
+use crate::deposit::now_secs;
+use serde::Serialize;
+use std::future::IntoFuture;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// Outcome of a single liveness or readiness check
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct HealthCheck {
+    pub name: &'static str,
+    /// Chain the check is about; absent for process-wide checks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    pub healthy: bool,
+    /// Passed, but something needs an operator's attention, e.g. a low faucet balance
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub degraded: bool,
+    pub detail: String,
+}
+
+impl HealthCheck {
+    pub fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            chain_id: None,
+            healthy: true,
+            degraded: false,
+            detail: detail.into(),
+        }
+    }
+
+    /// A passing check that marks the report degraded without failing it
+    pub fn degraded(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            degraded: true,
+            ..Self::pass(name, detail)
+        }
+    }
+
+    pub fn fail(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            chain_id: None,
+            healthy: false,
+            degraded: false,
+            detail: detail.into(),
+        }
+    }
+
+    pub fn on_chain(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+}
+
+/// Body of `/health`, `/health/live`, and `/health/ready`
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct HealthReport {
+    /// "ok" when every check passed, "degraded" when every check passed but some need
+    /// attention, "failing" otherwise
+    pub status: &'static str,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    pub fn new(checks: Vec<HealthCheck>) -> Self {
+        let status = if !checks.iter().all(|c| c.healthy) {
+            "failing"
+        } else if checks.iter().any(|c| c.degraded) {
+            "degraded"
+        } else {
+            "ok"
+        };
+        Self { status, checks }
+    }
+
+    /// Whether no check failed; a degraded report is still healthy
+    pub fn is_healthy(&self) -> bool {
+        self.status != "failing"
+    }
+}
+
+/// Time of the last successful iteration of a background loop
+#[derive(Debug, Default)]
+pub struct Heartbeat(AtomicU64);
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        self.0.store(now_secs(), Ordering::Relaxed);
+    }
+
+    /// Unix timestamp (seconds) of the last beat, or None if it never beat
+    pub fn last(&self) -> Option<u64> {
+        match self.0.load(Ordering::Relaxed) {
+            0 => None,
+            secs => Some(secs),
+        }
+    }
+}
+
+/// Upper bound on each RPC call made by a readiness check
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Run a check's RPC call, failing it if the node does not answer within [`CHECK_TIMEOUT`]
+pub async fn with_timeout<F, T, E>(call: F) -> anyhow::Result<T>
+where
+    F: IntoFuture<Output = Result<T, E>>,
+    E: Into<anyhow::Error>,
+{
+    match tokio::time::timeout(CHECK_TIMEOUT, call.into_future()).await {
+        Ok(result) => result.map_err(Into::into),
+        Err(_) => Err(anyhow::anyhow!(
+            "timed out after {}s",
+            CHECK_TIMEOUT.as_secs()
+        )),
+    }
+}
@@ -1,47 +1,371 @@
 // Library modules
+pub mod auth;
+pub mod client;
 pub mod config;
 pub mod db;
+pub mod deposit;
+pub mod error;
+pub mod export;
 pub(crate) mod faucet;
+mod funding;
+pub mod health;
+pub mod metrics;
 mod monitor;
+pub mod postgres;
+mod reclaim;
+pub mod settings;
+pub mod storage;
+pub mod supervisor;
 mod sweeper;
 pub mod traits;
+pub mod validation;
 mod wallet;
+mod watchdog;
 
 #[cfg(test)]
 mod e2e_tests;
 #[cfg(test)]
 mod tests;
 
-use alloy::providers::{ProviderBuilder, WsConnect};
-use alloy::transports::Transport;
-use config::{Config, ProviderUrl};
-use db::Db;
+use alloy::providers::{IpcConnect, Provider, ProviderBuilder, RootProvider};
+use alloy::transports::BoxTransport;
+use config::{ChainConfig, Config, DustReclaim, FundingPolicy, ProviderUrl};
+use db::{
+    DustReclaimRecord, DustReclaimStatus, FundingFilter, FundingReason, FundingRecord,
+    FundingStatus, IdempotencyRecord, NewAccount, Registration, PENDING_LEASE_SECS,
+};
+use deposit::{Deposit, DepositStatus, StatusChange};
+use error::{ServiceError, ServiceResult};
 use faucet::Faucet;
+use funding::{FundingJob, FundingQueue, FundingWorker};
+use health::{HealthCheck, HealthReport, Heartbeat};
 use monitor::Monitor;
+use reclaim::DustReclaimer;
 use serde::{Deserialize, Serialize};
+use settings::{RuntimeSettings, SettingChange, Settings, SettingsUpdate};
 use std::sync::Arc;
+use storage::Storage;
+use supervisor::Supervisor;
 use sweeper::Sweeper;
-use traits::Service;
+use utoipa::{IntoParams, ToSchema};
 use wallet::Wallet;
+use watchdog::FaucetWatchdog;
 
 /// Request structure for registering a new account
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
 pub struct RegisterRequest {
     pub id: String,
     pub webhook_url: String,
+    /// Chains to watch the address on; every configured chain if omitted.
+    /// Registering an existing account again adds the chains it is not watched on yet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_ids: Option<Vec<u64>>,
 }
 
 /// Response structure for account registration
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
 pub struct RegisterResponse {
     pub address: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub funding_tx: Option<String>,
+    /// Every chain the address is watched on
+    pub chains: Vec<ChainAddress>,
 }
 
+/// Request structure for registering many accounts at once
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct RegisterBatchRequest {
+    /// Up to 1000 registrations, each like a `POST /register` body
+    pub accounts: Vec<RegisterRequest>,
+}
+
+/// Response structure for batch registration, in the order of the request
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct RegisterBatchResponse {
+    pub accounts: Vec<RegisteredAccount>,
+}
+
+/// An account of a batch registration
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct RegisteredAccount {
+    pub id: String,
+    pub address: String,
+    /// Every chain the address is watched on
+    pub chains: Vec<ChainAddress>,
+    /// False if the id was registered before
+    pub created: bool,
+}
+
+/// Most accounts one batch registration may hold
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+/// Query parameters of the faucet spend report
+#[derive(Serialize, Deserialize, Clone, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FaucetSpendQuery {
+    /// Only report this chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    /// Only report the fundings and dust reclaims of this address, and list them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+/// What the faucet has spent, from the funding ledger
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct FaucetSpendReport {
+    /// Every configured chain, or only the queried one
+    pub chains: Vec<ChainFaucetSpend>,
+    /// The fundings of the queried address, oldest first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fundings: Option<Vec<FundingRecord>>,
+    /// The dust reclaims of the queried address, oldest first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dust_reclaims: Option<Vec<DustReclaimRecord>>,
+}
+
+/// Faucet spend on one chain
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ToSchema)]
+pub struct ChainFaucetSpend {
+    pub chain_id: u64,
+    /// Wei of the fundings that were sent
+    pub sent_wei: String,
+    /// Wei of the interrupted fundings, which may have been sent too
+    pub interrupted_wei: String,
+    pub sent: u64,
+    pub pending: u64,
+    pub failed: u64,
+    pub interrupted: u64,
+    /// Addresses with at least one sent funding
+    pub funded_addresses: u64,
+    /// Sent fundings per reason
+    pub by_reason: Vec<ReasonFaucetSpend>,
+    /// Refills of the faucet itself that were sent; they are not faucet spend, so
+    /// none of the other fields count them
+    pub refills: u64,
+    /// Wei the refills brought into the faucet
+    pub refilled_wei: String,
+    /// Dust reclaims sent from swept addresses, to the faucet or the treasury
+    pub dust_reclaims: u64,
+    /// Wei the dust reclaims moved
+    pub reclaimed_wei: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ToSchema)]
+pub struct ReasonFaucetSpend {
+    pub reason: FundingReason,
+    pub sent: u64,
+    pub sent_wei: String,
+}
+
+impl ChainFaucetSpend {
+    fn new(
+        chain_id: u64,
+        fundings: &[&FundingRecord],
+        dust_reclaims: &[&DustReclaimRecord],
+    ) -> anyhow::Result<Self> {
+        use alloy::primitives::U256;
+        use std::collections::{BTreeMap, HashSet};
+
+        let mut report = Self {
+            chain_id,
+            sent_wei: String::new(),
+            interrupted_wei: String::new(),
+            sent: 0,
+            pending: 0,
+            failed: 0,
+            interrupted: 0,
+            funded_addresses: 0,
+            by_reason: Vec::new(),
+            refills: 0,
+            refilled_wei: String::new(),
+            dust_reclaims: 0,
+            reclaimed_wei: String::new(),
+        };
+        let (mut sent_wei, mut interrupted_wei) = (U256::ZERO, U256::ZERO);
+        let mut refilled_wei = U256::ZERO;
+        let mut funded = HashSet::new();
+        let mut by_reason = BTreeMap::<&str, (FundingReason, u64, U256)>::new();
+        for funding in fundings {
+            let amount = U256::from_str_radix(&funding.amount, 10)?;
+            if funding.reason == FundingReason::FaucetRefill {
+                if funding.status == FundingStatus::Sent {
+                    report.refills += 1;
+                    refilled_wei = refilled_wei.saturating_add(amount);
+                }
+                continue;
+            }
+            match funding.status {
+                FundingStatus::Sent => {
+                    report.sent += 1;
+                    sent_wei = sent_wei.saturating_add(amount);
+                    funded.insert(funding.address.to_ascii_lowercase());
+                    let entry = by_reason.entry(funding.reason.as_str()).or_insert((
+                        funding.reason,
+                        0,
+                        U256::ZERO,
+                    ));
+                    entry.1 += 1;
+                    entry.2 = entry.2.saturating_add(amount);
+                }
+                FundingStatus::Pending => report.pending += 1,
+                FundingStatus::Failed => report.failed += 1,
+                FundingStatus::Interrupted => {
+                    report.interrupted += 1;
+                    interrupted_wei = interrupted_wei.saturating_add(amount);
+                }
+            }
+        }
+        report.sent_wei = sent_wei.to_string();
+        report.interrupted_wei = interrupted_wei.to_string();
+        report.refilled_wei = refilled_wei.to_string();
+        let mut reclaimed_wei = U256::ZERO;
+        for reclaim in dust_reclaims {
+            if reclaim.status == DustReclaimStatus::Sent {
+                report.dust_reclaims += 1;
+                reclaimed_wei =
+                    reclaimed_wei.saturating_add(U256::from_str_radix(&reclaim.amount, 10)?);
+            }
+        }
+        report.reclaimed_wei = reclaimed_wei.to_string();
+        report.funded_addresses = funded.len() as u64;
+        report.by_reason = by_reason
+            .into_values()
+            .map(|(reason, sent, sent_wei)| ReasonFaucetSpend {
+                reason,
+                sent,
+                sent_wei: sent_wei.to_string(),
+            })
+            .collect();
+        Ok(report)
+    }
+}
+
+/// An account's deposit address on one chain
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ToSchema)]
+pub struct ChainAddress {
+    pub chain_id: u64,
+    pub address: String,
+}
+
+/// Request structure for updating an existing account
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct UpdateAccountRequest {
+    /// New webhook URL for deposit notifications
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    /// Arbitrary JSON metadata attached to the account (replaces any previous value)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Response structure describing a registered account
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct AccountResponse {
+    pub id: String,
+    pub address: String,
+    /// Every chain the address is watched on
+    pub chains: Vec<ChainAddress>,
+    pub webhook_url: String,
+    /// Lifecycle status ("active" or "deactivated")
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Query parameters for listing deposits
+#[derive(Serialize, Deserialize, Clone, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DepositQuery {
+    /// Only return deposits made on this chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    /// Only return deposits with this status (e.g. "detected", "swept")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Only return deposits of this token: "native", a token address, or a token symbol
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Cursor returned as `next_cursor` by the previous page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Maximum number of deposits to return (default 50, max 500)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+/// A native or ERC20 deposit and its current status
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct DepositResponse {
+    /// chain_id:tx_hash for native deposits, chain_id:tx_hash:log_index for ERC20 deposits
+    pub id: String,
+    pub chain_id: u64,
+    pub registration_id: String,
+    pub tx_hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_address: Option<String>,
+    pub amount: String,
+    pub status: DepositStatus,
+    /// Token type ("native" or "erc20")
+    pub token_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_symbol: Option<String>,
+    pub block_number: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sweep_tx_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Every status the deposit went through, with timestamps
+    pub history: Vec<StatusChange>,
+}
+
+impl From<Deposit> for DepositResponse {
+    fn from(deposit: Deposit) -> Self {
+        Self {
+            token_type: deposit.token_type().to_string(),
+            id: deposit.key,
+            chain_id: deposit.chain_id,
+            registration_id: deposit.account_id,
+            tx_hash: deposit.tx_hash,
+            from_address: deposit.from_address,
+            amount: deposit.amount,
+            status: deposit.status,
+            token_address: deposit.token_address,
+            token_symbol: deposit.token_symbol,
+            block_number: deposit.block_number,
+            block_hash: deposit.block_hash,
+            sweep_tx_hash: deposit.sweep_tx_hash,
+            last_error: deposit.last_error,
+            history: deposit.history,
+        }
+    }
+}
+
+/// A page of deposits
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct DepositListResponse {
+    pub deposits: Vec<DepositResponse>,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+const DEFAULT_PAGE_LIMIT: usize = 50;
+const MAX_PAGE_LIMIT: usize = 500;
+
+/// How long the response to a request with an `Idempotency-Key` is kept for replays
+pub const IDEMPOTENCY_TTL_SECS: u64 = 24 * 60 * 60;
+
 /// Request structure for verifying a transfer
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
 pub struct VerifyTransferRequest {
+    /// Chain the transaction was sent on; the default chain if omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
     /// Transaction hash to verify
     pub tx_hash: String,
     /// Expected recipient address
@@ -53,10 +377,10 @@ pub struct VerifyTransferRequest {
     #[serde(default = "default_token_type")]
     pub token_type: String,
     /// Token contract address (required for ERC20)
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub token_address: Option<String>,
     /// Token symbol (optional, for additional validation with ERC20)
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub token_symbol: Option<String>,
 }
 
@@ -65,11 +389,12 @@ fn default_token_type() -> String {
 }
 
 /// Response structure for transfer verification
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
 #[serde(tag = "status", rename_all = "lowercase")]
 pub enum VerifyTransferResponse {
     /// Transfer was successfully verified
     Success {
+        chain_id: u64,
         /// Actual recipient address found in the transaction
         actual_to: String,
         /// Actual amount found in the transaction
@@ -85,6 +410,7 @@ pub enum VerifyTransferResponse {
     },
     /// Transfer verification failed
     Error {
+        chain_id: u64,
         /// Error message describing why verification failed
         message: String,
         /// Token type ("native" or "erc20") if known
@@ -96,88 +422,794 @@ pub enum VerifyTransferResponse {
     },
 }
 
+/// Request body of `POST /block_number`
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct SetBlockNumberRequest {
+    /// The default chain if omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    pub block_number: u64,
+}
+
+/// The last processed block of a chain
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ToSchema)]
+pub struct BlockNumberResponse {
+    pub chain_id: u64,
+    pub block_number: u64,
+}
+
+/// A watched chain: its settings (with the chain id resolved), node connection, and faucet
+struct Chain<P> {
+    config: ChainConfig,
+    provider: P,
+    faucet: Arc<Faucet<P>>,
+    /// Fundings of new addresses, sent by the chain's funding worker
+    funding: FundingQueue,
+    sweeper_heartbeat: Arc<Heartbeat>,
+}
+
+impl<P> Chain<P> {
+    fn id(&self) -> u64 {
+        self.config.chain_id
+    }
+}
+
 /// Core Hot Wallet Service that manages background tasks and provides account registration
-pub struct HotWalletService<T>
+pub struct HotWalletService<P = RootProvider<BoxTransport>>
 where
-    T: Transport + Clone + Send + Sync + 'static,
+    P: Provider + Clone + 'static,
 {
     config: Config,
-    db: Db,
+    db: Arc<dyn Storage>,
     wallet: Wallet,
-    faucet: Arc<Faucet<alloy::providers::RootProvider<T>>>,
-    provider: alloy::providers::RootProvider<T>,
+    /// In the order of `config.chains`; the first is the default chain
+    chains: Vec<Chain<P>>,
+    /// Shared with every Monitor, Sweeper, and faucet
+    settings: Settings,
+    /// Serializes settings changes so each is checked against the one before it
+    settings_lock: tokio::sync::Mutex<()>,
+    supervisor: Supervisor,
 }
 
-impl<T> HotWalletService<T>
+impl<P> HotWalletService<P>
 where
-    T: Transport + Clone + Send + Sync + 'static,
+    P: Provider + Clone + 'static,
 {
-    /// Get a reference to the database
-    pub fn db(&self) -> &Db {
+    /// Create a new HotWalletService that talks to each chain's node through the provider
+    /// at the same position in `providers`, e.g. ones built on a custom transport.
+    ///
+    /// Chains configured with chain id 0 take the id their node reports.
+    pub async fn with_providers(mut config: Config, providers: Vec<P>) -> anyhow::Result<Self> {
+        if providers.len() != config.chains.len() {
+            return Err(anyhow::anyhow!(
+                "{} chains are configured but {} providers were given",
+                config.chains.len(),
+                providers.len()
+            ));
+        }
+
+        for (chain, provider) in config.chains.iter_mut().zip(&providers) {
+            if chain.chain_id == 0 {
+                chain.chain_id = metrics::observe_rpc("eth_chainId", provider.get_chain_id())
+                    .await
+                    .map_err(|e| {
+                        anyhow::anyhow!("Failed to read the chain id of {}: {}", chain.name, e)
+                    })?;
+            }
+        }
+        for (i, chain) in config.chains.iter().enumerate() {
+            if config.chains[..i]
+                .iter()
+                .any(|c| c.chain_id == chain.chain_id)
+            {
+                return Err(anyhow::anyhow!(
+                    "Chain id {} is configured more than once",
+                    chain.chain_id
+                ));
+            }
+        }
+
+        let db = storage::open(&config.database_url).await?;
+        let default_chain = config.default_chain().chain_id;
+        let assigned = db.assign_legacy_chain(default_chain).await?;
+        if assigned > 0 {
+            tracing::info!(
+                "🔗 Assigned {} accounts and deposits recorded before multi-chain support to chain {}",
+                assigned,
+                default_chain
+            );
+        }
+
+        let wallet = Wallet::new(config.mnemonic.clone());
+        let settings = Settings::new(&config);
+        let chains = config
+            .chains
+            .iter()
+            .zip(providers)
+            .map(|(chain, provider)| {
+                let faucet = Faucet::new(
+                    chain.chain_id,
+                    config.faucet_mnemonic.clone(),
+                    provider.clone(),
+                    settings.clone(),
+                )?
+                .with_gas_top_up(chain.gas_top_up.clone())
+                .with_ledger(db.clone(), chain.funding_cap.clone());
+                Ok(Chain {
+                    config: chain.clone(),
+                    provider,
+                    faucet: Arc::new(faucet),
+                    funding: FundingQueue::new(chain.chain_id).with_storage(db.clone()),
+                    sweeper_heartbeat: Arc::default(),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            config,
+            db,
+            wallet,
+            chains,
+            settings,
+            settings_lock: tokio::sync::Mutex::new(()),
+            supervisor: Supervisor::default(),
+        })
+    }
+
+    /// Start background services (a Monitor, a Sweeper, and a funding worker per chain)
+    /// Returns immediately after spawning the background tasks
+    pub async fn start_background_services(&self) -> anyhow::Result<()> {
+        for chain in &self.chains {
+            // Other instances may share the database, so only fundings pending past their
+            // lease are taken for left over from a stopped process
+            let interrupted = self
+                .db
+                .interrupt_pending_fundings(
+                    chain.config.chain_id,
+                    deposit::now_secs().saturating_sub(PENDING_LEASE_SECS),
+                )
+                .await?;
+            if interrupted > 0 {
+                tracing::warn!(
+                    "⚠️ {} faucet fundings on chain {} were left pending by a stopped process; check their addresses' balances",
+                    interrupted,
+                    chain.config.chain_id
+                );
+            }
+            let interrupted = self
+                .db
+                .interrupt_pending_dust_reclaims(
+                    chain.config.chain_id,
+                    deposit::now_secs().saturating_sub(PENDING_LEASE_SECS),
+                )
+                .await?;
+            if interrupted > 0 {
+                tracing::warn!(
+                    "⚠️ {} dust reclaims on chain {} were left pending by a stopped process; their addresses are checked again",
+                    interrupted,
+                    chain.config.chain_id
+                );
+            }
+            self.supervisor.spawn(Arc::new(
+                Monitor::new(
+                    self.config.clone(),
+                    chain.config.clone(),
+                    self.db.clone(),
+                    chain.provider.clone(),
+                )
+                .with_settings(self.settings.clone())
+                .with_funding_queue(
+                    (self.config.funding_policy == FundingPolicy::OnFirstDeposit)
+                        .then(|| chain.funding.clone()),
+                ),
+            ));
+
+            let reclaimer = (self.config.dust_reclaim != DustReclaim::Off).then(|| {
+                DustReclaimer::new(
+                    &self.config,
+                    chain.config.clone(),
+                    self.db.clone(),
+                    self.wallet.clone(),
+                    chain.provider.clone(),
+                    chain.faucet.clone(),
+                )
+                .with_settings(self.settings.clone())
+            });
+            self.supervisor.spawn(Arc::new(
+                Sweeper::new(
+                    self.config.clone(),
+                    chain.config.clone(),
+                    self.db.clone(),
+                    self.wallet.clone(),
+                    chain.provider.clone(),
+                    chain.faucet.clone(),
+                )
+                .with_settings(self.settings.clone())
+                .with_heartbeat(chain.sweeper_heartbeat.clone())
+                .with_dust_reclaimer(reclaimer),
+            ));
+
+            let restored = chain.funding.restore().await?;
+            if restored > 0 {
+                tracing::info!(
+                    "Requeued {} fundings on chain {} left queued or in progress by a stopped process",
+                    restored,
+                    chain.config.chain_id
+                );
+            }
+            self.supervisor.spawn(Arc::new(
+                FundingWorker::new(
+                    &chain.config.name,
+                    chain.funding.clone(),
+                    chain.faucet.clone(),
+                    self.db.clone(),
+                    self.config.funding_rate_per_sec,
+                )
+                .with_webhook_jwt_token(self.config.webhook_jwt_token.clone()),
+            ));
+
+            let refiller = match (&self.config.refill_mnemonic, &chain.config.faucet_refill) {
+                (Some(mnemonic), Some(_)) => Some(
+                    Faucet::new(
+                        chain.id(),
+                        mnemonic.clone(),
+                        chain.provider.clone(),
+                        self.settings.clone(),
+                    )?
+                    .with_balance_metric(false)
+                    .with_ledger(self.db.clone(), None),
+                ),
+                _ => None,
+            };
+            self.supervisor.spawn(Arc::new(
+                FaucetWatchdog::new(
+                    chain.config.clone(),
+                    chain.faucet.clone(),
+                    self.db.clone(),
+                    self.config.faucet_watchdog_interval_secs,
+                )
+                .with_refiller(refiller)
+                .with_alerts(
+                    self.config.alert_webhook_url.clone(),
+                    self.config.webhook_jwt_token.clone(),
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Get a reference to the storage backend
+    pub fn storage(&self) -> &Arc<dyn Storage> {
         &self.db
     }
 
-    /// Get a reference to the configuration
+    /// Get a reference to the configuration, with every chain id resolved. Runtime settings
+    /// changes are not reflected here; see [`settings`](Self::settings).
     pub fn config(&self) -> &Config {
         &self.config
     }
 
-    /// Health check method - returns Ok if service is healthy
-    pub async fn health(&self) -> anyhow::Result<String> {
-        Ok("OK".to_string())
+    /// Current values of the settings that can change without a restart
+    pub fn settings(&self) -> RuntimeSettings {
+        self.settings.current()
+    }
+
+    /// Check and apply `update`, recording every changed setting in the audit log as made
+    /// by `source`. Invalid values fail with [`ServiceError::InvalidSettings`] and change
+    /// nothing.
+    pub async fn update_settings(
+        &self,
+        update: &SettingsUpdate,
+        source: &str,
+    ) -> ServiceResult<Vec<SettingChange>> {
+        let _guard = self.settings_lock.lock().await;
+        let current = self.settings.current();
+        let next = current.updated(update, self.config.health_max_sweeper_idle_secs)?;
+        Ok(self.apply_settings(current, next, source).await?)
+    }
+
+    /// Take the runtime settings from a reloaded configuration, e.g. on SIGHUP. Other
+    /// changes in `config` take effect on the next start. A poll interval above the running
+    /// `health_max_sweeper_idle_secs` fails and changes nothing.
+    pub async fn reload_settings(
+        &self,
+        config: &Config,
+        source: &str,
+    ) -> anyhow::Result<Vec<SettingChange>> {
+        let _guard = self.settings_lock.lock().await;
+        let current = self.settings.current();
+        let next = current.reloaded(config, self.config.health_max_sweeper_idle_secs)?;
+        self.apply_settings(current, next, source).await
+    }
+
+    /// The active API key `key` belongs to, or None if it is unknown or revoked
+    pub async fn authenticate(&self, key: &str) -> anyhow::Result<Option<auth::ApiKey>> {
+        let Some(id) = auth::key_id(key) else {
+            return Ok(None);
+        };
+        Ok(self
+            .db
+            .get_api_key(id)
+            .await?
+            .filter(|api_key| !api_key.is_revoked() && api_key.matches(key)))
+    }
+
+    /// The most recent `limit` settings changes, newest first
+    pub async fn settings_audit_log(&self, limit: usize) -> anyhow::Result<Vec<SettingChange>> {
+        self.db.list_setting_changes(limit).await
+    }
+
+    /// Totals of the funding ledger and the dust reclaims per chain, and the fundings and
+    /// reclaims of `query.address`
+    pub async fn faucet_spend(&self, query: &FaucetSpendQuery) -> ServiceResult<FaucetSpendReport> {
+        use std::str::FromStr;
+
+        let chain_ids: Vec<u64> = match query.chain_id {
+            Some(chain_id) => vec![self.chain(Some(chain_id))?.id()],
+            None => self.chains.iter().map(|c| c.id()).collect(),
+        };
+        if let Some(address) = &query.address {
+            alloy::primitives::Address::from_str(address).map_err(|_| {
+                ServiceError::invalid(format!("address {:?} is not an address", address))
+            })?;
+        }
+
+        let fundings = self
+            .db
+            .list_fundings(&FundingFilter {
+                chain_id: query.chain_id,
+                address: query.address.clone(),
+            })
+            .await?;
+        let dust_reclaims: Vec<_> = self
+            .db
+            .list_dust_reclaims(query.chain_id)
+            .await?
+            .into_iter()
+            .filter(|r| {
+                query
+                    .address
+                    .as_ref()
+                    .is_none_or(|address| r.address.eq_ignore_ascii_case(address))
+            })
+            .collect();
+        let chains = chain_ids
+            .iter()
+            .map(|&chain_id| {
+                let fundings: Vec<_> = fundings.iter().filter(|f| f.chain_id == chain_id).collect();
+                let reclaims: Vec<_> = dust_reclaims
+                    .iter()
+                    .filter(|r| r.chain_id == chain_id)
+                    .collect();
+                ChainFaucetSpend::new(chain_id, &fundings, &reclaims)
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(FaucetSpendReport {
+            chains,
+            fundings: query.address.is_some().then_some(fundings),
+            dust_reclaims: query.address.is_some().then_some(dust_reclaims),
+        })
+    }
+
+    /// Changes are recorded before they apply, so none goes unaudited
+    async fn apply_settings(
+        &self,
+        current: RuntimeSettings,
+        next: RuntimeSettings,
+        source: &str,
+    ) -> anyhow::Result<Vec<SettingChange>> {
+        let changes = current.changes(&next, source);
+        if changes.is_empty() {
+            return Ok(changes);
+        }
+        self.db.record_setting_changes(&changes).await?;
+        self.settings.set(next);
+
+        for change in &changes {
+            match change.chain_id {
+                Some(chain_id) => tracing::info!(
+                    "⚙️ {} on chain {} changed from {} to {} ({})",
+                    change.setting,
+                    chain_id,
+                    change.old_value,
+                    change.new_value,
+                    source
+                ),
+                None => tracing::info!(
+                    "⚙️ {} changed from {} to {} ({})",
+                    change.setting,
+                    change.old_value,
+                    change.new_value,
+                    source
+                ),
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Cancelled when shutdown starts (SIGTERM, or [`shutdown`](Self::shutdown) being called)
+    pub fn shutdown_token(&self) -> tokio_util::sync::CancellationToken {
+        self.supervisor.shutdown_token()
     }
 
-    /// Set the last processed block number manually
-    pub fn set_block_number(&self, block_number: u64) -> anyhow::Result<()> {
-        self.db.set_last_processed_block(block_number)
+    /// Stop the Monitors and Sweepers, letting an in-flight block or sweep finish, then
+    /// close the storage backend. Waits at most `shutdown_timeout_secs` for the services.
+    pub async fn shutdown(&self) -> anyhow::Result<()> {
+        let timeout = std::time::Duration::from_secs(self.config.shutdown_timeout_secs);
+        if !self.supervisor.shutdown(timeout).await {
+            tracing::warn!(
+                "⏱️ Background services did not stop within {}s",
+                timeout.as_secs()
+            );
+        }
+        self.db.close().await
     }
 
-    /// Get the current last processed block number
-    pub fn get_block_number(&self) -> anyhow::Result<u64> {
-        self.db.get_last_processed_block()
+    /// Liveness: fails only when a background task has stopped and the process needs a restart
+    pub fn liveness(&self) -> HealthReport {
+        HealthReport::new(vec![self.supervisor.liveness()])
+    }
+
+    /// Readiness: storage, then RPC, monitor lag, sweeper progress, and faucet balance of
+    /// every chain, then background tasks. A low faucet balance only marks the report
+    /// degraded: the faucet watchdog alerts on it, and most endpoints don't need the faucet.
+    pub async fn readiness(&self) -> HealthReport {
+        let mut checks = Vec::new();
+
+        match self.db.get_last_processed_block(self.chains[0].id()).await {
+            Ok(block) => checks.push(HealthCheck::pass(
+                "database",
+                format!("last processed block {}", block),
+            )),
+            Err(e) => checks.push(HealthCheck::fail("database", e.to_string())),
+        }
+
+        // Chains are checked concurrently, so slow nodes cost one RPC timeout in total
+        let chains =
+            futures::future::join_all(self.chains.iter().map(|c| self.chain_readiness(c))).await;
+        checks.extend(chains.into_iter().flatten());
+
+        checks.push(self.supervisor.readiness());
+
+        HealthReport::new(checks)
+    }
+
+    async fn chain_readiness(&self, chain: &Chain<P>) -> Vec<HealthCheck> {
+        let chain_id = chain.id();
+        let mut checks = Vec::new();
+
+        let (reported_id, head, faucet_balance, last_processed) = tokio::join!(
+            health::with_timeout(metrics::observe_rpc(
+                "eth_chainId",
+                chain.provider.get_chain_id(),
+            )),
+            health::with_timeout(metrics::observe_rpc(
+                "eth_blockNumber",
+                chain.provider.get_block_number(),
+            )),
+            health::with_timeout(chain.faucet.balance()),
+            self.db.get_last_processed_block(chain_id),
+        );
+
+        checks.push(
+            match reported_id {
+                Ok(id) if id == chain_id => HealthCheck::pass("rpc", format!("chain id {}", id)),
+                Ok(id) => HealthCheck::fail(
+                    "rpc",
+                    format!("node reports chain id {}, expected {}", id, chain_id),
+                ),
+                Err(e) => HealthCheck::fail("rpc", e.to_string()),
+            }
+            .on_chain(chain_id),
+        );
+
+        checks.push(
+            match (head, last_processed) {
+                (Err(e), _) => {
+                    HealthCheck::fail("monitor_lag", format!("chain head unknown: {}", e))
+                }
+                (Ok(_), Err(e)) => HealthCheck::fail(
+                    "monitor_lag",
+                    format!("last processed block unknown: {}", e),
+                ),
+                (Ok(_), Ok(0)) => HealthCheck::fail("monitor_lag", "no block processed yet"),
+                (Ok(head), Ok(last)) => {
+                    // The monitor deliberately stays block_offset_from_head behind the head
+                    let offset = self
+                        .settings
+                        .chain(chain_id)
+                        .map(|c| c.block_offset_from_head)
+                        .unwrap_or(chain.config.block_offset_from_head);
+                    let lag = head.saturating_sub(offset).saturating_sub(last);
+                    let detail =
+                        format!("{} blocks behind (head {}, processed {})", lag, head, last);
+                    if lag <= self.config.health_max_block_lag {
+                        HealthCheck::pass("monitor_lag", detail)
+                    } else {
+                        HealthCheck::fail("monitor_lag", detail)
+                    }
+                }
+            }
+            .on_chain(chain_id),
+        );
+
+        checks.push(
+            match chain.sweeper_heartbeat.last() {
+                None => HealthCheck::fail("sweeper", "no completed sweep cycle yet"),
+                Some(last) => {
+                    let idle = deposit::now_secs().saturating_sub(last);
+                    let detail = format!("last sweep cycle completed {}s ago", idle);
+                    if idle <= self.config.health_max_sweeper_idle_secs {
+                        HealthCheck::pass("sweeper", detail)
+                    } else {
+                        HealthCheck::fail("sweeper", detail)
+                    }
+                }
+            }
+            .on_chain(chain_id),
+        );
+
+        checks.push(
+            match faucet_balance {
+                Err(e) => HealthCheck::degraded("faucet_balance", e.to_string()),
+                Ok(balance) => match alloy::primitives::U256::from_str_radix(
+                    &chain.config.faucet_min_balance,
+                    10,
+                ) {
+                    Err(e) => HealthCheck::degraded(
+                        "faucet_balance",
+                        format!("invalid FAUCET_MIN_BALANCE: {}", e),
+                    ),
+                    Ok(min) => {
+                        let detail = format!("{} wei (minimum {} wei)", balance, min);
+                        if balance >= min {
+                            HealthCheck::pass("faucet_balance", detail)
+                        } else {
+                            HealthCheck::degraded("faucet_balance", detail)
+                        }
+                    }
+                },
+            }
+            .on_chain(chain_id),
+        );
+
+        checks
+    }
+
+    /// The chain `chain_id` names, or the default chain if it is None
+    fn chain(&self, chain_id: Option<u64>) -> ServiceResult<&Chain<P>> {
+        match chain_id {
+            None => Ok(&self.chains[0]),
+            Some(id) => self
+                .chains
+                .iter()
+                .find(|c| c.id() == id)
+                .ok_or(ServiceError::UnknownChain(id)),
+        }
+    }
+
+    /// Set the last processed block number of a chain (the default chain if None) manually.
+    /// Returns the chain id it was set for.
+    pub async fn set_block_number(
+        &self,
+        chain_id: Option<u64>,
+        block_number: u64,
+    ) -> ServiceResult<u64> {
+        let chain_id = self.chain(chain_id)?.id();
+        self.db
+            .set_last_processed_block(chain_id, block_number)
+            .await?;
+        Ok(chain_id)
+    }
+
+    /// Get the last processed block number of a chain (the default chain if None),
+    /// as (chain id, block number)
+    pub async fn get_block_number(&self, chain_id: Option<u64>) -> ServiceResult<(u64, u64)> {
+        let chain_id = self.chain(chain_id)?.id();
+        let block_number = self.db.get_last_processed_block(chain_id).await?;
+        Ok((chain_id, block_number))
+    }
+
+    /// Get an account by its registration id
+    pub async fn get_account(&self, id: &str) -> ServiceResult<AccountResponse> {
+        let Some((_index, address, webhook_url)) = self.db.get_account_by_id(id).await? else {
+            return Err(ServiceError::AccountNotFound(id.to_string()));
+        };
+
+        let status = self.db.get_account_status(id).await?;
+        let metadata = self
+            .db
+            .get_account_metadata(id)
+            .await?
+            .map(|m| serde_json::from_str(&m))
+            .transpose()
+            .map_err(anyhow::Error::from)?;
+
+        let chains = self.chain_addresses(id, &address).await?;
+
+        Ok(AccountResponse {
+            id: id.to_string(),
+            address,
+            chains,
+            webhook_url,
+            status: status.as_str().to_string(),
+            metadata,
+        })
+    }
+
+    /// The chains an account's address is watched on
+    async fn chain_addresses(&self, id: &str, address: &str) -> anyhow::Result<Vec<ChainAddress>> {
+        Ok(self
+            .db
+            .get_account_chains(id)
+            .await?
+            .into_iter()
+            .map(|chain_id| ChainAddress {
+                chain_id,
+                address: address.to_string(),
+            })
+            .collect())
+    }
+
+    /// Update the webhook URL and/or metadata of an account
+    pub async fn update_account(
+        &self,
+        id: &str,
+        request: UpdateAccountRequest,
+    ) -> ServiceResult<AccountResponse> {
+        if let Some(webhook_url) = &request.webhook_url {
+            validation::check_webhook_url(webhook_url, &self.config.webhook_allowed_hosts)?;
+        }
+        let metadata = request
+            .metadata
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(anyhow::Error::from)?;
+
+        if !self
+            .db
+            .update_account(id, request.webhook_url.as_deref(), metadata.as_deref())
+            .await?
+        {
+            return Err(ServiceError::AccountNotFound(id.to_string()));
+        }
+
+        tracing::info!("Updated account {}", id);
+        self.get_account(id).await
+    }
+
+    /// Deactivate an account so the Monitor stops crediting deposits to it.
+    /// Deposits that still arrive are swept to the treasury without notifications.
+    pub async fn deactivate_account(&self, id: &str) -> ServiceResult<AccountResponse> {
+        if !self.db.deactivate_account(id).await? {
+            return Err(ServiceError::AccountNotFound(id.to_string()));
+        }
+
+        tracing::info!("Deactivated account {}", id);
+        self.get_account(id).await
+    }
+
+    /// List deposits across all accounts, optionally filtered by status and token
+    pub async fn list_deposits(&self, query: DepositQuery) -> ServiceResult<DepositListResponse> {
+        self.query_deposits(None, query).await
+    }
+
+    /// List the deposits of an account, oldest first
+    pub async fn list_account_deposits(
+        &self,
+        id: &str,
+        query: DepositQuery,
+    ) -> ServiceResult<DepositListResponse> {
+        if self.db.get_account_by_id(id).await?.is_none() {
+            return Err(ServiceError::AccountNotFound(id.to_string()));
+        }
+        self.query_deposits(Some(id.to_string()), query).await
+    }
+
+    /// Get all deposits (native and ERC20) recorded for a transaction hash on `chain_id`, or
+    /// on every configured chain if None; at least one
+    pub async fn get_deposits_by_tx_hash(
+        &self,
+        chain_id: Option<u64>,
+        tx_hash: &str,
+    ) -> ServiceResult<Vec<DepositResponse>> {
+        let chain_ids: Vec<u64> = match chain_id {
+            Some(chain_id) => vec![self.chain(Some(chain_id))?.id()],
+            None => self.chains.iter().map(|c| c.id()).collect(),
+        };
+        let mut deposits = Vec::new();
+        for chain_id in chain_ids {
+            deposits.extend(self.db.get_deposits_by_tx_hash(chain_id, tx_hash).await?);
+        }
+        if deposits.is_empty() {
+            return Err(ServiceError::DepositsNotFound(tx_hash.to_string()));
+        }
+        Ok(deposits.into_iter().map(DepositResponse::from).collect())
+    }
+
+    /// Export a consistent snapshot of everything in storage as JSON lines, while the service keeps running
+    pub async fn export(&self) -> anyhow::Result<Vec<u8>> {
+        let mut snapshot = Vec::new();
+        let summary = self.db.export(&mut snapshot).await?;
+        tracing::info!("Exported {}", summary);
+        Ok(snapshot)
+    }
+
+    async fn query_deposits(
+        &self,
+        account_id: Option<String>,
+        query: DepositQuery,
+    ) -> ServiceResult<DepositListResponse> {
+        let filter = db::DepositFilter {
+            account_id,
+            chain_id: query.chain_id,
+            status: query.status,
+            token: query.token,
+        };
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_PAGE_LIMIT)
+            .clamp(1, MAX_PAGE_LIMIT);
+
+        let page = self
+            .db
+            .list_deposits(&filter, query.cursor.as_deref(), limit)
+            .await?;
+
+        Ok(DepositListResponse {
+            deposits: page
+                .deposits
+                .into_iter()
+                .map(DepositResponse::from)
+                .collect(),
+            next_cursor: page.next_cursor,
+        })
     }
 
     /// Verify if a transaction contains a transfer matching the expected criteria
     pub async fn verify_transfer(
         &self,
         request: VerifyTransferRequest,
-    ) -> anyhow::Result<VerifyTransferResponse> {
+    ) -> ServiceResult<VerifyTransferResponse> {
         use alloy::primitives::{Address, FixedBytes, U256};
         use std::str::FromStr;
         use tracing::info;
 
         info!("Verifying transfer: {:?}", request);
 
+        let chain = self.chain(request.chain_id)?;
+
         // Parse the transaction hash
         let tx_hash: FixedBytes<32> = request
             .tx_hash
             .parse()
-            .map_err(|_| anyhow::anyhow!("Invalid transaction hash format"))?;
+            .map_err(|_| ServiceError::invalid("Invalid transaction hash format"))?;
 
         // Parse expected values
         let expected_to = Address::from_str(&request.to_address)
-            .map_err(|_| anyhow::anyhow!("Invalid to_address format"))?;
+            .map_err(|_| ServiceError::invalid("Invalid to_address format"))?;
         let expected_amount = U256::from_str(&request.amount)
-            .map_err(|_| anyhow::anyhow!("Invalid amount format"))?;
+            .map_err(|_| ServiceError::invalid("Invalid amount format"))?;
 
         // Determine if this is a native or ERC20 transfer based on token_type
         let is_native = request.token_type.to_lowercase() == "native";
 
         if is_native {
             // Verify native ETH transfer
-            self.verify_native_transfer(tx_hash, expected_to, expected_amount)
-                .await
+            Self::verify_native_transfer(chain, tx_hash, expected_to, expected_amount).await
         } else {
             // Verify ERC20 transfer - token_address is required
-            let token_address_str = request
-                .token_address
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("token_address is required for ERC20 transfers"))?;
+            let token_address_str = request.token_address.as_ref().ok_or_else(|| {
+                ServiceError::invalid("token_address is required for ERC20 transfers")
+            })?;
 
             let token_address = Address::from_str(token_address_str)
-                .map_err(|_| anyhow::anyhow!("Invalid token_address format"))?;
+                .map_err(|_| ServiceError::invalid("Invalid token_address format"))?;
 
-            self.verify_erc20_transfer(
+            Self::verify_erc20_transfer(
+                chain,
                 tx_hash,
                 expected_to,
                 expected_amount,
@@ -189,31 +1221,40 @@ where
     }
 
     async fn verify_native_transfer(
-        &self,
+        chain: &Chain<P>,
         tx_hash: alloy::primitives::FixedBytes<32>,
         expected_to: alloy::primitives::Address,
         expected_amount: alloy::primitives::U256,
-    ) -> anyhow::Result<VerifyTransferResponse> {
-        use alloy::providers::Provider;
+    ) -> ServiceResult<VerifyTransferResponse> {
         use tracing::info;
 
+        let chain_id = chain.id();
+
         // Fetch the transaction
-        let tx = self
-            .provider
-            .get_transaction_by_hash(tx_hash)
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Transaction not found"))?;
+        let tx = metrics::observe_rpc(
+            "eth_getTransactionByHash",
+            chain.provider.get_transaction_by_hash(tx_hash),
+        )
+        .await
+        .map_err(|e| ServiceError::node(chain_id, e))?
+        .ok_or_else(|| ServiceError::TransactionNotFound(tx_hash.to_string()))?;
 
         info!("Found transaction: {:?}", tx.hash);
 
         // Get block number from transaction receipt for confirmation
-        let receipt = self.provider.get_transaction_receipt(tx_hash).await?;
+        let receipt = metrics::observe_rpc(
+            "eth_getTransactionReceipt",
+            chain.provider.get_transaction_receipt(tx_hash),
+        )
+        .await
+        .map_err(|e| ServiceError::node(chain_id, e))?;
         let block_number = receipt.as_ref().and_then(|r| r.block_number);
 
         // Check if transaction was successful
         if let Some(ref r) = receipt {
             if !r.status() {
                 return Ok(VerifyTransferResponse::Error {
+                    chain_id,
                     message: "Transaction failed (reverted)".to_string(),
                     token_type: Some("native".to_string()),
                     block_number,
@@ -235,6 +1276,7 @@ where
 
         if to_matches && amount_matches {
             Ok(VerifyTransferResponse::Success {
+                chain_id,
                 actual_to: actual_to.map(|a| a.to_string()).unwrap_or_default(),
                 actual_amount: actual_amount.to_string(),
                 token_type: "native".to_string(),
@@ -243,6 +1285,7 @@ where
             })
         } else {
             Ok(VerifyTransferResponse::Error {
+                chain_id,
                 message: format!(
                     "Mismatch: to_matches={}, amount_matches={} (expected >= {})",
                     to_matches, amount_matches, expected_amount
@@ -254,29 +1297,33 @@ where
     }
 
     async fn verify_erc20_transfer(
-        &self,
+        chain: &Chain<P>,
         tx_hash: alloy::primitives::FixedBytes<32>,
         expected_to: alloy::primitives::Address,
         expected_amount: alloy::primitives::U256,
         token_address: alloy::primitives::Address,
         expected_symbol: Option<&str>,
-    ) -> anyhow::Result<VerifyTransferResponse> {
+    ) -> ServiceResult<VerifyTransferResponse> {
         use alloy::primitives::{Address, FixedBytes, U256};
-        use alloy::providers::Provider;
         use tracing::info;
 
+        let chain_id = chain.id();
+
         // Fetch the transaction receipt to get logs
-        let receipt = self
-            .provider
-            .get_transaction_receipt(tx_hash)
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Transaction receipt not found"))?;
+        let receipt = metrics::observe_rpc(
+            "eth_getTransactionReceipt",
+            chain.provider.get_transaction_receipt(tx_hash),
+        )
+        .await
+        .map_err(|e| ServiceError::node(chain_id, e))?
+        .ok_or_else(|| ServiceError::TransactionNotFound(tx_hash.to_string()))?;
 
         let block_number = receipt.block_number;
 
         // Check if transaction was successful
         if !receipt.status() {
             return Ok(VerifyTransferResponse::Error {
+                chain_id,
                 message: "Transaction failed (reverted)".to_string(),
                 token_type: Some("erc20".to_string()),
                 block_number,
@@ -284,13 +1331,14 @@ where
         }
 
         // Fetch token symbol from chain if we need to validate it
-        let actual_symbol = self.fetch_token_symbol(token_address).await.ok();
+        let actual_symbol = Self::fetch_token_symbol(chain, token_address).await.ok();
 
         // Validate token symbol if provided
         if let Some(expected) = expected_symbol {
             if let Some(ref actual) = actual_symbol {
                 if !actual.eq_ignore_ascii_case(expected) {
                     return Ok(VerifyTransferResponse::Error {
+                        chain_id,
                         message: format!(
                             "Token symbol mismatch: expected '{}', got '{}'",
                             expected, actual
@@ -341,6 +1389,7 @@ where
 
             if to_matches && amount_matches {
                 return Ok(VerifyTransferResponse::Success {
+                    chain_id,
                     actual_to: to_address.to_string(),
                     actual_amount: amount.to_string(),
                     token_type: "erc20".to_string(),
@@ -352,6 +1401,7 @@ where
 
         // No matching transfer found
         Ok(VerifyTransferResponse::Error {
+            chain_id,
             message: format!(
                 "No matching ERC20 Transfer event found to {} with amount >= {}",
                 expected_to, expected_amount
@@ -363,7 +1413,7 @@ where
 
     /// Fetch token symbol from the blockchain
     async fn fetch_token_symbol(
-        &self,
+        chain: &Chain<P>,
         token_address: alloy::primitives::Address,
     ) -> anyhow::Result<String> {
         use alloy::sol;
@@ -375,336 +1425,308 @@ where
             }
         }
 
-        let contract = IERC20Symbol::new(token_address, &self.provider);
-        let symbol = contract.symbol().call().await?._0;
+        let contract = IERC20Symbol::new(token_address, &chain.provider);
+        let symbol = metrics::observe_rpc("eth_call", contract.symbol().call())
+            .await?
+            ._0;
         Ok(symbol)
     }
 
-    /// Register a new account with the hot wallet service
-    /// Returns the derived address and optionally a funding transaction hash
-    pub async fn register(&self, request: RegisterRequest) -> anyhow::Result<RegisterResponse> {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        use tracing::{error, info};
+    /// Register a new account with the hot wallet service, watching its address on the
+    /// requested chains (every chain by default). Registering an existing account again
+    /// adds the chains it is not watched on yet.
+    /// Returns the derived address; faucet funding is queued per new chain
+    pub async fn register(&self, request: RegisterRequest) -> ServiceResult<RegisterResponse> {
+        let account = self.new_account(&request)?;
+        // Checks for an existing account in the same transaction as the insert, so
+        // concurrent registrations of one id can't both create it
+        let registration = self
+            .db
+            .register_or_add_chains(
+                &account.id,
+                account.index,
+                &account.address,
+                &account.webhook_url,
+                &account.chain_ids,
+            )
+            .await?;
+        let registered = self.finish_registration(account, registration).await?;
+        Ok(RegisterResponse {
+            address: registered.address,
+            funding_tx: None, // No longer waiting for funding - it's queued
+            chains: registered.chains,
+        })
+    }
 
-        // Check if account already exists
-        if let Ok(Some((_index, existing_address, _webhook))) =
-            self.db.get_account_by_id(&request.id)
-        {
-            info!(
-                "Account {} already exists with address {}",
-                request.id, existing_address
-            );
-            return Ok(RegisterResponse {
-                address: existing_address,
-                funding_tx: None,
-            });
+    /// Register up to [`MAX_BATCH_SIZE`] accounts in one transaction: either all of them
+    /// are registered, or none are. Each entry behaves like a [`register`](Self::register)
+    /// request; fundings are queued and sent at the configured rate.
+    pub async fn register_batch(
+        &self,
+        request: RegisterBatchRequest,
+    ) -> ServiceResult<RegisterBatchResponse> {
+        if request.accounts.is_empty() {
+            return Err(ServiceError::invalid("accounts must not be empty"));
+        }
+        if request.accounts.len() > MAX_BATCH_SIZE {
+            return Err(ServiceError::invalid(format!(
+                "accounts must hold at most {} entries",
+                MAX_BATCH_SIZE
+            )));
         }
 
-        // Derive deterministic index from account_id using hash
-        let mut hasher = DefaultHasher::new();
-        request.id.hash(&mut hasher);
-        let hash = hasher.finish();
-        let index = (hash & 0x7FFFFFFF) as u32;
-
-        // Derive address from the deterministic index
-        let address = self.wallet.derive_address(index)?;
-        let address_str = address.to_string();
-
-        // Save to DB with webhook URL
-        self.db
-            .register_account(&request.id, index, &address_str, &request.webhook_url)?;
-
-        info!(
-            "Registered account {} with address {} (index: {})",
-            request.id, address_str, index
-        );
-
-        // Fire-and-forget: Fund the new address with existential deposit in the background
-        let faucet = Arc::clone(&self.faucet);
-        let db = self.db.clone();
-        let account_id = request.id.clone();
-        let address_for_funding = address_str.clone();
-        let webhook_jwt_token = self.config.webhook_jwt_token.clone();
-
-        tokio::spawn(async move {
-            info!(
-                "Background task: Starting faucet funding for address {}",
-                address_for_funding
-            );
-
-            match faucet.fund_new_address(&address_for_funding).await {
-                Ok(tx_hash) => {
-                    info!(
-                        "Successfully funded address {} with tx: {}",
-                        address_for_funding, tx_hash
-                    );
-
-                    // Send webhook notification for successful funding
-                    if let Err(e) = send_faucet_funding_webhook(
-                        &db,
-                        &account_id,
-                        &address_for_funding,
-                        &tx_hash,
-                        true,
-                        None,
-                        webhook_jwt_token.as_deref(),
-                    )
-                    .await
-                    {
-                        error!(
-                            "Failed to send faucet funding webhook for {}: {:?}",
-                            account_id, e
-                        );
-                    }
+        let mut accounts = Vec::with_capacity(request.accounts.len());
+        let mut ids = std::collections::HashSet::new();
+        for (i, entry) in request.accounts.iter().enumerate() {
+            let account = self.new_account(entry).map_err(|e| match e {
+                ServiceError::InvalidRequest(message) => {
+                    ServiceError::InvalidRequest(format!("accounts[{}]: {}", i, message))
                 }
-                Err(e) => {
-                    error!("Failed to fund address {}: {:?}", address_for_funding, e);
-
-                    // Send webhook notification for failed funding
-                    if let Err(webhook_err) = send_faucet_funding_webhook(
-                        &db,
-                        &account_id,
-                        &address_for_funding,
-                        "",
-                        false,
-                        Some(&e.to_string()),
-                        webhook_jwt_token.as_deref(),
-                    )
-                    .await
-                    {
-                        error!(
-                            "Failed to send faucet funding error webhook for {}: {:?}",
-                            account_id, webhook_err
-                        );
-                    }
+                ServiceError::WebhookUrlNotAllowed(message) => {
+                    ServiceError::WebhookUrlNotAllowed(format!("accounts[{}]: {}", i, message))
                 }
+                other => other,
+            })?;
+            if !ids.insert(account.id.clone()) {
+                return Err(ServiceError::invalid(format!(
+                    "accounts[{}]: id {} is listed more than once",
+                    i, account.id
+                )));
             }
-        });
-
-        Ok(RegisterResponse {
-            address: address_str,
-            funding_tx: None, // No longer waiting for funding - it's fire-and-forget
-        })
-    }
-}
-
-// HTTP Provider implementation
-impl HotWalletService<alloy::transports::http::Http<reqwest::Client>> {
-    /// Create a new HotWalletService with HTTP provider from configuration
-    pub async fn new_http(config: Config) -> anyhow::Result<Self> {
-        let db = Db::new(&config.database_url)?;
-        let wallet = Wallet::new(config.mnemonic.clone());
-
-        let url = match &config.provider_url {
-            ProviderUrl::Http(url) => url,
-            _ => return Err(anyhow::anyhow!("Expected HTTP provider URL")),
-        };
+            accounts.push(account);
+        }
 
-        let provider = ProviderBuilder::new().on_http(url.parse()?);
-        let faucet = Faucet::new(
-            config.faucet_mnemonic.clone(),
-            provider.clone(),
-            &config.existential_deposit,
-        )?;
+        let registrations = self.db.register_accounts(&accounts).await?;
+        // Nothing was written if an account is deactivated
+        if let Some(i) = registrations
+            .iter()
+            .position(|r| *r == Registration::Deactivated)
+        {
+            return Err(ServiceError::AccountDeactivated(accounts[i].id.clone()));
+        }
 
-        Ok(Self {
-            config,
-            db,
-            wallet,
-            faucet: Arc::new(faucet),
-            provider,
+        let mut registered = Vec::with_capacity(accounts.len());
+        for (account, registration) in accounts.into_iter().zip(registrations) {
+            registered.push(self.finish_registration(account, registration).await?);
+        }
+        tracing::info!("📋 Registered a batch of {} accounts", registered.len());
+        Ok(RegisterBatchResponse {
+            accounts: registered,
         })
     }
 
-    /// Start background services (Monitor and Sweeper) for HTTP provider
-    /// Returns immediately after spawning the background tasks
-    pub async fn start_background_services(&self) -> anyhow::Result<()> {
-        let url = match &self.config.provider_url {
-            ProviderUrl::Http(url) => url,
-            _ => return Err(anyhow::anyhow!("Expected HTTP provider URL")),
-        };
-
-        let provider = ProviderBuilder::new().on_http(url.parse()?);
+    /// Validate a registration request and derive the account's address
+    fn new_account(&self, request: &RegisterRequest) -> ServiceResult<NewAccount> {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
 
-        // Spawn Monitor
-        tokio::spawn({
-            let config = self.config.clone();
-            let db = self.db.clone();
-            let provider = provider.clone();
+        validation::check_account_id(&request.id)?;
+        validation::check_webhook_url(&request.webhook_url, &self.config.webhook_allowed_hosts)?;
 
-            async move {
-                tracing::info!("Starting Monitor in Polling mode");
-                Monitor::new(config, db, provider).run().await;
+        let chain_ids = match &request.chain_ids {
+            Some(ids) if ids.is_empty() => {
+                return Err(ServiceError::invalid("chain_ids must not be empty"))
             }
-        });
-
-        // Create faucet for sweeper
-        let sweeper_faucet = Arc::new(Faucet::new(
-            self.config.faucet_mnemonic.clone(),
-            provider.clone(),
-            &self.config.existential_deposit,
-        )?);
-
-        // Spawn Sweeper
-        tokio::spawn({
-            let config = self.config.clone();
-            let db = self.db.clone();
-            let wallet = self.wallet.clone();
-            let provider = provider.clone();
-            let faucet = sweeper_faucet;
-            async move {
-                tracing::info!("Starting Sweeper in Polling mode");
-                Sweeper::new(config, db, wallet, provider, faucet)
-                    .run()
-                    .await;
+            Some(ids) => {
+                let mut chain_ids = Vec::with_capacity(ids.len());
+                for id in ids {
+                    let chain_id = self.chain(Some(*id))?.id();
+                    if !chain_ids.contains(&chain_id) {
+                        chain_ids.push(chain_id);
+                    }
+                }
+                chain_ids
             }
-        });
-
-        Ok(())
-    }
-}
-
-// WebSocket Provider implementation
-impl HotWalletService<alloy::pubsub::PubSubFrontend> {
-    /// Create a new HotWalletService with WebSocket provider from configuration
-    pub async fn new_ws(config: Config) -> anyhow::Result<Self> {
-        let db = Db::new(&config.database_url)?;
-        let wallet = Wallet::new(config.mnemonic.clone());
-
-        let url = match &config.provider_url {
-            ProviderUrl::Ws(url) => url,
-            _ => return Err(anyhow::anyhow!("Expected WebSocket provider URL")),
+            None => self.chains.iter().map(|c| c.id()).collect(),
         };
 
-        let provider = ProviderBuilder::new().on_ws(WsConnect::new(url)).await?;
-        let faucet = Faucet::new(
-            config.faucet_mnemonic.clone(),
-            provider.clone(),
-            &config.existential_deposit,
-        )?;
+        // Derive deterministic index from account_id using hash
+        let mut hasher = DefaultHasher::new();
+        request.id.hash(&mut hasher);
+        let hash = hasher.finish();
+        let index = (hash & 0x7FFFFFFF) as u32;
 
-        Ok(Self {
-            config,
-            db,
-            wallet,
-            faucet: Arc::new(faucet),
-            provider,
+        // Derive address from the deterministic index
+        let address = self.wallet.derive_address(index)?.to_string();
+
+        Ok(NewAccount {
+            id: request.id.clone(),
+            index,
+            address,
+            webhook_url: request.webhook_url.clone(),
+            chain_ids,
         })
     }
 
-    /// Start background services (Monitor and Sweeper) for WebSocket provider
-    /// Returns immediately after spawning the background tasks
-    pub async fn start_background_services(&self) -> anyhow::Result<()> {
-        let url = match &self.config.provider_url {
-            ProviderUrl::Ws(url) => url,
-            _ => return Err(anyhow::anyhow!("Expected WebSocket provider URL")),
-        };
-
-        let provider = ProviderBuilder::new().on_ws(WsConnect::new(url)).await?;
+    /// Queue the fundings a stored registration needs and describe the account
+    async fn finish_registration(
+        &self,
+        account: NewAccount,
+        registration: Registration,
+    ) -> ServiceResult<RegisteredAccount> {
+        use tracing::info;
 
-        // Spawn Monitor
-        tokio::spawn({
-            let config = self.config.clone();
-            let db = self.db.clone();
-            let provider = provider.clone();
-            async move {
-                tracing::info!("Starting Monitor in Streaming mode");
-                Monitor::new(config, db, provider).run().await;
+        let (address, created) = match registration {
+            Registration::Created => {
+                info!(
+                    "Registered account {} with address {} (index: {}) on chains {:?}",
+                    account.id, account.address, account.index, account.chain_ids
+                );
+                if self.config.funding_policy == FundingPolicy::Eager {
+                    self.queue_funding(&account.id, &account.address, &account.chain_ids)
+                        .await?;
+                }
+                (account.address, true)
+            }
+            Registration::Existing { address, added } => {
+                if added.is_empty() {
+                    info!(
+                        "Account {} already exists with address {}",
+                        account.id, address
+                    );
+                } else {
+                    info!(
+                        "Account {} with address {} now watched on chains {:?}",
+                        account.id, address, added
+                    );
+                    if self.config.funding_policy == FundingPolicy::Eager {
+                        self.queue_funding(&account.id, &address, &added).await?;
+                    }
+                }
+                (address, false)
             }
-        });
-
-        // Create faucet for sweeper
-        let sweeper_faucet = Arc::new(Faucet::new(
-            self.config.faucet_mnemonic.clone(),
-            provider.clone(),
-            &self.config.existential_deposit,
-        )?);
-
-        // Spawn Sweeper
-        tokio::spawn({
-            let config = self.config.clone();
-            let db = self.db.clone();
-            let wallet = self.wallet.clone();
-            let provider = provider.clone();
-            let faucet = sweeper_faucet;
-            async move {
-                tracing::info!("Starting Sweeper in Streaming mode");
-                Sweeper::new(config, db, wallet, provider, faucet)
-                    .run()
-                    .await;
+            Registration::Deactivated => {
+                return Err(ServiceError::AccountDeactivated(account.id));
             }
-        });
+        };
 
-        Ok(())
+        let chains = if created {
+            // Exactly the requested chains, so the storage needn't be asked
+            let mut chain_ids = account.chain_ids;
+            chain_ids.sort_unstable();
+            chain_ids
+                .into_iter()
+                .map(|chain_id| ChainAddress {
+                    chain_id,
+                    address: address.clone(),
+                })
+                .collect()
+        } else {
+            self.chain_addresses(&account.id, &address).await?
+        };
+        Ok(RegisteredAccount {
+            id: account.id,
+            address,
+            chains,
+            created,
+        })
     }
-}
-
-/// Send webhook notification for faucet funding event
-/// registration_id: The original id used when registering the account
-/// address: The Polygon address (account_id in webhook)
-/// jwt_token: Optional JWT token for authorization header
-async fn send_faucet_funding_webhook(
-    db: &Db,
-    registration_id: &str,
-    address: &str,
-    tx_hash: &str,
-    success: bool,
-    error_message: Option<&str>,
-    jwt_token: Option<&str>,
-) -> anyhow::Result<()> {
-    use tracing::{error, info};
-
-    // Get the webhook URL using registration_id (the key in ACCOUNTS table)
-    let Some(webhook_url) = db.get_webhook_url(registration_id)? else {
-        error!(
-            "No webhook URL found for registration_id: {}",
-            registration_id
-        );
-        return Ok(());
-    };
-
-    let client = reqwest::Client::new();
 
-    let mut payload = serde_json::json!({
-        "event": "faucet_funding",
-        "account_id": address,
-        "registration_id": registration_id,
-        "success": success,
-        "id": format!("{}:funding", registration_id)
-    });
+    /// [`register`](Self::register) with an idempotency key: the first successful response
+    /// for `key` is stored for [`IDEMPOTENCY_TTL_SECS`] and returned again when the same
+    /// request is retried with the key, without registering twice. `scope` keeps the keys
+    /// of different clients apart, e.g. the id of their API key.
+    ///
+    /// Returns the response and whether it is a replay. Reusing a key for a different
+    /// request fails with [`ServiceError::IdempotencyKeyReused`].
+    pub async fn register_idempotent(
+        &self,
+        scope: &str,
+        key: &str,
+        request: RegisterRequest,
+    ) -> ServiceResult<(RegisterResponse, bool)> {
+        use sha2::{Digest, Sha256};
+
+        validation::check_idempotency_key(key)?;
+        let stored_key = format!("{}:{}", scope, key);
+        let request_hash = hex::encode(Sha256::digest(
+            serde_json::to_vec(&request).map_err(anyhow::Error::from)?,
+        ));
+        let now = deposit::now_secs();
+        let expired_before = now.saturating_sub(IDEMPOTENCY_TTL_SECS);
+
+        if let Some(record) = self.db.get_idempotency_record(&stored_key).await? {
+            if record.created_at >= expired_before {
+                return replay(key, &request_hash, &record);
+            }
+        }
 
-    // Add tx_hash if funding was successful
-    if success && !tx_hash.is_empty() {
-        payload["tx_hash"] = serde_json::json!(tx_hash);
+        let response = self.register(request).await?;
+        let record = IdempotencyRecord {
+            key: stored_key,
+            request_hash: request_hash.clone(),
+            response: serde_json::to_string(&response).map_err(anyhow::Error::from)?,
+            created_at: now,
+        };
+        let stored = self
+            .db
+            .save_idempotency_record(&record, expired_before)
+            .await?;
+        if stored == record {
+            Ok((response, false))
+        } else {
+            // A concurrent request with the same key finished first
+            replay(key, &request_hash, &stored)
+        }
     }
 
-    // Add error message if funding failed
-    if let Some(error) = error_message {
-        payload["error"] = serde_json::json!(error);
+    /// Queue funding `address` with the existential deposit of each of `chain_ids`
+    async fn queue_funding(
+        &self,
+        account_id: &str,
+        address: &str,
+        chain_ids: &[u64],
+    ) -> anyhow::Result<()> {
+        for &chain_id in chain_ids {
+            self.chain(Some(chain_id))?
+                .funding
+                .push(FundingJob {
+                    account_id: account_id.to_string(),
+                    address: address.to_string(),
+                    reason: FundingReason::Registration,
+                })
+                .await?;
+        }
+        Ok(())
     }
+}
 
-    let mut request = client.post(&webhook_url).json(&payload);
-
-    // Add JWT authorization header if provided
-    if let Some(token) = jwt_token {
-        request = request.header("Authorization", format!("Bearer {}", token));
+impl HotWalletService {
+    /// Create a new HotWalletService from configuration, connecting to each chain's node
+    /// over the transport its `provider_url` selects
+    pub async fn new(config: Config) -> anyhow::Result<Self> {
+        let mut providers = Vec::with_capacity(config.chains.len());
+        for chain in &config.chains {
+            providers.push(connect(&chain.provider_url).await?);
+        }
+        Self::with_providers(config, providers).await
     }
+}
 
-    let res = request.send().await;
+/// Connect to the node at `provider_url`, boxing the transport so every service shares
+/// one provider type whatever the connection is
+pub async fn connect(provider_url: &ProviderUrl) -> anyhow::Result<RootProvider<BoxTransport>> {
+    let provider = match provider_url {
+        ProviderUrl::Ipc(path) => ProviderBuilder::new()
+            .on_ipc(IpcConnect::new(std::path::PathBuf::from(path)))
+            .await
+            .map(|provider| provider.boxed()),
+        ProviderUrl::Http(url) | ProviderUrl::Ws(url) => {
+            ProviderBuilder::new().on_builtin(url).await
+        }
+    };
+    provider.map_err(|e| anyhow::anyhow!("Failed to connect to {}: {}", provider_url.as_str(), e))
+}
 
-    match res {
-        Ok(r) => info!(
-            "Faucet funding webhook sent to {}: status={}, registration_id={}",
-            webhook_url,
-            r.status(),
-            registration_id
-        ),
-        Err(e) => error!(
-            "Failed to send faucet funding webhook to {}: {:?}",
-            webhook_url, e
-        ),
+/// The stored response for an idempotency key, if it was stored for the same request
+fn replay(
+    key: &str,
+    request_hash: &str,
+    record: &IdempotencyRecord,
+) -> ServiceResult<(RegisterResponse, bool)> {
+    if record.request_hash != request_hash {
+        return Err(ServiceError::IdempotencyKeyReused(key.to_string()));
     }
-
-    Ok(())
+    let response = serde_json::from_str(&record.response).map_err(anyhow::Error::from)?;
+    Ok((response, true))
 }
@@ -1,4 +1,6 @@
-use evm_hot_wallet::{config::Config, HotWalletService};
+use evm_hot_wallet::auth::{self, ApiKey};
+use evm_hot_wallet::config::{Config, ConfigErrors};
+use evm_hot_wallet::{storage, HotWalletService};
 
 mod api;
 
@@ -17,29 +19,118 @@ async fn main() -> anyhow::Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let config = Config::from_env()?;
+    // --migrate-only upgrades the database schema and exits; add --dry-run to
+    // run the migrations in a transaction that is rolled back afterwards
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // --config <file> reads settings from a TOML file (also CONFIG_FILE);
+    // environment variables override it
+    dotenvy::dotenv().ok();
+    let config_file = flag_value(&args, "--config")
+        .map(str::to_string)
+        .or_else(|| std::env::var("CONFIG_FILE").ok());
+    let config_file = config_file.as_deref().map(std::path::Path::new);
+
+    // `openapi` prints the OpenAPI document of the API, e.g. to generate a client
+    if args.first().map(String::as_str) == Some("openapi") {
+        use utoipa::OpenApi;
+        println!("{}", api::ApiDoc::openapi().to_pretty_json()?);
+        return Ok(());
+    }
+
+    // `config check` reports every configuration problem and exits
+    if args.first().map(String::as_str) == Some("config")
+        && args.get(1).map(String::as_str) == Some("check")
+    {
+        return check_config(config_file);
+    }
+
+    // `api-key create <name> --scopes register,read`, `api-key list`, and
+    // `api-key revoke <id>` manage the keys the API accepts
+    if args.first().map(String::as_str) == Some("api-key") {
+        return api_key_command(&Config::database_url(config_file)?, &args[1..]).await;
+    }
+
+    if args.iter().any(|arg| arg == "--migrate-only") {
+        let dry_run = args.iter().any(|arg| arg == "--dry-run");
+        return migrate(&Config::database_url(config_file)?, dry_run).await;
+    }
+
+    // --export <file> writes a JSON-lines snapshot ("-" for stdout);
+    // --import <file> loads one into an empty database
+    if let Some(path) = flag_value(&args, "--export") {
+        return export(&Config::database_url(config_file)?, path).await;
+    }
+    if let Some(path) = flag_value(&args, "--import") {
+        return import(&Config::database_url(config_file)?, path).await;
+    }
+
+    let config = Config::load(config_file)?;
 
     // Log configuration on startup
     tracing::info!("🚀 Starting EVM Hot Wallet");
     tracing::info!("📊 Database: {}", config.database_url);
 
-    match &config.provider_url {
-        evm_hot_wallet::config::ProviderUrl::Http(url) => {
-            tracing::info!("🌐 RPC Provider (HTTP): {}", url)
+    for chain in &config.chains {
+        if chain.chain_id == 0 {
+            tracing::info!("⛓️ Chain {} (id read from the node)", chain.name);
+        } else {
+            tracing::info!("⛓️ Chain {} (id {})", chain.name, chain.chain_id);
+        }
+        match &chain.provider_url {
+            evm_hot_wallet::config::ProviderUrl::Http(url) => {
+                tracing::info!("   🌐 RPC Provider (HTTP): {}", url)
+            }
+            evm_hot_wallet::config::ProviderUrl::Ws(url) => {
+                tracing::info!("   🌐 RPC Provider (WebSocket): {}", url)
+            }
+            evm_hot_wallet::config::ProviderUrl::Ipc(path) => {
+                tracing::info!("   🌐 RPC Provider (IPC): {}", path)
+            }
         }
-        evm_hot_wallet::config::ProviderUrl::Ws(url) => {
-            tracing::info!("🌐 RPC Provider (WebSocket): {}", url)
+        tracing::info!("   💰 Treasury Address: {}", chain.treasury_address);
+        tracing::info!(
+            "   ⚡ Existential Deposit: {} wei",
+            chain.existential_deposit
+        );
+        tracing::info!(
+            "   📦 Block Offset from Head: {} blocks",
+            chain.block_offset_from_head
+        );
+        if let Some(top_up) = &chain.gas_top_up {
+            tracing::info!(
+                "   ⛽ Gas Top-Up: shortfall + {}%, {} to {} wei",
+                top_up.margin_percent,
+                top_up.min,
+                top_up.max
+            );
+        }
+        if let Some(refill) = &chain.faucet_refill {
+            tracing::info!(
+                "   🚰 Faucet Refill: up to {} wei, at most {} wei a day",
+                refill.target,
+                refill.daily_cap
+            );
         }
     }
-    tracing::info!("💰 Treasury Address: {}", config.treasury_address);
     tracing::info!("🚰 Faucet Address: {}", config.faucet_address);
-    tracing::info!("⚡ Existential Deposit: {} wei", config.existential_deposit);
     tracing::info!("🔄 Poll Interval: {} seconds", config.poll_interval);
+    tracing::info!("🌐 API Port: {}", config.port);
     tracing::info!(
-        "📦 Block Offset from Head: {} blocks",
-        config.block_offset_from_head
+        "🚰 Funding Policy: {} ({} per second)",
+        config.funding_policy.as_str(),
+        config.funding_rate_per_sec
+    );
+    tracing::info!(
+        "🧹 Dust Reclaim: {} (every {} seconds)",
+        config.dust_reclaim.as_str(),
+        config.dust_reclaim_interval_secs
+    );
+    tracing::info!(
+        "🐶 Faucet Watchdog: every {} seconds, alerts {}",
+        config.faucet_watchdog_interval_secs,
+        config.alert_webhook_url.as_deref().unwrap_or("not sent")
     );
-    tracing::info!("🌐 API Port: {}", config.port);
     tracing::info!(
         "🔐 Webhook JWT Auth: {}",
         if config.webhook_jwt_token.is_some() {
@@ -51,29 +142,263 @@ async fn main() -> anyhow::Result<()> {
 
     let port = config.port;
 
-    match &config.provider_url {
-        evm_hot_wallet::config::ProviderUrl::Http(_) => {
-            // Create the service with HTTP provider
-            let service = HotWalletService::new_http(config).await?;
+    // Connects to each chain over the transport its provider URL selects
+    let service = HotWalletService::new(config).await?;
 
-            // Start background services
-            service.start_background_services().await?;
+    // Start background services
+    service.start_background_services().await?;
+
+    // Serve the API until SIGTERM or Ctrl-C, then shut down
+    serve(service, port, config_file.map(std::path::Path::to_path_buf)).await
+}
 
-            // Start API server (blocks forever)
-            api::start_server(service, port).await;
+/// Print every problem in the configuration, exiting with status 1 if there are any
+fn check_config(config_file: Option<&std::path::Path>) -> anyhow::Result<()> {
+    match Config::load(config_file) {
+        Ok(config) => {
+            let chains: Vec<_> = config.chains.iter().map(|c| c.name.as_str()).collect();
+            println!(
+                "✅ Configuration is valid ({} chain{}: {})",
+                chains.len(),
+                if chains.len() == 1 { "" } else { "s" },
+                chains.join(", ")
+            );
+            Ok(())
         }
+        Err(e) => match e.downcast_ref::<ConfigErrors>() {
+            Some(ConfigErrors(problems)) => {
+                println!("❌ {} configuration problem(s):", problems.len());
+                for problem in problems {
+                    println!("  - {}", problem);
+                }
+                std::process::exit(1);
+            }
+            None => Err(e),
+        },
+    }
+}
 
-        evm_hot_wallet::config::ProviderUrl::Ws(_) => {
-            // Create the service with WebSocket provider
-            let service = HotWalletService::new_ws(config).await?;
+const API_KEY_USAGE: &str = "Usage: api-key create <name> --scopes <register,read,admin> | api-key list | api-key revoke <id>";
 
-            // Start background services
-            service.start_background_services().await?;
+async fn api_key_command(database_url: &str, args: &[String]) -> anyhow::Result<()> {
+    let positional = |i: usize| args.get(i).filter(|arg| !arg.starts_with("--"));
+    let storage = storage::open(database_url).await?;
 
-            // Start API server (blocks forever)
-            api::start_server(service, port).await;
+    match args.first().map(String::as_str) {
+        Some("create") => {
+            let name = positional(1).ok_or_else(|| anyhow::anyhow!(API_KEY_USAGE))?;
+            let scopes =
+                flag_value(args, "--scopes").ok_or_else(|| anyhow::anyhow!(API_KEY_USAGE))?;
+            let (api_key, key) = ApiKey::generate(name, auth::parse_scopes(scopes)?);
+            storage.create_api_key(&api_key).await?;
+            println!(
+                "🔑 Created API key {} for {} with scopes {}",
+                api_key.id,
+                api_key.name,
+                scope_list(&api_key)
+            );
+            println!("{}", key);
+            println!("Store the key now; it cannot be shown again.");
+        }
+        Some("list") => {
+            for api_key in storage.list_api_keys().await? {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    api_key.id,
+                    api_key.name,
+                    scope_list(&api_key),
+                    if api_key.is_revoked() {
+                        "revoked"
+                    } else {
+                        "active"
+                    }
+                );
+            }
+        }
+        Some("revoke") => {
+            let id = positional(1).ok_or_else(|| anyhow::anyhow!(API_KEY_USAGE))?;
+            if !storage.revoke_api_key(id).await? {
+                return Err(anyhow::anyhow!("No API key with id {}", id));
+            }
+            println!("🚫 Revoked API key {}", id);
+        }
+        _ => return Err(anyhow::anyhow!(API_KEY_USAGE)),
+    }
+
+    storage.close().await
+}
+
+fn scope_list(api_key: &ApiKey) -> String {
+    api_key
+        .scopes
+        .iter()
+        .map(|scope| scope.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+async fn migrate(database_url: &str, dry_run: bool) -> anyhow::Result<()> {
+    let report = storage::migrate(database_url, dry_run).await?;
+
+    if report.applied.is_empty() {
+        tracing::info!(
+            "✅ Database {} is up to date (schema version {})",
+            database_url,
+            report.to_version
+        );
+        return Ok(());
+    }
+
+    for (version, description) in &report.applied {
+        tracing::info!("🗄️ Migration {}: {}", version, description);
+    }
+    if dry_run {
+        tracing::info!(
+            "🧪 Dry run: {} would be migrated from schema version {} to {}, no changes were written",
+            database_url,
+            report.from_version,
+            report.to_version
+        );
+    } else {
+        tracing::info!(
+            "✅ Migrated {} from schema version {} to {}",
+            database_url,
+            report.from_version,
+            report.to_version
+        );
+    }
+    Ok(())
+}
+
+/// Run the API server until a shutdown signal arrives, then stop the background
+/// services and close the database
+async fn serve(
+    service: HotWalletService,
+    port: u16,
+    config_file: Option<std::path::PathBuf>,
+) -> anyhow::Result<()> {
+    let service = std::sync::Arc::new(service);
+    let shutdown = service.shutdown_token();
+
+    // SIGHUP reloads the runtime-adjustable settings from the configuration
+    #[cfg(unix)]
+    tokio::spawn(reload_on_sighup(service.clone(), config_file));
+    #[cfg(not(unix))]
+    let _ = config_file;
+
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            wait_for_signal().await;
+            tracing::info!("🛑 Shutdown signal received, finishing in-flight work");
+            shutdown.cancel();
+        }
+    });
+
+    // Stops accepting requests once the token is cancelled
+    api::start_server(service.clone(), port, shutdown).await;
+
+    service.shutdown().await?;
+    tracing::info!("👋 Shutdown complete");
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn reload_on_sighup(
+    service: std::sync::Arc<HotWalletService>,
+    config_file: Option<std::path::PathBuf>,
+) {
+    let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+        Ok(signal) => signal,
+        Err(e) => {
+            tracing::error!("Failed to listen for SIGHUP: {}", e);
+            return;
         }
     };
 
+    while hangup.recv().await.is_some() {
+        tracing::info!("🔁 SIGHUP received, reloading settings");
+        let config = match Config::load(config_file.as_deref()) {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::error!("❌ Keeping the current settings: {}", e);
+                continue;
+            }
+        };
+        let pinned = config.runtime_env_overrides();
+        if !pinned.is_empty() {
+            tracing::warn!(
+                "🔁 Set in the environment, which wins over the configuration file; change these through the API instead: {}",
+                pinned.join(", ")
+            );
+        }
+        match service.reload_settings(&config, "sighup").await {
+            Ok(changes) => tracing::info!(
+                "🔁 Reloaded settings, {} changed; other changes take effect after a restart",
+                changes.len()
+            ),
+            Err(e) => tracing::error!("❌ Failed to apply reloaded settings: {}", e),
+        }
+    }
+}
+
+async fn wait_for_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+async fn export(database_url: &str, path: &str) -> anyhow::Result<()> {
+    let storage = storage::open(database_url).await?;
+
+    let summary = if path == "-" {
+        storage.export(&mut std::io::stdout()).await?
+    } else {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        storage.export(&mut file).await?
+    };
+
+    tracing::info!("📤 Exported {} to {}", summary, path);
+    Ok(())
+}
+
+async fn import(database_url: &str, path: &str) -> anyhow::Result<()> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let records = evm_hot_wallet::export::read_export(file)?;
+
+    let storage = storage::open(database_url).await?;
+    let summary = storage.import(&records).await?;
+
+    tracing::info!("📥 Imported {} into {}", summary, database_url);
     Ok(())
 }
@@ -0,0 +1,266 @@
+use anyhow::Result;
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use std::future::IntoFuture;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+/// Process-wide metrics, registered once and served at `GET /metrics`.
+/// Block, deposit, sweep, and faucet metrics are labelled by `chain_id`.
+pub struct Metrics {
+    registry: Registry,
+    /// Highest block fully processed by the Monitor
+    pub last_processed_block: IntGaugeVec,
+    /// Latest block number reported by the node
+    pub chain_head_block: IntGaugeVec,
+    /// Chain head minus last processed block
+    pub block_lag: IntGaugeVec,
+    pub blocks_processed: IntCounterVec,
+    /// New deposits by token symbol (`native` for ETH)
+    pub deposits_detected: IntCounterVec,
+    pub deposits_swept: IntCounterVec,
+    /// Failed sweep attempts by reason
+    pub sweep_failures: IntCounterVec,
+    pub faucet_balance_wei: GaugeVec,
+    /// Faucet fundings waiting in the funding queue
+    pub funding_queue_depth: IntGaugeVec,
+    /// Faucet fundings by reason and outcome (`sent`, `failed`, `in_flight`, or `cap_reached`)
+    pub faucet_fundings: IntCounterVec,
+    /// 1 while the faucet balance is below its minimum, as seen by the faucet watchdog
+    pub faucet_low_balance: IntGaugeVec,
+    /// Swept addresses checked for dust, by outcome (`sent`, `skipped`, or `failed`)
+    pub dust_reclaims: IntCounterVec,
+    /// Webhook deliveries by event and outcome (`success` or `failure`)
+    pub webhook_deliveries: IntCounterVec,
+    pub webhook_duration: HistogramVec,
+    /// RPC calls by method and outcome (`success` or `error`)
+    pub rpc_requests: IntCounterVec,
+    pub rpc_duration: HistogramVec,
+    /// Restarts of crashed background services
+    pub service_restarts: IntCounterVec,
+}
+
+static METRICS: LazyLock<Metrics> =
+    LazyLock::new(|| Metrics::new().expect("metric definitions are valid"));
+
+/// The global metrics
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+impl Metrics {
+    fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some("hot_wallet".to_string()), None)?;
+
+        let last_processed_block = IntGaugeVec::new(
+            Opts::new("last_processed_block", "Highest block fully processed"),
+            &["chain_id"],
+        )?;
+        let chain_head_block = IntGaugeVec::new(
+            Opts::new(
+                "chain_head_block",
+                "Latest block number reported by the node",
+            ),
+            &["chain_id"],
+        )?;
+        let block_lag = IntGaugeVec::new(
+            Opts::new(
+                "block_lag",
+                "Blocks between the chain head and the last processed block",
+            ),
+            &["chain_id"],
+        )?;
+        let blocks_processed = IntCounterVec::new(
+            Opts::new("blocks_processed_total", "Blocks scanned for deposits"),
+            &["chain_id"],
+        )?;
+        let deposits_detected = IntCounterVec::new(
+            Opts::new("deposits_detected_total", "New deposits detected"),
+            &["chain_id", "token"],
+        )?;
+        let deposits_swept = IntCounterVec::new(
+            Opts::new("deposits_swept_total", "Deposits swept to the treasury"),
+            &["chain_id", "token"],
+        )?;
+        let sweep_failures = IntCounterVec::new(
+            Opts::new("sweep_failures_total", "Failed sweep attempts"),
+            &["chain_id", "reason"],
+        )?;
+        let faucet_balance_wei = GaugeVec::new(
+            Opts::new("faucet_balance_wei", "Last observed faucet balance in wei"),
+            &["chain_id"],
+        )?;
+        let funding_queue_depth = IntGaugeVec::new(
+            Opts::new("funding_queue_depth", "Faucet fundings waiting to be sent"),
+            &["chain_id"],
+        )?;
+        let faucet_fundings = IntCounterVec::new(
+            Opts::new("faucet_fundings_total", "Faucet funding attempts"),
+            &["chain_id", "reason", "outcome"],
+        )?;
+        let faucet_low_balance = IntGaugeVec::new(
+            Opts::new(
+                "faucet_low_balance",
+                "Whether the faucet balance is below its minimum",
+            ),
+            &["chain_id"],
+        )?;
+        let dust_reclaims = IntCounterVec::new(
+            Opts::new(
+                "dust_reclaims_total",
+                "Dust reclaim checks of swept addresses",
+            ),
+            &["chain_id", "outcome"],
+        )?;
+        let webhook_deliveries = IntCounterVec::new(
+            Opts::new("webhook_deliveries_total", "Webhook delivery attempts"),
+            &["event", "outcome"],
+        )?;
+        let webhook_duration = HistogramVec::new(
+            HistogramOpts::new("webhook_duration_seconds", "Webhook delivery latency"),
+            &["event"],
+        )?;
+        let rpc_requests = IntCounterVec::new(
+            Opts::new("rpc_requests_total", "RPC calls to the node"),
+            &["method", "outcome"],
+        )?;
+        let rpc_duration = HistogramVec::new(
+            HistogramOpts::new("rpc_duration_seconds", "RPC call latency"),
+            &["method"],
+        )?;
+
+        registry.register(Box::new(last_processed_block.clone()))?;
+        registry.register(Box::new(chain_head_block.clone()))?;
+        registry.register(Box::new(block_lag.clone()))?;
+        registry.register(Box::new(blocks_processed.clone()))?;
+        registry.register(Box::new(deposits_detected.clone()))?;
+        registry.register(Box::new(deposits_swept.clone()))?;
+        registry.register(Box::new(sweep_failures.clone()))?;
+        registry.register(Box::new(faucet_balance_wei.clone()))?;
+        registry.register(Box::new(funding_queue_depth.clone()))?;
+        registry.register(Box::new(faucet_fundings.clone()))?;
+        registry.register(Box::new(faucet_low_balance.clone()))?;
+        registry.register(Box::new(dust_reclaims.clone()))?;
+        registry.register(Box::new(webhook_deliveries.clone()))?;
+        registry.register(Box::new(webhook_duration.clone()))?;
+        registry.register(Box::new(rpc_requests.clone()))?;
+        let service_restarts = IntCounterVec::new(
+            Opts::new(
+                "service_restarts_total",
+                "Background service restarts after a crash",
+            ),
+            &["service"],
+        )?;
+
+        registry.register(Box::new(rpc_duration.clone()))?;
+        registry.register(Box::new(service_restarts.clone()))?;
+
+        Ok(Self {
+            registry,
+            last_processed_block,
+            chain_head_block,
+            block_lag,
+            blocks_processed,
+            deposits_detected,
+            deposits_swept,
+            sweep_failures,
+            faucet_balance_wei,
+            funding_queue_depth,
+            faucet_fundings,
+            faucet_low_balance,
+            dust_reclaims,
+            webhook_deliveries,
+            webhook_duration,
+            rpc_requests,
+            rpc_duration,
+            service_restarts,
+        })
+    }
+
+    pub fn set_chain_head(&self, chain_id: u64, block: u64) {
+        let chain = chain_id.to_string();
+        self.chain_head_block
+            .with_label_values(&[&chain])
+            .set(block as i64);
+        self.update_lag(&chain);
+    }
+
+    pub fn set_last_processed_block(&self, chain_id: u64, block: u64) {
+        let chain = chain_id.to_string();
+        self.last_processed_block
+            .with_label_values(&[&chain])
+            .set(block as i64);
+        self.update_lag(&chain);
+    }
+
+    fn update_lag(&self, chain: &str) {
+        let lag = self.chain_head_block.with_label_values(&[chain]).get()
+            - self.last_processed_block.with_label_values(&[chain]).get();
+        self.block_lag.with_label_values(&[chain]).set(lag.max(0));
+    }
+
+    pub fn record_webhook(&self, event: &str, elapsed: Duration, delivered: bool) {
+        let outcome = if delivered { "success" } else { "failure" };
+        self.webhook_deliveries
+            .with_label_values(&[event, outcome])
+            .inc();
+        self.webhook_duration
+            .with_label_values(&[event])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Render every metric in the Prometheus text format
+    pub fn render(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+/// Time an RPC call and count it by method and outcome
+pub async fn observe_rpc<F, T, E>(method: &str, call: F) -> std::result::Result<T, E>
+where
+    F: IntoFuture<Output = std::result::Result<T, E>>,
+{
+    let metrics = metrics();
+    let start = Instant::now();
+    let result = call.await;
+    metrics
+        .rpc_duration
+        .with_label_values(&[method])
+        .observe(start.elapsed().as_secs_f64());
+    let outcome = if result.is_ok() { "success" } else { "error" };
+    metrics
+        .rpc_requests
+        .with_label_values(&[method, outcome])
+        .inc();
+    result
+}
+
+static WEBHOOK_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .expect("webhook client settings are valid")
+});
+
+/// The client webhooks and alerts are sent with. It doesn't follow redirects: only the
+/// configured URL passes the webhook host checks, and a redirect could lead anywhere,
+/// including the service's own network.
+pub fn webhook_client() -> &'static reqwest::Client {
+    &WEBHOOK_CLIENT
+}
+
+/// Send a webhook request, recording its latency and whether the receiver accepted it
+pub async fn send_webhook(
+    event: &str,
+    request: reqwest::RequestBuilder,
+) -> reqwest::Result<reqwest::Response> {
+    let start = Instant::now();
+    let res = request.send().await;
+    let delivered = matches!(&res, Ok(r) if r.status().is_success());
+    metrics().record_webhook(event, start.elapsed(), delivered);
+    res
+}
@@ -1,8 +1,14 @@
-use crate::{config::Config, db::Db};
+use crate::config::{ChainConfig, Config};
+use crate::db::{DepositFilter, FundingReason};
+use crate::funding::{FundingJob, FundingQueue};
+use crate::metrics::{metrics, observe_rpc};
+use crate::settings::Settings;
+use crate::{deposit::Deposit, storage::Storage};
 use alloy::primitives::Address;
 use alloy::providers::Provider;
 use alloy::rpc::types::BlockNumberOrTag;
 use anyhow::Result;
+use std::sync::Arc;
 use tracing::{error, info, warn};
 
 /// Information about a detected deposit for webhook notification
@@ -10,6 +16,7 @@ struct DepositInfo<'a> {
     id: &'a str,
     account_id: &'a str,      // Polygon address
     registration_id: &'a str, // Original id used when registering
+    chain_id: u64,
     tx_hash: &'a str,
     amount: &'a str,
     token_type: &'a str,
@@ -18,30 +25,88 @@ struct DepositInfo<'a> {
     token_decimals: Option<u8>,
 }
 
+/// Watches one chain for deposits to registered addresses
 pub struct Monitor<P> {
     config: Config,
-    db: Db,
+    chain: ChainConfig,
+    /// Read every cycle for the poll interval and block offset
+    settings: Settings,
+    db: Arc<dyn Storage>,
     provider: P,
+    /// Where first deposits queue the address's funding, under the on-first-deposit policy
+    funding: Option<FundingQueue>,
 }
 
-impl<T> Monitor<alloy::providers::RootProvider<T>>
+impl<P> Monitor<P>
 where
-    T: alloy::transports::Transport + Clone,
+    P: Provider,
 {
-    pub fn new(config: Config, db: Db, provider: alloy::providers::RootProvider<T>) -> Self {
+    /// `provider` must be connected to `chain`, whose chain id must already be resolved
+    pub fn new(config: Config, chain: ChainConfig, db: Arc<dyn Storage>, provider: P) -> Self {
         Self {
+            settings: Settings::new(&config),
             config,
+            chain,
             db,
             provider,
+            funding: None,
         }
     }
 
-    async fn catch_up(&self) -> Result<()> {
-        let latest_block = self.provider.get_block_number().await?;
+    /// Follow `settings` instead of the values `config` had at construction
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Queue funding an address on `funding` when its first deposit is detected
+    pub fn with_funding_queue(mut self, funding: Option<FundingQueue>) -> Self {
+        self.funding = funding;
+        self
+    }
+
+    /// Queue the existential deposit for `address` if the deposit just recorded is the
+    /// first one of `registration_id` on this chain
+    async fn fund_on_first_deposit(&self, registration_id: &str, address: &str) -> Result<()> {
+        let Some(ref funding) = self.funding else {
+            return Ok(());
+        };
+        let filter = DepositFilter {
+            chain_id: Some(self.chain.chain_id),
+            account_id: Some(registration_id.to_string()),
+            ..Default::default()
+        };
+        let page = self.db.list_deposits(&filter, None, 2).await?;
+        if page.deposits.len() == 1 {
+            info!(
+                "First deposit to {} on chain {}, queueing its funding",
+                address, self.chain.chain_id
+            );
+            funding
+                .push(FundingJob {
+                    account_id: registration_id.to_string(),
+                    address: address.to_string(),
+                    reason: FundingReason::FirstDeposit,
+                })
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn catch_up(&self, ctx: &ServiceContext) -> Result<()> {
+        let latest_block = observe_rpc("eth_blockNumber", self.provider.get_block_number()).await?;
+        metrics().set_chain_head(self.chain.chain_id, latest_block);
 
         // Use saturating_sub to prevent underflow if block_offset_from_head > latest_block
-        let current_block = latest_block.saturating_sub(self.config.block_offset_from_head);
-        let last_processed = self.db.get_last_processed_block()?;
+        let block_offset_from_head = self
+            .settings
+            .chain(self.chain.chain_id)?
+            .block_offset_from_head;
+        let current_block = latest_block.saturating_sub(block_offset_from_head);
+        let last_processed = self
+            .db
+            .get_last_processed_block(self.chain.chain_id)
+            .await?;
 
         let start_block = if last_processed == 0 {
             current_block // Start from now if fresh
@@ -50,7 +115,8 @@ where
         };
 
         info!("--------------------------------");
-        info!("Offset from head: {}", self.config.block_offset_from_head);
+        info!("Chain: {} ({})", self.chain.name, self.chain.chain_id);
+        info!("Offset from head: {}", block_offset_from_head);
         info!("Start block: {}", start_block);
         info!("Current block: {}", current_block);
         info!("Last processed block: {}", last_processed);
@@ -69,6 +135,10 @@ where
         );
 
         for block_num in start_block..=current_block {
+            // Stop between blocks on shutdown; the next start resumes from last_processed
+            if ctx.is_cancelled() {
+                break;
+            }
             self.process_single_block(block_num).await?;
         }
 
@@ -76,12 +146,14 @@ where
     }
 
     async fn process_single_block(&self, block_num: u64) -> Result<()> {
-        info!("🔍 Processing block {}", block_num);
-
-        if let Some(block) = self
-            .provider
-            .get_block_by_number(BlockNumberOrTag::Number(block_num), true)
-            .await?
+        info!("🔍 Processing block {} on {}", block_num, self.chain.name);
+
+        if let Some(block) = observe_rpc(
+            "eth_getBlockByNumber",
+            self.provider
+                .get_block_by_number(BlockNumberOrTag::Number(block_num), true),
+        )
+        .await?
         {
             // Process native ETH transfers
             if let Some(txs) = block.transactions.as_transactions() {
@@ -99,8 +171,10 @@ where
                             continue;
                         }
 
-                        if let Some(registration_id) =
-                            self.db.get_registration_id_by_address(&to_address_str)?
+                        if let Some(registration_id) = self
+                            .db
+                            .get_registration_id_by_address(self.chain.chain_id, &to_address_str)
+                            .await?
                         {
                             info!(
                                 "Native ETH deposit detected! Tx: {:?}, Address: {}, Registration ID: {}",
@@ -109,19 +183,45 @@ where
 
                             // Only send webhook if this is a new deposit (not a duplicate)
                             let tx_hash_str = tx.hash.to_string();
-                            let is_new_deposit = self.db.record_deposit(
+                            let deposit = Deposit::native(
+                                self.chain.chain_id,
                                 &tx_hash_str,
                                 &registration_id,
+                                &from_address_str,
                                 &tx.value.to_string(),
-                            )?;
+                                block_num,
+                                block.header.hash.map(|h| h.to_string()),
+                            );
+                            let is_new_deposit = self.db.record_deposit(&deposit).await?;
+                            if is_new_deposit {
+                                metrics()
+                                    .deposits_detected
+                                    .with_label_values(&[
+                                        &self.chain.chain_id.to_string(),
+                                        "native",
+                                    ])
+                                    .inc();
+                                self.fund_on_first_deposit(&registration_id, &to_address_str)
+                                    .await?;
+                            }
+
+                            // Deactivated accounts are still swept, but no longer credited
+                            let is_active = self.db.is_account_active(&registration_id).await?;
+                            if is_new_deposit && !is_active {
+                                info!(
+                                    "Deposit {} to deactivated account {} recorded for sweeping only",
+                                    tx_hash_str, registration_id
+                                );
+                            }
 
                             // Send webhook notification for deposit detection only if it's new
-                            if is_new_deposit {
+                            if is_new_deposit && is_active {
                                 let amount_str = tx.value.to_string();
                                 let deposit_info = DepositInfo {
-                                    id: &tx_hash_str,
+                                    id: &deposit.key,
                                     account_id: &to_address_str,
                                     registration_id: &registration_id,
+                                    chain_id: self.chain.chain_id,
                                     tx_hash: &tx_hash_str,
                                     amount: &amount_str,
                                     token_type: "native",
@@ -145,7 +245,14 @@ where
         }
 
         // info!("Processing D {}", block_num);
-        self.db.set_last_processed_block(block_num)?;
+        self.db
+            .set_last_processed_block(self.chain.chain_id, block_num)
+            .await?;
+        metrics()
+            .blocks_processed
+            .with_label_values(&[&self.chain.chain_id.to_string()])
+            .inc();
+        metrics().set_last_processed_block(self.chain.chain_id, block_num);
         Ok(())
     }
 
@@ -190,8 +297,10 @@ where
                 }
 
                 // Check if this is one of our monitored addresses
-                if let Some(registration_id) =
-                    self.db.get_registration_id_by_address(&to_address_str)?
+                if let Some(registration_id) = self
+                    .db
+                    .get_registration_id_by_address(self.chain.chain_id, &to_address_str)
+                    .await?
                 {
                     // Decode the amount from data field (ABI-encoded uint256 is 32 bytes)
                     let amount = if log.data().data.len() >= 32 {
@@ -233,26 +342,52 @@ where
                     if let Some(tx_hash) = log.transaction_hash {
                         let log_index = log.log_index.unwrap_or(0);
                         let tx_hash_str = tx_hash.to_string();
-                        let deposit_id = format!("{}:{}", tx_hash_str, log_index);
 
                         // Only send webhook if this is a new deposit (not a duplicate)
-                        let is_new_deposit = self.db.record_erc20_deposit(
+                        let deposit = Deposit::erc20(
+                            self.chain.chain_id,
                             &tx_hash_str,
                             log_index,
                             &registration_id,
+                            &from_address_str,
                             &amount.to_string(),
                             &token_address.to_string(),
                             &token_info.symbol,
-                        )?;
+                            log.block_number.unwrap_or(block_num),
+                            log.block_hash.map(|h| h.to_string()),
+                        );
+                        let deposit_id = deposit.key.as_str();
+                        let is_new_deposit = self.db.record_deposit(&deposit).await?;
+                        if is_new_deposit {
+                            metrics()
+                                .deposits_detected
+                                .with_label_values(&[
+                                    &self.chain.chain_id.to_string(),
+                                    &token_info.symbol,
+                                ])
+                                .inc();
+                            self.fund_on_first_deposit(&registration_id, &to_address_str)
+                                .await?;
+                        }
+
+                        // Deactivated accounts are still swept, but no longer credited
+                        let is_active = self.db.is_account_active(&registration_id).await?;
+                        if is_new_deposit && !is_active {
+                            info!(
+                                "ERC20 deposit {} to deactivated account {} recorded for sweeping only",
+                                deposit_id, registration_id
+                            );
+                        }
 
                         // Send webhook notification for ERC20 deposit detection only if it's new
-                        if is_new_deposit {
+                        if is_new_deposit && is_active {
                             let token_addr_str = token_address.to_string();
                             let amount_str = amount.to_string();
                             let deposit_info = DepositInfo {
-                                id: &deposit_id,
+                                id: deposit_id,
                                 account_id: &to_address_str,
                                 registration_id: &registration_id,
+                                chain_id: self.chain.chain_id,
                                 tx_hash: &tx_hash_str,
                                 amount: &amount_str,
                                 token_type: "erc20",
@@ -285,7 +420,9 @@ where
         let mut last_result = Ok(Vec::new());
 
         for attempt in 1..=max_retries {
-            last_result = self.provider.get_logs(filter).await.map_err(|e| e.into());
+            last_result = observe_rpc("eth_getLogs", self.provider.get_logs(filter))
+                .await
+                .map_err(|e| e.into());
 
             match &last_result {
                 Ok(logs) if !logs.is_empty() => {
@@ -320,7 +457,9 @@ where
         let token_address_str = token_address.to_string();
 
         // Check cache first
-        if let Some((symbol, decimals, name)) = self.db.get_token_metadata(&token_address_str)? {
+        if let Some((symbol, decimals, name)) =
+            self.db.get_token_metadata(&token_address_str).await?
+        {
             return Ok(TokenInfo {
                 address: token_address_str,
                 symbol,
@@ -333,12 +472,14 @@ where
         match get_token_info(&self.provider, token_address).await {
             Ok(token_info) => {
                 // Cache it
-                self.db.store_token_metadata(
-                    &token_address_str,
-                    &token_info.symbol,
-                    token_info.decimals,
-                    &token_info.name,
-                )?;
+                self.db
+                    .store_token_metadata(
+                        &token_address_str,
+                        &token_info.symbol,
+                        token_info.decimals,
+                        &token_info.name,
+                    )
+                    .await?;
                 Ok(token_info)
             }
             Err(e) => {
@@ -359,7 +500,7 @@ where
 
     async fn send_deposit_detected_webhook(&self, info: &DepositInfo<'_>) -> Result<()> {
         // Get the webhook URL for this account using registration_id
-        let Some(webhook_url) = self.db.get_webhook_url(info.registration_id)? else {
+        let Some(webhook_url) = self.db.get_webhook_url(info.registration_id).await? else {
             error!(
                 "No webhook URL found for registration_id: {}",
                 info.registration_id
@@ -367,13 +508,14 @@ where
             return Ok(());
         };
 
-        let client = reqwest::Client::new();
+        let client = crate::metrics::webhook_client();
 
         let mut payload = serde_json::json!({
             "id": info.id,
             "event": "deposit_detected",
             "account_id": info.account_id,
             "registration_id": info.registration_id,
+            "chain_id": info.chain_id,
             "tx_hash": info.tx_hash,
             "amount": info.amount,
             "token_type": info.token_type
@@ -397,7 +539,7 @@ where
             request = request.header("Authorization", format!("Bearer {}", token));
         }
 
-        let res = request.send().await;
+        let res = crate::metrics::send_webhook("deposit_detected", request).await;
 
         match res {
             Ok(r) => info!(
@@ -442,18 +584,17 @@ struct TokenInfo {
     name: String,
 }
 
-async fn get_token_info<T>(
-    provider: &alloy::providers::RootProvider<T>,
-    token_address: Address,
-) -> Result<TokenInfo>
+async fn get_token_info<P>(provider: &P, token_address: Address) -> Result<TokenInfo>
 where
-    T: alloy::transports::Transport + Clone,
+    P: Provider,
 {
     let contract = IERC20::new(token_address, provider);
 
-    let symbol = contract.symbol().call().await?._0;
-    let decimals = contract.decimals().call().await?._0;
-    let name = contract.name().call().await?._0;
+    let symbol = observe_rpc("eth_call", contract.symbol().call()).await?._0;
+    let decimals = observe_rpc("eth_call", contract.decimals().call())
+        .await?
+        ._0;
+    let name = observe_rpc("eth_call", contract.name().call()).await?._0;
 
     Ok(TokenInfo {
         address: token_address.to_string(),
@@ -463,62 +604,85 @@ where
     })
 }
 
-use crate::traits::Service;
+use crate::traits::{Service, ServiceContext, ServiceStatus};
 use async_trait::async_trait;
+use std::time::Duration;
+use tokio::time::sleep;
 
-use alloy::transports::http::Http;
-use reqwest::Client;
-
-// Implementation for HTTP Provider (Polling)
 #[async_trait]
-impl Service for Monitor<alloy::providers::RootProvider<Http<Client>>> {
-    async fn run(&self) {
-        use std::time::Duration;
-        use tokio::time::sleep;
+impl<P> Service for Monitor<P>
+where
+    P: Provider,
+{
+    fn name(&self) -> String {
+        format!("monitor[{}]", self.chain.name)
+    }
 
-        info!("Starting Monitor in Polling mode");
-        loop {
-            if let Err(e) = self.catch_up().await {
-                error!("Error in monitor loop: {:?}", e);
-            }
-            info!("Sleeping for {} seconds", self.config.poll_interval);
-            sleep(Duration::from_secs(self.config.poll_interval)).await;
+    async fn run(&self, ctx: ServiceContext) {
+        ctx.set_status(ServiceStatus::Running);
+        if self.chain.provider_url.is_pubsub() {
+            self.run_streaming(&ctx).await;
+        } else {
+            self.run_polling(&ctx).await;
         }
+        info!("Monitor for {} stopped", self.chain.name);
     }
 }
 
-// Implementation for WebSocket Provider (Streaming)
-#[async_trait]
-impl Service for Monitor<alloy::providers::RootProvider<alloy::pubsub::PubSubFrontend>> {
-    async fn run(&self) {
-        use std::time::Duration;
-        use tokio::time::sleep;
+impl<P> Monitor<P>
+where
+    P: Provider,
+{
+    async fn run_polling(&self, ctx: &ServiceContext) {
+        info!("Starting Monitor for {} in Polling mode", self.chain.name);
+        while !ctx.is_cancelled() {
+            if let Err(e) = self.catch_up(ctx).await {
+                error!("Error in monitor loop: {:?}", e);
+            }
+            let poll_interval = self.settings.poll_interval();
+            info!("Sleeping for {} seconds", poll_interval);
+            tokio::select! {
+                _ = sleep(Duration::from_secs(poll_interval)) => {}
+                _ = ctx.cancelled() => {}
+            }
+        }
+    }
 
-        info!("Starting Monitor in Streaming mode");
-        loop {
+    async fn run_streaming(&self, ctx: &ServiceContext) {
+        info!("Starting Monitor for {} in Streaming mode", self.chain.name);
+        while !ctx.is_cancelled() {
             // 1. Catch up first
-            if let Err(e) = self.catch_up().await {
+            if let Err(e) = self.catch_up(ctx).await {
                 error!("Error during catch-up: {:?}", e);
             }
 
             // 2. Subscribe
-            match self.provider.subscribe_blocks().await {
-                Ok(mut stream) => {
-                    while let Ok(header) = stream.recv().await {
-                        if let Some(block_num) = header.header.number {
-                            info!("New block received via WS: {}", block_num);
-                            if let Err(e) = self.process_single_block(block_num).await {
-                                error!("Error processing block {}: {:?}", block_num, e);
-                            }
+            match observe_rpc("eth_subscribe", self.provider.subscribe_blocks()).await {
+                Ok(mut stream) => loop {
+                    let header = tokio::select! {
+                        header = stream.recv() => header,
+                        _ = ctx.cancelled() => break,
+                    };
+                    let Ok(header) = header else {
+                        error!("Block subscription ended");
+                        break;
+                    };
+                    if let Some(block_num) = header.header.number {
+                        info!("New block received via subscription: {}", block_num);
+                        metrics().set_chain_head(self.chain.chain_id, block_num);
+                        if let Err(e) = self.process_single_block(block_num).await {
+                            error!("Error processing block {}: {:?}", block_num, e);
                         }
                     }
-                    error!("WebSocket stream ended");
-                }
+                },
                 Err(e) => error!("Failed to subscribe to blocks: {:?}", e),
             }
 
             // Reconnect delay
-            sleep(Duration::from_secs(5)).await;
+            tokio::select! {
+                _ = sleep(Duration::from_secs(5)) => {}
+                _ = ctx.cancelled() => {}
+            }
         }
     }
 }
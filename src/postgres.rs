@@ -0,0 +1,1752 @@
+use crate::auth::{ApiKey, Scope};
+use crate::db::{
+    AccountStatus, DepositFilter, DepositPage, DustReclaimRecord, DustReclaimStatus, FundingFilter,
+    FundingReason, FundingRecord, FundingStart, FundingStatus, IdempotencyRecord, MigrationReport,
+    NewAccount, NewDustReclaim, NewFunding, QueuedFunding, Registration, PENDING_LEASE_SECS,
+};
+use crate::deposit::{now_secs, Deposit, DepositStatus};
+use crate::export::{summarize, AccountRecord, ExportRecord, ExportSummary, ExportWriter};
+use crate::settings::SettingChange;
+use crate::storage::Storage;
+use alloy::primitives::U256;
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::types::Json;
+use sqlx::{Postgres, Transaction};
+use std::io::Write;
+use std::str::FromStr;
+
+/// Latest PostgreSQL schema version known to this build
+pub const PG_SCHEMA_VERSION: u32 = PG_MIGRATIONS[PG_MIGRATIONS.len() - 1].version;
+
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Serializes migrations of instances starting at the same time
+const MIGRATION_LOCK_ID: i64 = 0x686f_745f_7761_6c6c; // "hot_wall"
+
+struct PgMigration {
+    version: u32,
+    description: &'static str,
+    statements: &'static [&'static str],
+}
+
+/// Append new migrations to the end; never reorder or edit released ones
+const PG_MIGRATIONS: &[PgMigration] = &[
+    PgMigration {
+        version: 1,
+        description: "create accounts, deposits, and token metadata tables",
+        statements: &[
+            "CREATE TABLE accounts (
+            id TEXT PRIMARY KEY,
+            derivation_index BIGINT NOT NULL,
+            address TEXT NOT NULL,
+            webhook_url TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'active',
+            status_updated_at BIGINT,
+            metadata JSONB,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        )",
+            "CREATE INDEX accounts_address_idx ON accounts (address)",
+            // The full record is the source of truth; the other columns exist for filtering and joins
+            "CREATE TABLE deposits (
+            key TEXT PRIMARY KEY,
+            tx_hash TEXT NOT NULL,
+            account_id TEXT NOT NULL,
+            status TEXT NOT NULL,
+            amount TEXT NOT NULL,
+            token_address TEXT,
+            token_symbol TEXT,
+            block_number BIGINT NOT NULL,
+            record JSONB NOT NULL
+        )",
+            "CREATE INDEX deposits_status_idx ON deposits (status, key)",
+            "CREATE INDEX deposits_account_idx ON deposits (account_id, block_number, key)",
+            "CREATE INDEX deposits_tx_hash_idx ON deposits (tx_hash)",
+            "CREATE TABLE token_metadata (
+            address TEXT PRIMARY KEY,
+            symbol TEXT NOT NULL,
+            decimals SMALLINT NOT NULL,
+            name TEXT NOT NULL
+        )",
+        ],
+    },
+    PgMigration {
+        version: 2,
+        description: "record the chain of deposits and account addresses",
+        statements: &[
+            "CREATE TABLE account_chains (
+            account_id TEXT NOT NULL REFERENCES accounts (id),
+            chain_id BIGINT NOT NULL,
+            address TEXT NOT NULL,
+            PRIMARY KEY (account_id, chain_id)
+        )",
+            "CREATE INDEX account_chains_address_idx ON account_chains (chain_id, address)",
+            // 0 until the first start with a chain configured assigns existing deposits to it
+            "ALTER TABLE deposits ADD COLUMN chain_id BIGINT NOT NULL DEFAULT 0",
+        ],
+    },
+    PgMigration {
+        version: 3,
+        description: "create settings audit log table",
+        statements: &["CREATE TABLE settings_audit (
+            id BIGSERIAL PRIMARY KEY,
+            changed_at BIGINT NOT NULL,
+            source TEXT NOT NULL,
+            chain_id BIGINT,
+            setting TEXT NOT NULL,
+            old_value TEXT NOT NULL,
+            new_value TEXT NOT NULL
+        )"],
+    },
+    PgMigration {
+        version: 4,
+        description: "create API key table",
+        statements: &["CREATE TABLE api_keys (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            scopes TEXT[] NOT NULL,
+            key_hash TEXT NOT NULL,
+            created_at BIGINT NOT NULL,
+            revoked_at BIGINT
+        )"],
+    },
+    PgMigration {
+        version: 5,
+        description: "create idempotency key table",
+        statements: &[
+            "CREATE TABLE idempotency_keys (
+                key TEXT PRIMARY KEY,
+                request_hash TEXT NOT NULL,
+                response TEXT NOT NULL,
+                created_at BIGINT NOT NULL
+            )",
+            "CREATE INDEX idempotency_keys_created_at_idx ON idempotency_keys (created_at)",
+        ],
+    },
+    PgMigration {
+        version: 6,
+        description: "create faucet funding ledger table",
+        statements: &[
+            "CREATE TABLE faucet_fundings (
+                id BIGSERIAL PRIMARY KEY,
+                chain_id BIGINT NOT NULL,
+                address TEXT NOT NULL,
+                amount TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                status TEXT NOT NULL,
+                tx_hash TEXT,
+                error TEXT,
+                created_at BIGINT NOT NULL,
+                updated_at BIGINT NOT NULL
+            )",
+            "CREATE INDEX faucet_fundings_address_idx ON faucet_fundings (chain_id, lower(address))",
+            // At most one funding of an address in flight
+            "CREATE UNIQUE INDEX faucet_fundings_pending_idx ON faucet_fundings (chain_id, lower(address))
+                WHERE status = 'pending'",
+        ],
+    },
+    PgMigration {
+        version: 7,
+        description: "create dust reclaim table",
+        statements: &[
+            "CREATE TABLE dust_reclaims (
+                id BIGSERIAL PRIMARY KEY,
+                chain_id BIGINT NOT NULL,
+                address TEXT NOT NULL,
+                destination TEXT NOT NULL,
+                amount TEXT NOT NULL,
+                status TEXT NOT NULL,
+                tx_hash TEXT,
+                error TEXT,
+                created_at BIGINT NOT NULL,
+                updated_at BIGINT NOT NULL
+            )",
+            "CREATE INDEX dust_reclaims_chain_idx ON dust_reclaims (chain_id)",
+        ],
+    },
+    PgMigration {
+        version: 8,
+        description: "key deposits by chain",
+        // So a transaction replayed on another chain is no longer taken for a duplicate.
+        // Deposits not yet assigned to a chain are keyed by chain 0 until they are.
+        statements: &["UPDATE deposits SET key = chain_id::text || ':' || key,
+                record = jsonb_set(record, '{key}', to_jsonb(chain_id::text || ':' || key))"],
+    },
+    PgMigration {
+        version: 9,
+        description: "create funding queue table",
+        statements: &[
+            "CREATE TABLE funding_queue (
+                id BIGSERIAL PRIMARY KEY,
+                chain_id BIGINT NOT NULL,
+                account_id TEXT NOT NULL,
+                address TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                queued_at BIGINT NOT NULL
+            )",
+            "CREATE INDEX funding_queue_chain_idx ON funding_queue (chain_id, id)",
+        ],
+    },
+    PgMigration {
+        version: 10,
+        description: "index deposits by status and chain",
+        // Each chain's services read only their own deposits
+        statements: &[
+            "DROP INDEX deposits_status_idx",
+            "CREATE INDEX deposits_status_chain_idx ON deposits (status, chain_id, key)",
+        ],
+    },
+];
+
+/// Last processed block of a database written before chains were tracked
+const LEGACY_LAST_BLOCK_KEY: &str = "last_block";
+
+/// Set once records without a chain have been assigned to a chain
+const LEGACY_CHAIN_KEY: &str = "legacy_chain_id";
+
+fn last_block_key(chain_id: u64) -> String {
+    format!("{}:{}", LEGACY_LAST_BLOCK_KEY, chain_id)
+}
+
+/// PostgreSQL storage, shareable between several service instances
+#[derive(Clone)]
+pub struct PgStorage {
+    pool: PgPool,
+}
+
+impl PgStorage {
+    /// Connect to `url` and bring the schema up to date
+    pub async fn connect(url: &str) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(10)
+            .connect(url)
+            .await?;
+
+        let mut txn = pool.begin().await?;
+        let report = run_migrations(&mut txn).await?;
+        txn.commit().await?;
+
+        for (version, description) in &report.applied {
+            tracing::info!("🗄️ Applied migration {}: {}", version, description);
+        }
+
+        Ok(Self { pool })
+    }
+
+    /// Run pending migrations without starting the service.
+    /// With `dry_run` the migrations are executed but rolled back.
+    pub async fn migrate(url: &str, dry_run: bool) -> Result<MigrationReport> {
+        let pool = PgPoolOptions::new().max_connections(1).connect(url).await?;
+
+        let mut txn = pool.begin().await?;
+        let report = run_migrations(&mut txn).await?;
+        if dry_run {
+            txn.rollback().await?;
+        } else {
+            txn.commit().await?;
+        }
+        pool.close().await;
+
+        Ok(report)
+    }
+
+    /// Schema version the database was last migrated to
+    pub async fn schema_version(&self) -> Result<u32> {
+        let mut conn = self.pool.acquire().await?;
+        read_schema_version(&mut conn).await
+    }
+
+    async fn deposits_where(&self, condition: &str, bind: Option<&str>) -> Result<Vec<Deposit>> {
+        let sql = format!(
+            "SELECT record FROM deposits WHERE {} ORDER BY key",
+            condition
+        );
+        let mut query = sqlx::query_scalar::<_, Json<Deposit>>(&sql);
+        if let Some(value) = bind {
+            query = query.bind(value);
+        }
+        let rows = query.fetch_all(&self.pool).await?;
+        Ok(rows.into_iter().map(|Json(d)| d).collect())
+    }
+
+    /// Settle a pending or interrupted funding with `status`
+    async fn finish_funding(
+        &self,
+        id: u64,
+        status: FundingStatus,
+        tx_hash: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<FundingRecord> {
+        let row: Option<FundingRow> = sqlx::query_as(&format!(
+            "UPDATE faucet_fundings SET status = $2, tx_hash = $3, error = $4, updated_at = $5
+             WHERE id = $1 AND status IN ('pending', 'interrupted')
+             RETURNING {}",
+            FUNDING_COLUMNS
+        ))
+        .bind(id as i64)
+        .bind(status.as_str())
+        .bind(tx_hash)
+        .bind(error)
+        .bind(now_secs() as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+        match row {
+            Some(row) => funding_record_from_row(row),
+            None => Err(anyhow::anyhow!("Funding {} not found or not pending", id)),
+        }
+    }
+
+    async fn insert_dust_reclaim(
+        &self,
+        reclaim: &NewDustReclaim,
+        status: DustReclaimStatus,
+        error: Option<&str>,
+    ) -> Result<DustReclaimRecord> {
+        let row: DustReclaimRow = sqlx::query_as(&format!(
+            "INSERT INTO dust_reclaims
+                (chain_id, address, destination, amount, status, error, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $7)
+             RETURNING {}",
+            DUST_RECLAIM_COLUMNS
+        ))
+        .bind(reclaim.chain_id as i64)
+        .bind(&reclaim.address)
+        .bind(&reclaim.destination)
+        .bind(reclaim.amount.to_string())
+        .bind(status.as_str())
+        .bind(error)
+        .bind(now_secs() as i64)
+        .fetch_one(&self.pool)
+        .await?;
+        dust_reclaim_record_from_row(row)
+    }
+
+    /// Settle a pending or interrupted dust reclaim with `status`
+    async fn finish_dust_reclaim(
+        &self,
+        id: u64,
+        status: DustReclaimStatus,
+        tx_hash: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<DustReclaimRecord> {
+        let row: Option<DustReclaimRow> = sqlx::query_as(&format!(
+            "UPDATE dust_reclaims SET status = $2, tx_hash = $3, error = $4, updated_at = $5
+             WHERE id = $1 AND status IN ('pending', 'interrupted')
+             RETURNING {}",
+            DUST_RECLAIM_COLUMNS
+        ))
+        .bind(id as i64)
+        .bind(status.as_str())
+        .bind(tx_hash)
+        .bind(error)
+        .bind(now_secs() as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+        match row {
+            Some(row) => dust_reclaim_record_from_row(row),
+            None => Err(anyhow::anyhow!(
+                "Dust reclaim {} not found or not pending",
+                id
+            )),
+        }
+    }
+
+    /// Apply `update` to a deposit while holding its row lock
+    async fn update_deposit(
+        &self,
+        key: &str,
+        update: impl FnOnce(&mut Deposit) -> Result<()> + Send,
+    ) -> Result<Deposit> {
+        let mut txn = self.pool.begin().await?;
+        let Json(mut deposit) = sqlx::query_scalar::<_, Json<Deposit>>(
+            "SELECT record FROM deposits WHERE key = $1 FOR UPDATE",
+        )
+        .bind(key)
+        .fetch_optional(&mut *txn)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Deposit {} not found", key))?;
+
+        update(&mut deposit)?;
+        save_deposit(&mut txn, &deposit).await?;
+        txn.commit().await?;
+        Ok(deposit)
+    }
+}
+
+async fn read_schema_version(conn: &mut sqlx::PgConnection) -> Result<u32> {
+    let state_exists: bool = sqlx::query_scalar("SELECT to_regclass('state') IS NOT NULL")
+        .fetch_one(&mut *conn)
+        .await?;
+    if !state_exists {
+        return Ok(0);
+    }
+
+    let value: Option<String> = sqlx::query_scalar("SELECT value FROM state WHERE key = $1")
+        .bind(SCHEMA_VERSION_KEY)
+        .fetch_optional(&mut *conn)
+        .await?;
+    match value {
+        Some(v) => v
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid schema version in database: {}", v)),
+        None => Ok(0),
+    }
+}
+
+async fn run_migrations(txn: &mut Transaction<'_, Postgres>) -> Result<MigrationReport> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(MIGRATION_LOCK_ID)
+        .execute(&mut **txn)
+        .await?;
+    sqlx::query("CREATE TABLE IF NOT EXISTS state (key TEXT PRIMARY KEY, value TEXT NOT NULL)")
+        .execute(&mut **txn)
+        .await?;
+
+    let from_version = read_schema_version(txn).await?;
+    if from_version > PG_SCHEMA_VERSION {
+        return Err(anyhow::anyhow!(
+            "Database schema version {} is newer than the latest version {} supported by this build",
+            from_version,
+            PG_SCHEMA_VERSION
+        ));
+    }
+
+    let mut applied = Vec::new();
+    for migration in PG_MIGRATIONS.iter().filter(|m| m.version > from_version) {
+        for statement in migration.statements {
+            sqlx::query(statement)
+                .execute(&mut **txn)
+                .await
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "Migration {} ({}) failed: {}",
+                        migration.version,
+                        migration.description,
+                        e
+                    )
+                })?;
+        }
+        applied.push((migration.version, migration.description));
+    }
+
+    if !applied.is_empty() {
+        set_state(
+            &mut **txn,
+            SCHEMA_VERSION_KEY,
+            &PG_SCHEMA_VERSION.to_string(),
+        )
+        .await?;
+    }
+
+    Ok(MigrationReport {
+        from_version,
+        to_version: PG_SCHEMA_VERSION,
+        applied,
+    })
+}
+
+async fn set_state<'e, E>(executor: E, key: &str, value: &str) -> Result<()>
+where
+    E: sqlx::Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        "INSERT INTO state (key, value) VALUES ($1, $2)
+         ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value",
+    )
+    .bind(key)
+    .bind(value)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Insert a deposit unless its key exists; returns whether it was inserted
+async fn insert_deposit<'e, E>(executor: E, deposit: &Deposit) -> Result<bool>
+where
+    E: sqlx::Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        "INSERT INTO deposits
+            (key, tx_hash, account_id, status, amount, token_address, token_symbol, block_number, record, chain_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+         ON CONFLICT (key) DO NOTHING",
+    )
+    .bind(&deposit.key)
+    .bind(&deposit.tx_hash)
+    .bind(&deposit.account_id)
+    .bind(deposit.status.as_str())
+    .bind(&deposit.amount)
+    .bind(&deposit.token_address)
+    .bind(&deposit.token_symbol)
+    .bind(deposit.block_number as i64)
+    .bind(Json(deposit))
+    .bind(deposit.chain_id as i64)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+async fn save_deposit(txn: &mut Transaction<'_, Postgres>, deposit: &Deposit) -> Result<()> {
+    sqlx::query("UPDATE deposits SET status = $2, record = $3 WHERE key = $1")
+        .bind(&deposit.key)
+        .bind(deposit.status.as_str())
+        .bind(Json(deposit))
+        .execute(&mut **txn)
+        .await?;
+    Ok(())
+}
+
+/// Watch an account's address on `chain_ids`; returns the chains that were newly added
+async fn insert_account_chains(
+    txn: &mut Transaction<'_, Postgres>,
+    id: &str,
+    chain_ids: &[u64],
+) -> Result<Vec<u64>> {
+    let mut added = Vec::new();
+    for &chain_id in chain_ids {
+        let result = sqlx::query(
+            "INSERT INTO account_chains (account_id, chain_id, address)
+             SELECT id, $2, address FROM accounts WHERE id = $1
+             ON CONFLICT (account_id, chain_id) DO NOTHING",
+        )
+        .bind(id)
+        .bind(chain_id as i64)
+        .execute(&mut **txn)
+        .await?;
+        if result.rows_affected() > 0 {
+            added.push(chain_id);
+        }
+    }
+    Ok(added)
+}
+
+#[async_trait]
+impl Storage for PgStorage {
+    async fn register_accounts(&self, accounts: &[NewAccount]) -> Result<Vec<Registration>> {
+        let mut txn = self.pool.begin().await?;
+        let mut registrations = Vec::with_capacity(accounts.len());
+        for account in accounts {
+            let inserted = sqlx::query(
+                "INSERT INTO accounts (id, derivation_index, address, webhook_url)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (id) DO NOTHING",
+            )
+            .bind(&account.id)
+            .bind(account.index as i64)
+            .bind(&account.address)
+            .bind(&account.webhook_url)
+            .execute(&mut *txn)
+            .await?
+            .rows_affected()
+                > 0;
+            let registration = if inserted {
+                insert_account_chains(&mut txn, &account.id, &account.chain_ids).await?;
+                Registration::Created
+            } else {
+                // Lock the row so a concurrent deactivation can't interleave
+                let (existing, status): (String, String) =
+                    sqlx::query_as("SELECT address, status FROM accounts WHERE id = $1 FOR UPDATE")
+                        .bind(&account.id)
+                        .fetch_one(&mut *txn)
+                        .await?;
+                if AccountStatus::parse(&status) == AccountStatus::Deactivated {
+                    Registration::Deactivated
+                } else {
+                    let added =
+                        insert_account_chains(&mut txn, &account.id, &account.chain_ids).await?;
+                    Registration::Existing {
+                        address: existing,
+                        added,
+                    }
+                }
+            };
+            registrations.push(registration);
+        }
+        if registrations.contains(&Registration::Deactivated) {
+            txn.rollback().await?;
+        } else {
+            txn.commit().await?;
+        }
+        Ok(registrations)
+    }
+
+    async fn get_account_chains(&self, id: &str) -> Result<Vec<u64>> {
+        let chain_ids: Vec<i64> = sqlx::query_scalar(
+            "SELECT chain_id FROM account_chains WHERE account_id = $1 ORDER BY chain_id",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(chain_ids.into_iter().map(|c| c as u64).collect())
+    }
+
+    async fn get_registration_id_by_address(
+        &self,
+        chain_id: u64,
+        address: &str,
+    ) -> Result<Option<String>> {
+        Ok(sqlx::query_scalar(
+            "SELECT a.id FROM account_chains c JOIN accounts a ON a.id = c.account_id
+             WHERE c.chain_id = $1 AND c.address = $2
+             ORDER BY a.created_at DESC LIMIT 1",
+        )
+        .bind(chain_id as i64)
+        .bind(address)
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    async fn get_account_by_id(&self, id: &str) -> Result<Option<(u32, String, String)>> {
+        let row: Option<(i64, String, String)> = sqlx::query_as(
+            "SELECT derivation_index, address, webhook_url FROM accounts WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|(index, address, webhook_url)| (index as u32, address, webhook_url)))
+    }
+
+    async fn get_webhook_url(&self, account_id: &str) -> Result<Option<String>> {
+        Ok(
+            sqlx::query_scalar("SELECT webhook_url FROM accounts WHERE id = $1")
+                .bind(account_id)
+                .fetch_optional(&self.pool)
+                .await?,
+        )
+    }
+
+    async fn update_account(
+        &self,
+        id: &str,
+        webhook_url: Option<&str>,
+        metadata: Option<&str>,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE accounts SET
+                webhook_url = COALESCE($2, webhook_url),
+                metadata = COALESCE($3::jsonb, metadata)
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(webhook_url)
+        .bind(metadata)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn deactivate_account(&self, id: &str) -> Result<bool> {
+        let result =
+            sqlx::query("UPDATE accounts SET status = $2, status_updated_at = $3 WHERE id = $1")
+                .bind(id)
+                .bind(AccountStatus::Deactivated.as_str())
+                .bind(now_secs() as i64)
+                .execute(&self.pool)
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_account_status(&self, id: &str) -> Result<AccountStatus> {
+        let status: Option<String> =
+            sqlx::query_scalar("SELECT status FROM accounts WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(status
+            .map(|s| AccountStatus::parse(&s))
+            .unwrap_or(AccountStatus::Active))
+    }
+
+    async fn get_account_metadata(&self, id: &str) -> Result<Option<String>> {
+        let metadata: Option<Option<String>> =
+            sqlx::query_scalar("SELECT metadata::text FROM accounts WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(metadata.flatten())
+    }
+
+    async fn record_deposit(&self, deposit: &Deposit) -> Result<bool> {
+        insert_deposit(&self.pool, deposit).await
+    }
+
+    async fn get_deposit(&self, key: &str) -> Result<Option<Deposit>> {
+        let record: Option<Json<Deposit>> =
+            sqlx::query_scalar("SELECT record FROM deposits WHERE key = $1")
+                .bind(key)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(record.map(|Json(d)| d))
+    }
+
+    async fn mark_deposit_sweeping(&self, key: &str) -> Result<Option<Deposit>> {
+        let mut claimed = false;
+        let deposit = self
+            .update_deposit(key, |deposit| {
+                if deposit.status == DepositStatus::Detected {
+                    deposit.transition(DepositStatus::Sweeping, None)?;
+                    claimed = true;
+                }
+                Ok(())
+            })
+            .await?;
+        Ok(claimed.then_some(deposit))
+    }
+
+    async fn mark_deposit_swept(&self, key: &str, sweep_tx_hash: &str) -> Result<Deposit> {
+        self.update_deposit(key, |deposit| {
+            deposit.transition(DepositStatus::Swept, None)?;
+            deposit.sweep_tx_hash = Some(sweep_tx_hash.to_string());
+            deposit.last_error = None;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn mark_deposit_failed(
+        &self,
+        key: &str,
+        sweep_tx_hash: &str,
+        error: &str,
+    ) -> Result<Deposit> {
+        self.update_deposit(key, |deposit| {
+            deposit.transition(DepositStatus::Failed, Some(error))?;
+            deposit.sweep_tx_hash = Some(sweep_tx_hash.to_string());
+            deposit.last_error = Some(error.to_string());
+            Ok(())
+        })
+        .await
+    }
+
+    async fn release_deposit(&self, key: &str, error: &str) -> Result<Deposit> {
+        self.update_deposit(key, |deposit| {
+            if deposit.status == DepositStatus::Sweeping {
+                deposit.transition(DepositStatus::Detected, Some(error))?;
+                deposit.last_error = Some(error.to_string());
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn quarantine_deposit(&self, key: &str, reason: &str) -> Result<Deposit> {
+        self.update_deposit(key, |deposit| {
+            deposit.transition(DepositStatus::Quarantined, Some(reason))?;
+            deposit.last_error = Some(reason.to_string());
+            Ok(())
+        })
+        .await
+    }
+
+    async fn requeue_interrupted_sweeps(
+        &self,
+        chain_id: u64,
+        started_before: u64,
+    ) -> Result<usize> {
+        let keys: Vec<String> =
+            sqlx::query_scalar("SELECT key FROM deposits WHERE status = $1 AND chain_id = $2")
+                .bind(DepositStatus::Sweeping.as_str())
+                .bind(chain_id as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+        let mut requeued = 0;
+        for key in &keys {
+            let mut released = false;
+            self.update_deposit(key, |deposit| {
+                if deposit.status == DepositStatus::Sweeping
+                    && deposit.updated_at() < started_before
+                {
+                    deposit.transition(DepositStatus::Detected, Some("sweep interrupted"))?;
+                    deposit.last_error = Some("sweep interrupted".to_string());
+                    released = true;
+                }
+                Ok(())
+            })
+            .await?;
+            requeued += released as usize;
+        }
+        Ok(requeued)
+    }
+
+    async fn get_deposits_by_status(
+        &self,
+        chain_id: u64,
+        status: DepositStatus,
+    ) -> Result<Vec<Deposit>> {
+        let condition = format!("status = $1 AND chain_id = {}", chain_id);
+        self.deposits_where(&condition, Some(status.as_str())).await
+    }
+
+    async fn get_detected_deposits(&self, chain_id: u64) -> Result<Vec<Deposit>> {
+        let condition = format!(
+            "status = 'detected' AND chain_id = {} AND token_address IS NULL",
+            chain_id
+        );
+        self.deposits_where(&condition, None).await
+    }
+
+    async fn get_detected_erc20_deposits(&self, chain_id: u64) -> Result<Vec<Deposit>> {
+        let condition = format!(
+            "status = 'detected' AND chain_id = {} AND token_address IS NOT NULL",
+            chain_id
+        );
+        self.deposits_where(&condition, None).await
+    }
+
+    async fn get_deposits_by_tx_hash(&self, chain_id: u64, tx_hash: &str) -> Result<Vec<Deposit>> {
+        let condition = format!("chain_id = {} AND tx_hash = $1", chain_id);
+        self.deposits_where(&condition, Some(tx_hash)).await
+    }
+
+    /// Deposits are listed in (block_number, key) order; the cursor is block_number:key
+    async fn list_deposits(
+        &self,
+        filter: &DepositFilter,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<DepositPage> {
+        let status = filter
+            .status
+            .as_deref()
+            .map(DepositStatus::from_str)
+            .transpose()?;
+        let (cursor_block, cursor_key) = match cursor {
+            Some(c) => {
+                let (block, key) = c
+                    .split_once(':')
+                    .and_then(|(b, k)| Some((b.parse::<i64>().ok()?, k)))
+                    .ok_or_else(|| anyhow::anyhow!("Invalid cursor"))?;
+                (Some(block), Some(key))
+            }
+            None => (None, None),
+        };
+
+        // One extra row tells whether another page follows
+        let mut deposits: Vec<Deposit> = sqlx::query_scalar::<_, Json<Deposit>>(
+            "SELECT record FROM deposits
+             WHERE ($1::text IS NULL OR account_id = $1)
+               AND ($2::text IS NULL OR status = $2)
+               AND ($3::text IS NULL OR CASE
+                    WHEN lower($3) = 'native' THEN token_address IS NULL
+                    ELSE lower(token_address) = lower($3) OR lower(token_symbol) = lower($3)
+                   END)
+               AND ($4::bigint IS NULL OR (block_number, key) > ($4, $5))
+               AND ($7::bigint IS NULL OR chain_id = $7)
+             ORDER BY block_number, key
+             LIMIT $6",
+        )
+        .bind(filter.account_id.as_deref())
+        .bind(status.map(|s| s.as_str()))
+        .bind(filter.token.as_deref())
+        .bind(cursor_block)
+        .bind(cursor_key)
+        .bind(limit as i64 + 1)
+        .bind(filter.chain_id.map(|c| c as i64))
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|Json(d)| d)
+        .collect();
+
+        let next_cursor = if deposits.len() > limit {
+            deposits.truncate(limit);
+            deposits
+                .last()
+                .map(|d| format!("{}:{}", d.block_number, d.key))
+        } else {
+            None
+        };
+
+        Ok(DepositPage {
+            deposits,
+            next_cursor,
+        })
+    }
+
+    async fn get_last_processed_block(&self, chain_id: u64) -> Result<u64> {
+        let value: Option<String> = sqlx::query_scalar("SELECT value FROM state WHERE key = $1")
+            .bind(last_block_key(chain_id))
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(value.and_then(|v| v.parse().ok()).unwrap_or(0))
+    }
+
+    async fn set_last_processed_block(&self, chain_id: u64, block: u64) -> Result<()> {
+        set_state(&self.pool, &last_block_key(chain_id), &block.to_string()).await
+    }
+
+    /// Instances starting at the same time serialize on the marker row; the later ones
+    /// find it already set and change nothing
+    async fn assign_legacy_chain(&self, chain_id: u64) -> Result<usize> {
+        let mut txn = self.pool.begin().await?;
+        let claimed = sqlx::query(
+            "INSERT INTO state (key, value) VALUES ($1, $2) ON CONFLICT (key) DO NOTHING",
+        )
+        .bind(LEGACY_CHAIN_KEY)
+        .bind(chain_id.to_string())
+        .execute(&mut *txn)
+        .await?
+        .rows_affected()
+            > 0;
+        if !claimed {
+            return Ok(0);
+        }
+
+        let accounts = sqlx::query(
+            "INSERT INTO account_chains (account_id, chain_id, address)
+             SELECT id, $1, address FROM accounts a
+             WHERE NOT EXISTS (SELECT 1 FROM account_chains c WHERE c.account_id = a.id)",
+        )
+        .bind(chain_id as i64)
+        .execute(&mut *txn)
+        .await?
+        .rows_affected();
+        // Keys of deposits without a chain start with "0:"; swap in the assigned chain
+        let deposits = sqlx::query(
+            "UPDATE deposits SET chain_id = $1, key = $1::text || substr(key, 2),
+                record = jsonb_set(
+                    jsonb_set(record, '{chain_id}', to_jsonb($1)),
+                    '{key}', to_jsonb($1::text || substr(key, 2))
+                )
+             WHERE chain_id = 0",
+        )
+        .bind(chain_id as i64)
+        .execute(&mut *txn)
+        .await?
+        .rows_affected();
+        sqlx::query(
+            "UPDATE state SET key = $2 WHERE key = $1
+             AND NOT EXISTS (SELECT 1 FROM state WHERE key = $2)",
+        )
+        .bind(LEGACY_LAST_BLOCK_KEY)
+        .bind(last_block_key(chain_id))
+        .execute(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+        Ok((accounts + deposits) as usize)
+    }
+
+    async fn store_token_metadata(
+        &self,
+        address: &str,
+        symbol: &str,
+        decimals: u8,
+        name: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO token_metadata (address, symbol, decimals, name) VALUES ($1, $2, $3, $4)
+             ON CONFLICT (address) DO UPDATE SET
+                symbol = EXCLUDED.symbol,
+                decimals = EXCLUDED.decimals,
+                name = EXCLUDED.name",
+        )
+        .bind(address)
+        .bind(symbol)
+        .bind(decimals as i16)
+        .bind(name)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_token_metadata(&self, address: &str) -> Result<Option<(String, u8, String)>> {
+        let row: Option<(String, i16, String)> =
+            sqlx::query_as("SELECT symbol, decimals, name FROM token_metadata WHERE address = $1")
+                .bind(address)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(row.map(|(symbol, decimals, name)| (symbol, decimals as u8, name)))
+    }
+
+    async fn create_api_key(&self, key: &ApiKey) -> Result<()> {
+        let scopes: Vec<&str> = key.scopes.iter().map(Scope::as_str).collect();
+        sqlx::query(
+            "INSERT INTO api_keys (id, name, scopes, key_hash, created_at, revoked_at)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(&key.id)
+        .bind(&key.name)
+        .bind(&scopes)
+        .bind(&key.key_hash)
+        .bind(key.created_at as i64)
+        .bind(key.revoked_at.map(|t| t as i64))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to create API key {}: {}", key.id, e))?;
+        Ok(())
+    }
+
+    async fn get_api_key(&self, id: &str) -> Result<Option<ApiKey>> {
+        let row: Option<ApiKeyRow> = sqlx::query_as(
+            "SELECT id, name, scopes, key_hash, created_at, revoked_at FROM api_keys WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(api_key_from_row).transpose()
+    }
+
+    async fn list_api_keys(&self) -> Result<Vec<ApiKey>> {
+        let rows: Vec<ApiKeyRow> = sqlx::query_as(
+            "SELECT id, name, scopes, key_hash, created_at, revoked_at FROM api_keys ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(api_key_from_row).collect()
+    }
+
+    async fn revoke_api_key(&self, id: &str) -> Result<bool> {
+        let result =
+            sqlx::query("UPDATE api_keys SET revoked_at = COALESCE(revoked_at, $2) WHERE id = $1")
+                .bind(id)
+                .bind(now_secs() as i64)
+                .execute(&self.pool)
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_idempotency_record(&self, key: &str) -> Result<Option<IdempotencyRecord>> {
+        let row: Option<IdempotencyRow> = sqlx::query_as(
+            "SELECT key, request_hash, response, created_at FROM idempotency_keys WHERE key = $1",
+        )
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(idempotency_record_from_row))
+    }
+
+    async fn save_idempotency_record(
+        &self,
+        record: &IdempotencyRecord,
+        expired_before: u64,
+    ) -> Result<IdempotencyRecord> {
+        let mut txn = self.pool.begin().await?;
+        sqlx::query("DELETE FROM idempotency_keys WHERE created_at < $1")
+            .bind(expired_before as i64)
+            .execute(&mut *txn)
+            .await?;
+        sqlx::query(
+            "INSERT INTO idempotency_keys (key, request_hash, response, created_at)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (key) DO NOTHING",
+        )
+        .bind(&record.key)
+        .bind(&record.request_hash)
+        .bind(&record.response)
+        .bind(record.created_at as i64)
+        .execute(&mut *txn)
+        .await?;
+        let row: IdempotencyRow = sqlx::query_as(
+            "SELECT key, request_hash, response, created_at FROM idempotency_keys WHERE key = $1",
+        )
+        .bind(&record.key)
+        .fetch_one(&mut *txn)
+        .await?;
+        txn.commit().await?;
+        Ok(idempotency_record_from_row(row))
+    }
+
+    async fn begin_funding(&self, funding: &NewFunding, cap: Option<U256>) -> Result<FundingStart> {
+        let mut txn = self.pool.begin().await?;
+        // A funding pending past its lease no longer holds off the address
+        sqlx::query(
+            "UPDATE faucet_fundings SET status = $1, updated_at = $2
+             WHERE chain_id = $3 AND lower(address) = lower($4) AND status = 'pending'
+               AND created_at < $5",
+        )
+        .bind(FundingStatus::Interrupted.as_str())
+        .bind(now_secs() as i64)
+        .bind(funding.chain_id as i64)
+        .bind(&funding.address)
+        .bind(now_secs().saturating_sub(PENDING_LEASE_SECS) as i64)
+        .execute(&mut *txn)
+        .await?;
+        let previous: Vec<FundingRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM faucet_fundings WHERE chain_id = $1 AND lower(address) = lower($2)",
+            FUNDING_COLUMNS
+        ))
+        .bind(funding.chain_id as i64)
+        .bind(&funding.address)
+        .fetch_all(&mut *txn)
+        .await?;
+        let previous = previous
+            .into_iter()
+            .map(funding_record_from_row)
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some(in_flight) = previous.iter().find(|f| f.status == FundingStatus::Pending) {
+            return Ok(FundingStart::InFlight(in_flight.clone()));
+        }
+        let spent = crate::db::capped_spend(&previous)?;
+        if cap.is_some_and(|cap| spent.saturating_add(funding.amount) > cap) {
+            return Ok(FundingStart::CapReached { spent });
+        }
+
+        let now = now_secs() as i64;
+        // A concurrent funding of the address that started first wins the pending index
+        let row: Option<FundingRow> = sqlx::query_as(&format!(
+            "INSERT INTO faucet_fundings
+                (chain_id, address, amount, reason, status, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, 'pending', $5, $5)
+             ON CONFLICT (chain_id, lower(address)) WHERE status = 'pending' DO NOTHING
+             RETURNING {}",
+            FUNDING_COLUMNS
+        ))
+        .bind(funding.chain_id as i64)
+        .bind(&funding.address)
+        .bind(funding.amount.to_string())
+        .bind(funding.reason.as_str())
+        .bind(now)
+        .fetch_optional(&mut *txn)
+        .await?;
+        let start = match row {
+            Some(row) => FundingStart::Started(funding_record_from_row(row)?),
+            None => {
+                let row: FundingRow = sqlx::query_as(&format!(
+                    "SELECT {} FROM faucet_fundings
+                     WHERE chain_id = $1 AND lower(address) = lower($2) AND status = 'pending'",
+                    FUNDING_COLUMNS
+                ))
+                .bind(funding.chain_id as i64)
+                .bind(&funding.address)
+                .fetch_one(&mut *txn)
+                .await?;
+                FundingStart::InFlight(funding_record_from_row(row)?)
+            }
+        };
+        txn.commit().await?;
+        Ok(start)
+    }
+
+    async fn mark_funding_sent(&self, id: u64, tx_hash: &str) -> Result<FundingRecord> {
+        self.finish_funding(id, FundingStatus::Sent, Some(tx_hash), None)
+            .await
+    }
+
+    async fn mark_funding_failed(&self, id: u64, error: &str) -> Result<FundingRecord> {
+        self.finish_funding(id, FundingStatus::Failed, None, Some(error))
+            .await
+    }
+
+    async fn interrupt_pending_fundings(
+        &self,
+        chain_id: u64,
+        started_before: u64,
+    ) -> Result<usize> {
+        let result = sqlx::query(
+            "UPDATE faucet_fundings SET status = $1, updated_at = $2
+             WHERE chain_id = $3 AND status = 'pending' AND created_at < $4",
+        )
+        .bind(FundingStatus::Interrupted.as_str())
+        .bind(now_secs() as i64)
+        .bind(chain_id as i64)
+        .bind(started_before as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn list_fundings(&self, filter: &FundingFilter) -> Result<Vec<FundingRecord>> {
+        let rows: Vec<FundingRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM faucet_fundings
+             WHERE ($1::BIGINT IS NULL OR chain_id = $1)
+               AND ($2::TEXT IS NULL OR lower(address) = lower($2))
+             ORDER BY id",
+            FUNDING_COLUMNS
+        ))
+        .bind(filter.chain_id.map(|id| id as i64))
+        .bind(filter.address.as_deref())
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(funding_record_from_row).collect()
+    }
+
+    async fn begin_dust_reclaim(&self, reclaim: &NewDustReclaim) -> Result<DustReclaimRecord> {
+        self.insert_dust_reclaim(reclaim, DustReclaimStatus::Pending, None)
+            .await
+    }
+
+    async fn skip_dust_reclaim(
+        &self,
+        reclaim: &NewDustReclaim,
+        reason: &str,
+    ) -> Result<DustReclaimRecord> {
+        self.insert_dust_reclaim(reclaim, DustReclaimStatus::Skipped, Some(reason))
+            .await
+    }
+
+    async fn mark_dust_reclaim_sent(&self, id: u64, tx_hash: &str) -> Result<DustReclaimRecord> {
+        self.finish_dust_reclaim(id, DustReclaimStatus::Sent, Some(tx_hash), None)
+            .await
+    }
+
+    async fn mark_dust_reclaim_failed(&self, id: u64, error: &str) -> Result<DustReclaimRecord> {
+        self.finish_dust_reclaim(id, DustReclaimStatus::Failed, None, Some(error))
+            .await
+    }
+
+    async fn interrupt_pending_dust_reclaims(
+        &self,
+        chain_id: u64,
+        started_before: u64,
+    ) -> Result<usize> {
+        let result = sqlx::query(
+            "UPDATE dust_reclaims SET status = $1, updated_at = $2
+             WHERE chain_id = $3 AND status = 'pending' AND created_at < $4",
+        )
+        .bind(DustReclaimStatus::Interrupted.as_str())
+        .bind(now_secs() as i64)
+        .bind(chain_id as i64)
+        .bind(started_before as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn queue_funding(
+        &self,
+        chain_id: u64,
+        account_id: &str,
+        address: &str,
+        reason: FundingReason,
+    ) -> Result<QueuedFunding> {
+        let row: QueuedFundingRow = sqlx::query_as(&format!(
+            "INSERT INTO funding_queue (chain_id, account_id, address, reason, queued_at)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING {}",
+            QUEUED_FUNDING_COLUMNS
+        ))
+        .bind(chain_id as i64)
+        .bind(account_id)
+        .bind(address)
+        .bind(reason.as_str())
+        .bind(now_secs() as i64)
+        .fetch_one(&self.pool)
+        .await?;
+        queued_funding_from_row(row)
+    }
+
+    async fn take_queued_funding(&self, id: u64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM funding_queue WHERE id = $1")
+            .bind(id as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_queued_fundings(&self, chain_id: u64) -> Result<Vec<QueuedFunding>> {
+        let rows: Vec<QueuedFundingRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM funding_queue WHERE chain_id = $1 ORDER BY id",
+            QUEUED_FUNDING_COLUMNS
+        ))
+        .bind(chain_id as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(queued_funding_from_row).collect()
+    }
+
+    async fn list_dust_reclaims(&self, chain_id: Option<u64>) -> Result<Vec<DustReclaimRecord>> {
+        let rows: Vec<DustReclaimRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM dust_reclaims
+             WHERE ($1::BIGINT IS NULL OR chain_id = $1)
+             ORDER BY id",
+            DUST_RECLAIM_COLUMNS
+        ))
+        .bind(chain_id.map(|id| id as i64))
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(dust_reclaim_record_from_row).collect()
+    }
+
+    async fn record_setting_changes(&self, changes: &[SettingChange]) -> Result<()> {
+        let mut txn = self.pool.begin().await?;
+        for change in changes {
+            sqlx::query(
+                "INSERT INTO settings_audit (changed_at, source, chain_id, setting, old_value, new_value)
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+            )
+            .bind(change.changed_at as i64)
+            .bind(&change.source)
+            .bind(change.chain_id.map(|id| id as i64))
+            .bind(&change.setting)
+            .bind(&change.old_value)
+            .bind(&change.new_value)
+            .execute(&mut *txn)
+            .await?;
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+
+    async fn list_setting_changes(&self, limit: usize) -> Result<Vec<SettingChange>> {
+        let rows: Vec<(i64, String, Option<i64>, String, String, String)> = sqlx::query_as(
+            "SELECT changed_at, source, chain_id, setting, old_value, new_value
+             FROM settings_audit ORDER BY id DESC LIMIT $1",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(
+                |(changed_at, source, chain_id, setting, old_value, new_value)| SettingChange {
+                    changed_at: changed_at as u64,
+                    source,
+                    chain_id: chain_id.map(|id| id as u64),
+                    setting,
+                    old_value,
+                    new_value,
+                },
+            )
+            .collect())
+    }
+
+    /// Reads everything in one REPEATABLE READ transaction so the snapshot is consistent
+    async fn export(&self, out: &mut (dyn Write + Send)) -> Result<ExportSummary> {
+        let mut txn = self.pool.begin().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut *txn)
+            .await?;
+
+        type AccountRow = (
+            String,
+            i64,
+            String,
+            String,
+            String,
+            Option<i64>,
+            Option<Json<serde_json::Value>>,
+        );
+        let accounts: Vec<AccountRow> = sqlx::query_as(
+            "SELECT id, derivation_index, address, webhook_url, status, status_updated_at, metadata
+             FROM accounts ORDER BY id",
+        )
+        .fetch_all(&mut *txn)
+        .await?;
+        let chains: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT account_id, chain_id FROM account_chains ORDER BY account_id, chain_id",
+        )
+        .fetch_all(&mut *txn)
+        .await?;
+        let deposits: Vec<Json<Deposit>> =
+            sqlx::query_scalar("SELECT record FROM deposits ORDER BY key")
+                .fetch_all(&mut *txn)
+                .await?;
+        let tokens: Vec<(String, String, i16, String)> = sqlx::query_as(
+            "SELECT address, symbol, decimals, name FROM token_metadata ORDER BY address",
+        )
+        .fetch_all(&mut *txn)
+        .await?;
+        // The schema version belongs to the database, not to the exported data
+        let state: Vec<(String, String)> =
+            sqlx::query_as("SELECT key, value FROM state WHERE key <> $1 ORDER BY key")
+                .bind(SCHEMA_VERSION_KEY)
+                .fetch_all(&mut *txn)
+                .await?;
+        let fundings: Vec<FundingRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM faucet_fundings ORDER BY id",
+            FUNDING_COLUMNS
+        ))
+        .fetch_all(&mut *txn)
+        .await?;
+        let api_keys: Vec<ApiKeyRow> = sqlx::query_as(
+            "SELECT id, name, scopes, key_hash, created_at, revoked_at FROM api_keys ORDER BY id",
+        )
+        .fetch_all(&mut *txn)
+        .await?;
+        let changes: Vec<(i64, String, Option<i64>, String, String, String)> = sqlx::query_as(
+            "SELECT changed_at, source, chain_id, setting, old_value, new_value
+             FROM settings_audit ORDER BY id",
+        )
+        .fetch_all(&mut *txn)
+        .await?;
+        let idempotency: Vec<IdempotencyRow> = sqlx::query_as(
+            "SELECT key, request_hash, response, created_at FROM idempotency_keys ORDER BY key",
+        )
+        .fetch_all(&mut *txn)
+        .await?;
+        let reclaims: Vec<DustReclaimRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM dust_reclaims ORDER BY id",
+            DUST_RECLAIM_COLUMNS
+        ))
+        .fetch_all(&mut *txn)
+        .await?;
+        let queue: Vec<QueuedFundingRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM funding_queue ORDER BY id",
+            QUEUED_FUNDING_COLUMNS
+        ))
+        .fetch_all(&mut *txn)
+        .await?;
+        txn.commit().await?;
+
+        let mut writer = ExportWriter::new(out)?;
+        for (id, index, address, webhook_url, status, status_updated_at, metadata) in accounts {
+            let chains = chains
+                .iter()
+                .filter(|(account_id, _)| *account_id == id)
+                .map(|(_, chain_id)| *chain_id as u64)
+                .collect();
+            writer.write(&ExportRecord::Account(AccountRecord {
+                id,
+                derivation_index: index as u32,
+                address,
+                webhook_url,
+                status,
+                status_updated_at: status_updated_at.map(|t| t as u64),
+                metadata: metadata.map(|Json(m)| m),
+                chains,
+            }))?;
+        }
+        for Json(deposit) in deposits {
+            writer.write(&ExportRecord::Deposit(deposit))?;
+        }
+        for (address, symbol, decimals, name) in tokens {
+            writer.write(&ExportRecord::TokenMetadata {
+                address,
+                symbol,
+                decimals: decimals as u8,
+                name,
+            })?;
+        }
+        for (key, value) in state {
+            writer.write(&ExportRecord::State { key, value })?;
+        }
+        for row in fundings {
+            writer.write(&ExportRecord::Funding(funding_record_from_row(row)?))?;
+        }
+        for row in api_keys {
+            writer.write(&ExportRecord::ApiKey(api_key_from_row(row)?))?;
+        }
+        for (changed_at, source, chain_id, setting, old_value, new_value) in changes {
+            writer.write(&ExportRecord::SettingChange(SettingChange {
+                changed_at: changed_at as u64,
+                source,
+                chain_id: chain_id.map(|id| id as u64),
+                setting,
+                old_value,
+                new_value,
+            }))?;
+        }
+        for row in idempotency {
+            writer.write(&ExportRecord::IdempotencyKey(idempotency_record_from_row(
+                row,
+            )))?;
+        }
+        for row in reclaims {
+            writer.write(&ExportRecord::DustReclaim(dust_reclaim_record_from_row(
+                row,
+            )?))?;
+        }
+        for row in queue {
+            writer.write(&ExportRecord::QueuedFunding(queued_funding_from_row(row)?))?;
+        }
+        writer.finish()
+    }
+
+    async fn import(&self, records: &[ExportRecord]) -> Result<ExportSummary> {
+        let mut txn = self.pool.begin().await?;
+        let existing: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM accounts) OR EXISTS (SELECT 1 FROM deposits)
+                OR EXISTS (SELECT 1 FROM faucet_fundings)",
+        )
+        .fetch_one(&mut *txn)
+        .await?;
+        if existing {
+            return Err(anyhow::anyhow!(
+                "Refusing to import into a database that already holds accounts, deposits, or fundings"
+            ));
+        }
+        // Exports from before chains were tracked carry unassigned records; let the next
+        // start assign them unless the export says they already were
+        sqlx::query("DELETE FROM state WHERE key = $1")
+            .bind(LEGACY_CHAIN_KEY)
+            .execute(&mut *txn)
+            .await?;
+
+        for record in records {
+            match record {
+                ExportRecord::Header { .. } => {}
+                ExportRecord::Account(account) => {
+                    sqlx::query(
+                        "INSERT INTO accounts
+                            (id, derivation_index, address, webhook_url, status, status_updated_at, metadata)
+                         VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                    )
+                    .bind(&account.id)
+                    .bind(account.derivation_index as i64)
+                    .bind(&account.address)
+                    .bind(&account.webhook_url)
+                    .bind(&account.status)
+                    .bind(account.status_updated_at.map(|t| t as i64))
+                    .bind(account.metadata.as_ref().map(Json))
+                    .execute(&mut *txn)
+                    .await?;
+                    insert_account_chains(&mut txn, &account.id, &account.chains).await?;
+                }
+                ExportRecord::Deposit(deposit) => {
+                    // Exports written before deposits were keyed by chain carry older keys
+                    let mut deposit = deposit.clone();
+                    deposit.key =
+                        Deposit::key_for(deposit.chain_id, &deposit.tx_hash, deposit.log_index);
+                    insert_deposit(&mut *txn, &deposit).await?;
+                }
+                ExportRecord::TokenMetadata {
+                    address,
+                    symbol,
+                    decimals,
+                    name,
+                } => {
+                    sqlx::query(
+                        "INSERT INTO token_metadata (address, symbol, decimals, name)
+                         VALUES ($1, $2, $3, $4)",
+                    )
+                    .bind(address)
+                    .bind(symbol)
+                    .bind(*decimals as i16)
+                    .bind(name)
+                    .execute(&mut *txn)
+                    .await?;
+                }
+                ExportRecord::State { key, value } => {
+                    if key != SCHEMA_VERSION_KEY {
+                        set_state(&mut *txn, key, value).await?;
+                    }
+                }
+                ExportRecord::Funding(funding) => {
+                    sqlx::query(
+                        "INSERT INTO faucet_fundings
+                            (id, chain_id, address, amount, reason, status, tx_hash, error, created_at, updated_at)
+                         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+                    )
+                    .bind(funding.id as i64)
+                    .bind(funding.chain_id as i64)
+                    .bind(&funding.address)
+                    .bind(&funding.amount)
+                    .bind(funding.reason.as_str())
+                    .bind(funding.status.as_str())
+                    .bind(&funding.tx_hash)
+                    .bind(&funding.error)
+                    .bind(funding.created_at as i64)
+                    .bind(funding.updated_at as i64)
+                    .execute(&mut *txn)
+                    .await?;
+                }
+                ExportRecord::ApiKey(key) => {
+                    let scopes: Vec<&str> = key.scopes.iter().map(Scope::as_str).collect();
+                    sqlx::query(
+                        "INSERT INTO api_keys (id, name, scopes, key_hash, created_at, revoked_at)
+                         VALUES ($1, $2, $3, $4, $5, $6)",
+                    )
+                    .bind(&key.id)
+                    .bind(&key.name)
+                    .bind(&scopes)
+                    .bind(&key.key_hash)
+                    .bind(key.created_at as i64)
+                    .bind(key.revoked_at.map(|t| t as i64))
+                    .execute(&mut *txn)
+                    .await?;
+                }
+                ExportRecord::SettingChange(change) => {
+                    sqlx::query(
+                        "INSERT INTO settings_audit (changed_at, source, chain_id, setting, old_value, new_value)
+                         VALUES ($1, $2, $3, $4, $5, $6)",
+                    )
+                    .bind(change.changed_at as i64)
+                    .bind(&change.source)
+                    .bind(change.chain_id.map(|id| id as i64))
+                    .bind(&change.setting)
+                    .bind(&change.old_value)
+                    .bind(&change.new_value)
+                    .execute(&mut *txn)
+                    .await?;
+                }
+                ExportRecord::IdempotencyKey(record) => {
+                    sqlx::query(
+                        "INSERT INTO idempotency_keys (key, request_hash, response, created_at)
+                         VALUES ($1, $2, $3, $4)",
+                    )
+                    .bind(&record.key)
+                    .bind(&record.request_hash)
+                    .bind(&record.response)
+                    .bind(record.created_at as i64)
+                    .execute(&mut *txn)
+                    .await?;
+                }
+                ExportRecord::DustReclaim(reclaim) => {
+                    sqlx::query(
+                        "INSERT INTO dust_reclaims
+                            (id, chain_id, address, destination, amount, status, tx_hash, error, created_at, updated_at)
+                         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+                    )
+                    .bind(reclaim.id as i64)
+                    .bind(reclaim.chain_id as i64)
+                    .bind(&reclaim.address)
+                    .bind(&reclaim.destination)
+                    .bind(&reclaim.amount)
+                    .bind(reclaim.status.as_str())
+                    .bind(&reclaim.tx_hash)
+                    .bind(&reclaim.error)
+                    .bind(reclaim.created_at as i64)
+                    .bind(reclaim.updated_at as i64)
+                    .execute(&mut *txn)
+                    .await?;
+                }
+                ExportRecord::QueuedFunding(queued) => {
+                    sqlx::query(
+                        "INSERT INTO funding_queue
+                            (id, chain_id, account_id, address, reason, queued_at)
+                         VALUES ($1, $2, $3, $4, $5, $6)",
+                    )
+                    .bind(queued.id as i64)
+                    .bind(queued.chain_id as i64)
+                    .bind(&queued.account_id)
+                    .bind(&queued.address)
+                    .bind(queued.reason.as_str())
+                    .bind(queued.queued_at as i64)
+                    .execute(&mut *txn)
+                    .await?;
+                }
+            }
+        }
+        // Imported ids were given explicitly; new rows continue after them
+        for table in ["faucet_fundings", "dust_reclaims", "funding_queue"] {
+            sqlx::query(&format!(
+                "SELECT setval(pg_get_serial_sequence('{0}', 'id'),
+                               (SELECT COALESCE(MAX(id), 0) + 1 FROM {0}), false)",
+                table
+            ))
+            .execute(&mut *txn)
+            .await?;
+        }
+
+        txn.commit().await?;
+        Ok(summarize(records))
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.pool.close().await;
+        Ok(())
+    }
+}
+
+/// (id, name, scopes, key_hash, created_at, revoked_at)
+type ApiKeyRow = (String, String, Vec<String>, String, i64, Option<i64>);
+
+fn api_key_from_row(
+    (id, name, scopes, key_hash, created_at, revoked_at): ApiKeyRow,
+) -> Result<ApiKey> {
+    Ok(ApiKey {
+        id,
+        name,
+        scopes: scopes
+            .iter()
+            .map(|s| s.parse())
+            .collect::<Result<Vec<Scope>>>()?,
+        key_hash,
+        created_at: created_at as u64,
+        revoked_at: revoked_at.map(|t| t as u64),
+    })
+}
+
+const FUNDING_COLUMNS: &str =
+    "id, chain_id, address, amount, reason, status, tx_hash, error, created_at, updated_at";
+
+/// The columns of [`FUNDING_COLUMNS`]
+type FundingRow = (
+    i64,
+    i64,
+    String,
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    i64,
+    i64,
+);
+
+fn funding_record_from_row(
+    (id, chain_id, address, amount, reason, status, tx_hash, error, created_at, updated_at): FundingRow,
+) -> Result<FundingRecord> {
+    Ok(FundingRecord {
+        id: id as u64,
+        chain_id: chain_id as u64,
+        address,
+        amount,
+        reason: reason.parse()?,
+        status: status.parse()?,
+        tx_hash,
+        error,
+        created_at: created_at as u64,
+        updated_at: updated_at as u64,
+    })
+}
+
+const DUST_RECLAIM_COLUMNS: &str =
+    "id, chain_id, address, destination, amount, status, tx_hash, error, created_at, updated_at";
+
+/// The columns of [`DUST_RECLAIM_COLUMNS`]
+type DustReclaimRow = (
+    i64,
+    i64,
+    String,
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    i64,
+    i64,
+);
+
+fn dust_reclaim_record_from_row(
+    (id, chain_id, address, destination, amount, status, tx_hash, error, created_at, updated_at): DustReclaimRow,
+) -> Result<DustReclaimRecord> {
+    Ok(DustReclaimRecord {
+        id: id as u64,
+        chain_id: chain_id as u64,
+        address,
+        destination,
+        amount,
+        status: status.parse()?,
+        tx_hash,
+        error,
+        created_at: created_at as u64,
+        updated_at: updated_at as u64,
+    })
+}
+
+const QUEUED_FUNDING_COLUMNS: &str = "id, chain_id, account_id, address, reason, queued_at";
+
+/// The columns of [`QUEUED_FUNDING_COLUMNS`]
+type QueuedFundingRow = (i64, i64, String, String, String, i64);
+
+fn queued_funding_from_row(
+    (id, chain_id, account_id, address, reason, queued_at): QueuedFundingRow,
+) -> Result<QueuedFunding> {
+    Ok(QueuedFunding {
+        id: id as u64,
+        chain_id: chain_id as u64,
+        account_id,
+        address,
+        reason: reason.parse()?,
+        queued_at: queued_at as u64,
+    })
+}
+
+/// (key, request_hash, response, created_at)
+type IdempotencyRow = (String, String, String, i64);
+
+fn idempotency_record_from_row(
+    (key, request_hash, response, created_at): IdempotencyRow,
+) -> IdempotencyRecord {
+    IdempotencyRecord {
+        key,
+        request_hash,
+        response,
+        created_at: created_at as u64,
+    }
+}
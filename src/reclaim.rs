@@ -0,0 +1,306 @@
+use crate::config::{ChainConfig, Config, DustReclaim};
+use crate::db::{DustReclaimStatus, NewDustReclaim, PENDING_LEASE_SECS};
+use crate::deposit::{now_secs, DepositStatus};
+use crate::faucet::Faucet;
+use crate::metrics::{metrics, observe_rpc};
+use crate::settings::Settings;
+use crate::storage::Storage;
+use crate::traits::ServiceContext;
+use crate::wallet::Wallet;
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, U256};
+use alloy::providers::Provider;
+use alloy::rpc::types::{BlockNumberOrTag, TransactionRequest};
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+/// Gas of a plain native transfer
+const TRANSFER_GAS: u64 = 21_000;
+
+/// Deposits whose address still needs its native balance: waiting to be swept, or
+/// waiting for an operator
+const UNSETTLED: [DepositStatus; 5] = [
+    DepositStatus::Detected,
+    DepositStatus::Confirming,
+    DepositStatus::Sweeping,
+    DepositStatus::Failed,
+    DepositStatus::Quarantined,
+];
+
+/// Sends the native balance left on swept addresses (the gas buffer of native sweeps,
+/// what remains of faucet fundings after ERC20 sweeps) to the faucet or the treasury.
+///
+/// Run by the chain's [`Sweeper`](crate::sweeper::Sweeper) between sweep cycles, so a
+/// reclaim never races a sweep from the same address. Every check is recorded; an address
+/// is checked again only after another of its deposits is swept.
+pub struct DustReclaimer<P> {
+    chain: ChainConfig,
+    destination: DustReclaim,
+    interval: Duration,
+    /// Read on every pass for the treasury address
+    settings: Settings,
+    db: Arc<dyn Storage>,
+    wallet: Wallet,
+    provider: P,
+    faucet: Arc<Faucet<P>>,
+    last_pass: Mutex<Option<Instant>>,
+}
+
+impl<P> DustReclaimer<P>
+where
+    P: Provider,
+{
+    /// `provider` and `faucet` must be connected to `chain`, whose chain id must already
+    /// be resolved
+    pub fn new(
+        config: &Config,
+        chain: ChainConfig,
+        db: Arc<dyn Storage>,
+        wallet: Wallet,
+        provider: P,
+        faucet: Arc<Faucet<P>>,
+    ) -> Self {
+        Self {
+            chain,
+            destination: config.dust_reclaim,
+            interval: Duration::from_secs(config.dust_reclaim_interval_secs),
+            settings: Settings::new(config),
+            db,
+            wallet,
+            provider,
+            faucet,
+            last_pass: Mutex::new(None),
+        }
+    }
+
+    /// Follow `settings` instead of the values `config` had at construction
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Run a pass if `DUST_RECLAIM_INTERVAL_SECS` elapsed since the last one
+    pub async fn reclaim_if_due(&self, ctx: &ServiceContext) {
+        {
+            let mut last_pass = self.last_pass.lock().unwrap();
+            if last_pass.is_some_and(|last| last.elapsed() < self.interval) {
+                return;
+            }
+            *last_pass = Some(Instant::now());
+        }
+        if let Err(e) = self.reclaim(ctx).await {
+            error!(
+                "Dust reclaim pass failed on chain {}: {:?}",
+                self.chain.chain_id, e
+            );
+        }
+    }
+
+    /// Check every address swept since its last check and reclaim its dust. Returns how
+    /// many addresses were checked.
+    pub async fn reclaim(&self, ctx: &ServiceContext) -> Result<usize> {
+        let destination = match self.destination {
+            DustReclaim::Off => return Ok(0),
+            DustReclaim::Faucet => self.faucet.address()?,
+            DustReclaim::Treasury => {
+                Address::from_str(&self.settings.chain(self.chain.chain_id)?.treasury_address)?
+            }
+        };
+        let chain_id = self.chain.chain_id;
+        // Every deposit up to this block is recorded, so an address's balance there holds
+        // no deposit still to be swept
+        let settled_block = self.db.get_last_processed_block(chain_id).await?;
+        let Some(block) = observe_rpc(
+            "eth_getBlockByNumber",
+            self.provider
+                .get_block_by_number(BlockNumberOrTag::Number(settled_block), false),
+        )
+        .await?
+        else {
+            return Err(anyhow::anyhow!("Block {} not found", settled_block));
+        };
+        // Sweeps after it aren't reflected in that balance yet; they wait for a later pass
+        let settled_at = block.header.timestamp;
+
+        let mut unsettled = HashSet::new();
+        for status in UNSETTLED {
+            for deposit in self.db.get_deposits_by_status(chain_id, status).await? {
+                unsettled.insert(deposit.account_id);
+            }
+        }
+        // Registration id -> when its last deposit was swept
+        let mut last_swept = HashMap::new();
+        for deposit in self
+            .db
+            .get_deposits_by_status(chain_id, DepositStatus::Swept)
+            .await?
+        {
+            if unsettled.contains(&deposit.account_id) {
+                continue;
+            }
+            let swept_at = deposit.history.last().map_or(0, |change| change.at);
+            if swept_at > settled_at {
+                unsettled.insert(deposit.account_id);
+                continue;
+            }
+            let entry = last_swept.entry(deposit.account_id).or_insert(0);
+            *entry = swept_at.max(*entry);
+        }
+        // Reclaims pending past their lease were left by a stopped process; fresher ones
+        // are in flight, possibly on another instance, and their addresses wait
+        self.db
+            .interrupt_pending_dust_reclaims(
+                chain_id,
+                now_secs().saturating_sub(PENDING_LEASE_SECS),
+            )
+            .await?;
+        // Lower-cased address -> when it was last settled by a check
+        let mut last_checked = HashMap::new();
+        let mut in_flight = HashSet::new();
+        for reclaim in self.db.list_dust_reclaims(Some(chain_id)).await? {
+            if reclaim.status == DustReclaimStatus::Pending {
+                in_flight.insert(reclaim.address.to_ascii_lowercase());
+            } else if reclaim.status.settles() {
+                let entry = last_checked
+                    .entry(reclaim.address.to_ascii_lowercase())
+                    .or_insert(0);
+                *entry = reclaim.created_at.max(*entry);
+            }
+        }
+
+        let mut candidates: Vec<_> = last_swept.into_iter().collect();
+        candidates.sort();
+        let mut checked = 0;
+        for (registration_id, swept_at) in candidates {
+            if ctx.is_cancelled() {
+                break;
+            }
+            let Some((index, address, _)) = self.db.get_account_by_id(&registration_id).await?
+            else {
+                continue;
+            };
+            let address_key = address.to_ascii_lowercase();
+            if in_flight.contains(&address_key)
+                || last_checked
+                    .get(&address_key)
+                    .is_some_and(|&checked_at| checked_at >= swept_at)
+            {
+                continue;
+            }
+            if let Err(e) = self
+                .reclaim_address(index, &address, destination, settled_block)
+                .await
+            {
+                error!(
+                    "Failed to reclaim dust from {} on chain {}: {:?}",
+                    address, chain_id, e
+                );
+            }
+            checked += 1;
+        }
+        Ok(checked)
+    }
+
+    async fn reclaim_address(
+        &self,
+        index: u32,
+        address_str: &str,
+        destination: Address,
+        settled_block: u64,
+    ) -> Result<()> {
+        let chain_id = self.chain.chain_id;
+        let address = Address::from_str(address_str)?;
+        let settled = observe_rpc(
+            "eth_getBalance",
+            self.provider.get_balance(address).number(settled_block),
+        )
+        .await?;
+        let latest = observe_rpc("eth_getBalance", self.provider.get_balance(address)).await?;
+        let balance = settled.min(latest);
+
+        let fees = observe_rpc("eth_feeHistory", self.provider.estimate_eip1559_fees(None)).await?;
+        let gas_cost = U256::from(TRANSFER_GAS) * U256::from(fees.max_fee_per_gas);
+        let amount = balance.saturating_sub(gas_cost);
+        let reclaim = NewDustReclaim {
+            chain_id,
+            address: address_str.to_string(),
+            destination: destination.to_string(),
+            amount,
+        };
+        let outcome = |outcome: &str| {
+            metrics()
+                .dust_reclaims
+                .with_label_values(&[&chain_id.to_string(), outcome])
+                .inc();
+        };
+
+        let min = U256::from_str(&self.chain.dust_reclaim_min)?;
+        if amount <= min {
+            let reason = format!(
+                "{} wei left after {} wei of gas is not more than {} wei",
+                amount, gas_cost, min
+            );
+            self.db.skip_dust_reclaim(&reclaim, &reason).await?;
+            outcome("skipped");
+            return Ok(());
+        }
+
+        let record = self.db.begin_dust_reclaim(&reclaim).await?;
+        info!(
+            "Reclaiming {} wei of dust from {} to {} on chain {}",
+            amount, address_str, destination, chain_id
+        );
+        match self.send(index, destination, amount, &fees).await {
+            Ok(tx_hash) => {
+                outcome("sent");
+                info!("Reclaimed dust from {} with tx: {}", address_str, tx_hash);
+                self.db.mark_dust_reclaim_sent(record.id, &tx_hash).await?;
+            }
+            Err(e) => {
+                outcome("failed");
+                self.db
+                    .mark_dust_reclaim_failed(record.id, &e.to_string())
+                    .await?;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn send(
+        &self,
+        index: u32,
+        destination: Address,
+        amount: U256,
+        fees: &alloy::providers::utils::Eip1559Estimation,
+    ) -> Result<String> {
+        let wallet = alloy::network::EthereumWallet::from(self.wallet.get_signer(index)?);
+        let provider = alloy::providers::ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(wallet)
+            .on_provider(&self.provider);
+
+        // The fees the amount was computed with, so the gas can't eat into it
+        let tx = TransactionRequest::default()
+            .with_to(destination)
+            .with_value(amount)
+            .with_gas_limit(TRANSFER_GAS.into())
+            .with_max_fee_per_gas(fees.max_fee_per_gas)
+            .with_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+        let pending_tx =
+            observe_rpc("eth_sendRawTransaction", provider.send_transaction(tx)).await?;
+        let receipt = observe_rpc("eth_getTransactionReceipt", pending_tx.get_receipt()).await?;
+        let tx_hash = receipt.transaction_hash.to_string();
+        if !receipt.status() {
+            return Err(anyhow::anyhow!(
+                "Dust reclaim transaction {} reverted",
+                tx_hash
+            ));
+        }
+        Ok(tx_hash)
+    }
+}
@@ -0,0 +1,296 @@
+use crate::config::{check_address, check_range, check_wei, Config, ConfigErrors, DEFAULT_CHAIN};
+use crate::deposit::now_secs;
+use alloy::primitives::U256;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use utoipa::ToSchema;
+
+/// The settings that can change while the service runs. Everything else in [`Config`]
+/// takes effect on the next start.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ToSchema)]
+pub struct RuntimeSettings {
+    /// Seconds the Monitors and Sweepers wait between cycles
+    pub poll_interval: u64,
+    /// In the order of `Config::chains`
+    pub chains: Vec<ChainSettings>,
+}
+
+/// Runtime-adjustable settings of one chain
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ToSchema)]
+pub struct ChainSettings {
+    pub name: String,
+    pub chain_id: u64,
+    pub treasury_address: String,
+    pub existential_deposit: String,
+    pub block_offset_from_head: u64,
+}
+
+/// Body of `PATCH /admin/settings`; omitted fields keep their value
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SettingsUpdate {
+    /// Chain the per-chain settings apply to; the default chain if omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll_interval: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub treasury_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub existential_deposit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_offset_from_head: Option<u64>,
+}
+
+/// An entry of the settings audit log
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ToSchema)]
+pub struct SettingChange {
+    /// Unix timestamp (seconds)
+    pub changed_at: u64,
+    /// What made the change, e.g. "api" or "sighup"
+    pub source: String,
+    /// Absent for settings shared by every chain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    pub setting: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+/// Response of `PATCH /admin/settings`
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct SettingsUpdateResponse {
+    pub settings: RuntimeSettings,
+    /// Settings whose value changed; empty if the update changed nothing
+    pub changes: Vec<SettingChange>,
+}
+
+impl RuntimeSettings {
+    /// The settings of `config`, whose chain ids must already be resolved
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            poll_interval: config.poll_interval,
+            chains: config
+                .chains
+                .iter()
+                .map(|chain| ChainSettings {
+                    name: chain.name.clone(),
+                    chain_id: chain.chain_id,
+                    treasury_address: chain.treasury_address.clone(),
+                    existential_deposit: chain.existential_deposit.clone(),
+                    block_offset_from_head: chain.block_offset_from_head,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn chain(&self, chain_id: u64) -> Option<&ChainSettings> {
+        self.chains.iter().find(|c| c.chain_id == chain_id)
+    }
+
+    /// These settings with the values of a reloaded, already validated `config`. Chains are
+    /// matched by name; chains `config` no longer lists keep their values, and chains it
+    /// adds are ignored until the next start. As with [`updated`](Self::updated), the poll
+    /// interval may not exceed the running `max_poll_interval`, whatever `config` allows.
+    pub fn reloaded(
+        &self,
+        config: &Config,
+        max_poll_interval: u64,
+    ) -> std::result::Result<Self, ConfigErrors> {
+        let mut problems = Vec::new();
+        check_range(
+            "poll_interval",
+            config.poll_interval,
+            1,
+            max_poll_interval.clamp(1, 3600),
+            &mut problems,
+        );
+        if !problems.is_empty() {
+            return Err(ConfigErrors(problems));
+        }
+
+        let mut next = self.clone();
+        next.poll_interval = config.poll_interval;
+        for chain in &mut next.chains {
+            if let Some(reloaded) = config.chains.iter().find(|c| c.name == chain.name) {
+                chain.treasury_address = reloaded.treasury_address.clone();
+                chain.existential_deposit = reloaded.existential_deposit.clone();
+                chain.block_offset_from_head = reloaded.block_offset_from_head;
+            }
+        }
+        Ok(next)
+    }
+
+    /// These settings with `update` applied, checked like the configuration is at startup.
+    /// The poll interval may not exceed `max_poll_interval`, the sweeper idle time that
+    /// fails readiness.
+    pub fn updated(
+        &self,
+        update: &SettingsUpdate,
+        max_poll_interval: u64,
+    ) -> std::result::Result<Self, ConfigErrors> {
+        let mut next = self.clone();
+        let mut problems = Vec::new();
+
+        if let Some(poll_interval) = update.poll_interval {
+            check_range(
+                "poll_interval",
+                poll_interval,
+                1,
+                max_poll_interval.clamp(1, 3600),
+                &mut problems,
+            );
+            next.poll_interval = poll_interval;
+        }
+
+        let changes_chain = update.treasury_address.is_some()
+            || update.existential_deposit.is_some()
+            || update.block_offset_from_head.is_some();
+        if changes_chain {
+            let single = next.chains.len() == 1;
+            let chain = match update.chain_id {
+                Some(chain_id) => next.chains.iter_mut().find(|c| c.chain_id == chain_id),
+                None => next.chains.first_mut(),
+            };
+            match chain {
+                None => problems.push(format!(
+                    "chain_id: chain {} is not configured",
+                    update.chain_id.unwrap_or_default()
+                )),
+                Some(chain) => {
+                    let field = |name: &str| {
+                        if single && chain.name == DEFAULT_CHAIN {
+                            name.to_string()
+                        } else {
+                            format!("chains.{}.{}", chain.name, name)
+                        }
+                    };
+                    if let Some(address) = &update.treasury_address {
+                        check_address(&field("treasury_address"), address, &mut problems);
+                    }
+                    if let Some(amount) = &update.existential_deposit {
+                        let field = field("existential_deposit");
+                        if check_wei(&field, amount, &mut problems) == Some(U256::ZERO) {
+                            problems.push(format!("{}: must be greater than 0", field));
+                        }
+                    }
+                    if let Some(offset) = update.block_offset_from_head {
+                        check_range(
+                            &field("block_offset_from_head"),
+                            offset,
+                            0,
+                            10_000,
+                            &mut problems,
+                        );
+                    }
+
+                    if let Some(address) = &update.treasury_address {
+                        chain.treasury_address = address.clone();
+                    }
+                    if let Some(amount) = &update.existential_deposit {
+                        chain.existential_deposit = amount.clone();
+                    }
+                    if let Some(offset) = update.block_offset_from_head {
+                        chain.block_offset_from_head = offset;
+                    }
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(next)
+        } else {
+            Err(ConfigErrors(problems))
+        }
+    }
+
+    /// Every setting whose value differs in `next`, as audit log entries
+    pub fn changes(&self, next: &Self, source: &str) -> Vec<SettingChange> {
+        let changed_at = now_secs();
+        let change = |chain_id, setting: &str, old: String, new: String| SettingChange {
+            changed_at,
+            source: source.to_string(),
+            chain_id,
+            setting: setting.to_string(),
+            old_value: old,
+            new_value: new,
+        };
+
+        let mut changes = Vec::new();
+        if self.poll_interval != next.poll_interval {
+            changes.push(change(
+                None,
+                "poll_interval",
+                self.poll_interval.to_string(),
+                next.poll_interval.to_string(),
+            ));
+        }
+        for old in &self.chains {
+            let Some(new) = next.chain(old.chain_id) else {
+                continue;
+            };
+            let chain_id = Some(old.chain_id);
+            if old.treasury_address != new.treasury_address {
+                changes.push(change(
+                    chain_id,
+                    "treasury_address",
+                    old.treasury_address.clone(),
+                    new.treasury_address.clone(),
+                ));
+            }
+            if old.existential_deposit != new.existential_deposit {
+                changes.push(change(
+                    chain_id,
+                    "existential_deposit",
+                    old.existential_deposit.clone(),
+                    new.existential_deposit.clone(),
+                ));
+            }
+            if old.block_offset_from_head != new.block_offset_from_head {
+                changes.push(change(
+                    chain_id,
+                    "block_offset_from_head",
+                    old.block_offset_from_head.to_string(),
+                    new.block_offset_from_head.to_string(),
+                ));
+            }
+        }
+        changes
+    }
+}
+
+/// Shared handle to the current [`RuntimeSettings`]. The Monitors, Sweepers, and faucets
+/// read it on every cycle, so a change applies without restarting them.
+#[derive(Clone, Debug)]
+pub struct Settings(Arc<RwLock<RuntimeSettings>>);
+
+impl Settings {
+    pub fn new(config: &Config) -> Self {
+        Self(Arc::new(RwLock::new(RuntimeSettings::from_config(config))))
+    }
+
+    pub fn current(&self) -> RuntimeSettings {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn poll_interval(&self) -> u64 {
+        self.0
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .poll_interval
+    }
+
+    pub fn chain(&self, chain_id: u64) -> Result<ChainSettings> {
+        self.0
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .chain(chain_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No settings for chain {}", chain_id))
+    }
+
+    pub(crate) fn set(&self, settings: RuntimeSettings) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = settings;
+    }
+}
@@ -0,0 +1,337 @@
+use crate::auth::ApiKey;
+use crate::db::{
+    AccountStatus, Db, DepositFilter, DepositPage, DustReclaimRecord, FundingFilter, FundingReason,
+    FundingRecord, FundingStart, IdempotencyRecord, MigrationReport, NewAccount, NewDustReclaim,
+    NewFunding, QueuedFunding, Registration,
+};
+use crate::deposit::{Deposit, DepositStatus};
+use crate::export::{ExportRecord, ExportSummary};
+use crate::postgres::PgStorage;
+use crate::settings::SettingChange;
+use alloy::primitives::U256;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Persistence used by the Monitor, the Sweeper, and HotWalletService.
+///
+/// Implemented by the embedded redb [`Db`] and by [`PgStorage`] for PostgreSQL;
+/// [`open`] picks one from the `DATABASE_URL` scheme.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    // ========== Accounts ==========
+
+    /// Register an account, or if `id` is registered already, watch its address on
+    /// `chain_ids` too. The existence check and the writes happen in one transaction, so
+    /// concurrent registrations of the same id create the account once.
+    async fn register_or_add_chains(
+        &self,
+        id: &str,
+        index: u32,
+        address: &str,
+        webhook_url: &str,
+        chain_ids: &[u64],
+    ) -> Result<Registration> {
+        let account = NewAccount {
+            id: id.to_string(),
+            index,
+            address: address.to_string(),
+            webhook_url: webhook_url.to_string(),
+            chain_ids: chain_ids.to_vec(),
+        };
+        let mut registrations = self.register_accounts(&[account]).await?;
+        Ok(registrations.remove(0))
+    }
+
+    /// [`register_or_add_chains`](Self::register_or_add_chains) for many accounts in one
+    /// transaction, returning their registrations in order. If any id belongs to a
+    /// deactivated account, nothing is written.
+    async fn register_accounts(&self, accounts: &[NewAccount]) -> Result<Vec<Registration>>;
+
+    /// Chains an account's address is watched on, in ascending order
+    async fn get_account_chains(&self, id: &str) -> Result<Vec<u64>>;
+
+    /// Registration id of the account owning `address`, if it is watched on `chain_id`
+    async fn get_registration_id_by_address(
+        &self,
+        chain_id: u64,
+        address: &str,
+    ) -> Result<Option<String>>;
+
+    /// (derivation index, address, webhook URL) of an account
+    async fn get_account_by_id(&self, id: &str) -> Result<Option<(u32, String, String)>>;
+
+    async fn get_webhook_url(&self, account_id: &str) -> Result<Option<String>>;
+
+    /// Update the webhook URL and/or metadata of an account.
+    /// Returns false if the account does not exist.
+    async fn update_account(
+        &self,
+        id: &str,
+        webhook_url: Option<&str>,
+        metadata: Option<&str>,
+    ) -> Result<bool>;
+
+    /// Mark an account as deactivated. Returns false if the account does not exist.
+    async fn deactivate_account(&self, id: &str) -> Result<bool>;
+
+    async fn get_account_status(&self, id: &str) -> Result<AccountStatus>;
+
+    async fn is_account_active(&self, id: &str) -> Result<bool> {
+        Ok(self.get_account_status(id).await? == AccountStatus::Active)
+    }
+
+    async fn get_account_metadata(&self, id: &str) -> Result<Option<String>>;
+
+    // ========== Deposits ==========
+
+    /// Record a deposit and return true if it was newly recorded, false if it was a duplicate
+    async fn record_deposit(&self, deposit: &Deposit) -> Result<bool>;
+
+    async fn get_deposit(&self, key: &str) -> Result<Option<Deposit>>;
+
+    /// Claim a detected deposit for sweeping. Returns `None` if it is no longer detected,
+    /// e.g. because another instance claimed it first.
+    async fn mark_deposit_sweeping(&self, key: &str) -> Result<Option<Deposit>>;
+
+    async fn mark_deposit_swept(&self, key: &str, sweep_tx_hash: &str) -> Result<Deposit>;
+
+    /// The sweep transaction was mined but reverted
+    async fn mark_deposit_failed(
+        &self,
+        key: &str,
+        sweep_tx_hash: &str,
+        error: &str,
+    ) -> Result<Deposit>;
+
+    /// Return a sweeping deposit to detected so the next sweep cycle retries it
+    async fn release_deposit(&self, key: &str, error: &str) -> Result<Deposit>;
+
+    /// Set a deposit aside for manual review
+    async fn quarantine_deposit(&self, key: &str, reason: &str) -> Result<Deposit>;
+
+    /// Return deposits on `chain_id` claimed for sweeping before `started_before` (unix
+    /// seconds) to detected
+    async fn requeue_interrupted_sweeps(&self, chain_id: u64, started_before: u64)
+        -> Result<usize>;
+
+    /// All deposits (native and ERC20) on `chain_id` currently in `status`
+    async fn get_deposits_by_status(
+        &self,
+        chain_id: u64,
+        status: DepositStatus,
+    ) -> Result<Vec<Deposit>>;
+
+    /// Native deposits on `chain_id` waiting to be swept
+    async fn get_detected_deposits(&self, chain_id: u64) -> Result<Vec<Deposit>>;
+
+    /// ERC20 deposits on `chain_id` waiting to be swept
+    async fn get_detected_erc20_deposits(&self, chain_id: u64) -> Result<Vec<Deposit>>;
+
+    /// All deposits (native and ERC20) belonging to a transaction on `chain_id`
+    async fn get_deposits_by_tx_hash(&self, chain_id: u64, tx_hash: &str) -> Result<Vec<Deposit>>;
+
+    /// List deposits matching `filter`; `cursor` is the `next_cursor` of the previous page
+    async fn list_deposits(
+        &self,
+        filter: &DepositFilter,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<DepositPage>;
+
+    // ========== State ==========
+
+    async fn get_last_processed_block(&self, chain_id: u64) -> Result<u64>;
+
+    async fn set_last_processed_block(&self, chain_id: u64, block: u64) -> Result<()>;
+
+    /// Assign accounts, deposits, and the last processed block written before chains were
+    /// tracked to `chain_id`. Only the first call has an effect; returns the number of
+    /// accounts and deposits it assigned.
+    async fn assign_legacy_chain(&self, chain_id: u64) -> Result<usize>;
+
+    // ========== ERC20 Token Metadata ==========
+
+    async fn store_token_metadata(
+        &self,
+        address: &str,
+        symbol: &str,
+        decimals: u8,
+        name: &str,
+    ) -> Result<()>;
+
+    /// (symbol, decimals, name) of a token
+    async fn get_token_metadata(&self, address: &str) -> Result<Option<(String, u8, String)>>;
+
+    // ========== API Keys ==========
+
+    /// Store a new API key; fails if its id is taken
+    async fn create_api_key(&self, key: &ApiKey) -> Result<()>;
+
+    async fn get_api_key(&self, id: &str) -> Result<Option<ApiKey>>;
+
+    /// Every API key, revoked ones included, ordered by id
+    async fn list_api_keys(&self) -> Result<Vec<ApiKey>>;
+
+    /// Mark an API key as revoked. Returns false if no key has this id.
+    async fn revoke_api_key(&self, id: &str) -> Result<bool>;
+
+    // ========== Idempotency Keys ==========
+
+    async fn get_idempotency_record(&self, key: &str) -> Result<Option<IdempotencyRecord>>;
+
+    /// Store `record` unless its key has a record already, after dropping records created
+    /// before `expired_before`. Returns the record stored for the key, which is the
+    /// earlier one if a concurrent request saved first.
+    async fn save_idempotency_record(
+        &self,
+        record: &IdempotencyRecord,
+        expired_before: u64,
+    ) -> Result<IdempotencyRecord>;
+
+    // ========== Faucet Funding Ledger ==========
+
+    /// Record `funding` as pending before it is sent, unless the address has a pending
+    /// funding on the chain already or the funding would take it past `cap` wei over its
+    /// lifetime. The check and the record are atomic, so concurrent fundings of one
+    /// address can't both start.
+    async fn begin_funding(&self, funding: &NewFunding, cap: Option<U256>) -> Result<FundingStart>;
+
+    async fn mark_funding_sent(&self, id: u64, tx_hash: &str) -> Result<FundingRecord>;
+
+    async fn mark_funding_failed(&self, id: u64, error: &str) -> Result<FundingRecord>;
+
+    /// Mark fundings of `chain_id` pending since before `started_before` as interrupted.
+    /// Returns how many there were.
+    async fn interrupt_pending_fundings(&self, chain_id: u64, started_before: u64)
+        -> Result<usize>;
+
+    /// Fundings matching `filter`, oldest first
+    async fn list_fundings(&self, filter: &FundingFilter) -> Result<Vec<FundingRecord>>;
+
+    // ========== Funding Queue ==========
+
+    /// Store a funding waiting in the queue of `chain_id`, so it survives a restart
+    async fn queue_funding(
+        &self,
+        chain_id: u64,
+        account_id: &str,
+        address: &str,
+        reason: FundingReason,
+    ) -> Result<QueuedFunding>;
+
+    /// Remove a queued funding to send it. Returns false if it was taken already, e.g. by
+    /// another instance sharing the database.
+    async fn take_queued_funding(&self, id: u64) -> Result<bool>;
+
+    /// Fundings still queued on `chain_id`, oldest first
+    async fn list_queued_fundings(&self, chain_id: u64) -> Result<Vec<QueuedFunding>>;
+
+    // ========== Dust Reclaims ==========
+
+    /// Record `reclaim` as pending before it is sent
+    async fn begin_dust_reclaim(&self, reclaim: &NewDustReclaim) -> Result<DustReclaimRecord>;
+
+    /// Record that `reclaim` was checked but not sent, and why
+    async fn skip_dust_reclaim(
+        &self,
+        reclaim: &NewDustReclaim,
+        reason: &str,
+    ) -> Result<DustReclaimRecord>;
+
+    async fn mark_dust_reclaim_sent(&self, id: u64, tx_hash: &str) -> Result<DustReclaimRecord>;
+
+    async fn mark_dust_reclaim_failed(&self, id: u64, error: &str) -> Result<DustReclaimRecord>;
+
+    /// Mark dust reclaims of `chain_id` pending since before `started_before` as
+    /// interrupted. Returns how many there were.
+    async fn interrupt_pending_dust_reclaims(
+        &self,
+        chain_id: u64,
+        started_before: u64,
+    ) -> Result<usize>;
+
+    /// Dust reclaims on `chain_id`, or on every chain, oldest first
+    async fn list_dust_reclaims(&self, chain_id: Option<u64>) -> Result<Vec<DustReclaimRecord>>;
+
+    // ========== Settings Audit Log ==========
+
+    /// Append changes of runtime settings to the audit log
+    async fn record_setting_changes(&self, changes: &[SettingChange]) -> Result<()>;
+
+    /// The most recent `limit` audit log entries, newest first
+    async fn list_setting_changes(&self, limit: usize) -> Result<Vec<SettingChange>>;
+
+    // ========== Backup ==========
+
+    /// Write every stored record as JSON lines, all read from one consistent snapshot
+    async fn export(&self, out: &mut (dyn Write + Send)) -> Result<ExportSummary>;
+
+    /// Load exported records in one transaction. Fails if the storage already holds
+    /// accounts, deposits, or fundings.
+    async fn import(&self, records: &[ExportRecord]) -> Result<ExportSummary>;
+
+    /// Release connections before the process exits. Writes are already durable
+    /// once the call that made them returns.
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Where a `DATABASE_URL` points
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StorageUrl {
+    /// Path of an embedded redb database file
+    Redb(String),
+    /// PostgreSQL connection URL
+    Postgres(String),
+}
+
+impl StorageUrl {
+    /// `postgres://` and `postgresql://` select PostgreSQL; `redb:` prefixed and plain paths
+    /// select the embedded redb file.
+    ///
+    /// `sqlite:` URLs are accepted for compatibility: earlier releases passed the whole URL
+    /// to redb, so the file on disk is literally named e.g. `sqlite:wallet.db`.
+    pub fn parse(url: &str) -> Result<Self> {
+        if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+            return Ok(StorageUrl::Postgres(url.to_string()));
+        }
+        if let Some(path) = url.strip_prefix("redb:") {
+            let path = path.strip_prefix("//").unwrap_or(path);
+            return Ok(StorageUrl::Redb(path.to_string()));
+        }
+        if url.starts_with("sqlite:") {
+            tracing::warn!(
+                "DATABASE_URL {} is a redb file, not SQLite; rename it and use a redb: URL",
+                url
+            );
+            return Ok(StorageUrl::Redb(url.to_string()));
+        }
+        if let Some((scheme, _)) = url.split_once("://") {
+            return Err(anyhow::anyhow!(
+                "Unsupported DATABASE_URL scheme: {} (expected postgres://, postgresql://, or redb:)",
+                scheme
+            ));
+        }
+        Ok(StorageUrl::Redb(url.to_string()))
+    }
+}
+
+/// Open the storage backend selected by `database_url`, running pending migrations
+pub async fn open(database_url: &str) -> Result<Arc<dyn Storage>> {
+    match StorageUrl::parse(database_url)? {
+        StorageUrl::Redb(path) => Ok(Arc::new(Db::new(&path)?)),
+        StorageUrl::Postgres(url) => Ok(Arc::new(PgStorage::connect(&url).await?)),
+    }
+}
+
+/// Run pending migrations of the backend selected by `database_url` without starting the service.
+/// With `dry_run` the migrations are executed but rolled back.
+pub async fn migrate(database_url: &str, dry_run: bool) -> Result<MigrationReport> {
+    match StorageUrl::parse(database_url)? {
+        StorageUrl::Redb(path) => Db::migrate(&path, dry_run),
+        StorageUrl::Postgres(url) => PgStorage::migrate(&url, dry_run).await,
+    }
+}
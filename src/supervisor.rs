@@ -0,0 +1,190 @@
+use crate::health::HealthCheck;
+use crate::metrics::metrics;
+use crate::traits::{Service, ServiceContext, ServiceStatus};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// Delay before the first restart of a crashed service; doubled on every further crash
+pub const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// A service that ran at least this long before crashing restarts after [`INITIAL_BACKOFF`] again
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+struct Supervised {
+    name: String,
+    ctx: ServiceContext,
+    restarts: Arc<AtomicU32>,
+}
+
+/// Runs background services, restarting them with exponential backoff when they panic
+/// or return before shutdown, and stops them all on [`shutdown`](Supervisor::shutdown).
+pub struct Supervisor {
+    shutdown: CancellationToken,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    services: Mutex<Vec<Supervised>>,
+    handles: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self::new(INITIAL_BACKOFF, MAX_BACKOFF)
+    }
+}
+
+impl Supervisor {
+    pub fn new(initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            shutdown: CancellationToken::new(),
+            initial_backoff,
+            max_backoff,
+            services: Mutex::new(Vec::new()),
+            handles: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Cancelled when shutdown starts, e.g. to stop the API server at the same time
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    pub fn spawn(&self, service: Arc<dyn Service>) {
+        let name = service.name();
+        let ctx = ServiceContext::new(self.shutdown.clone());
+        let restarts = Arc::new(AtomicU32::new(0));
+
+        let handle = tokio::spawn(supervise(
+            service,
+            ctx.clone(),
+            restarts.clone(),
+            self.initial_backoff,
+            self.max_backoff,
+        ));
+
+        self.services.lock().unwrap().push(Supervised {
+            name,
+            ctx,
+            restarts,
+        });
+        self.handles.lock().unwrap().push(handle);
+    }
+
+    /// (name, status, restart count) of every supervised service
+    pub fn statuses(&self) -> Vec<(String, ServiceStatus, u32)> {
+        self.services
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|s| {
+                (
+                    s.name.clone(),
+                    s.ctx.status(),
+                    s.restarts.load(Ordering::Relaxed),
+                )
+            })
+            .collect()
+    }
+
+    /// Ask every service to stop and wait up to `timeout` for them to finish their
+    /// current unit of work. Returns false if some were still running at the deadline.
+    pub async fn shutdown(&self, timeout: Duration) -> bool {
+        self.shutdown.cancel();
+        for service in self.services.lock().unwrap().iter() {
+            if service.ctx.status() != ServiceStatus::Stopped {
+                service.ctx.set_status(ServiceStatus::Stopping);
+            }
+        }
+
+        let handles: Vec<_> = self.handles.lock().unwrap().drain(..).collect();
+        tokio::time::timeout(timeout, futures::future::join_all(handles))
+            .await
+            .is_ok()
+    }
+
+    /// Liveness: fails if no service was started or one stopped outside of shutdown.
+    /// A service waiting to be restarted is still considered alive.
+    pub fn liveness(&self) -> HealthCheck {
+        self.check(|status| status != ServiceStatus::Stopped)
+    }
+
+    /// Readiness: every service must be up, not crashed and waiting for a restart
+    pub fn readiness(&self) -> HealthCheck {
+        self.check(|status| matches!(status, ServiceStatus::Starting | ServiceStatus::Running))
+    }
+
+    fn check(&self, healthy: impl Fn(ServiceStatus) -> bool) -> HealthCheck {
+        let statuses = self.statuses();
+        if statuses.is_empty() {
+            return HealthCheck::fail("background_tasks", "background services not started");
+        }
+
+        let detail = statuses
+            .iter()
+            .map(|(name, status, restarts)| match restarts {
+                0 => format!("{}: {}", name, status.as_str()),
+                n => format!("{}: {} ({} restarts)", name, status.as_str(), n),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        if !self.shutdown.is_cancelled() && statuses.iter().all(|(_, s, _)| healthy(*s)) {
+            HealthCheck::pass("background_tasks", detail)
+        } else {
+            HealthCheck::fail("background_tasks", detail)
+        }
+    }
+}
+
+async fn supervise(
+    service: Arc<dyn Service>,
+    ctx: ServiceContext,
+    restarts: Arc<AtomicU32>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+) {
+    let name = service.name();
+    let mut backoff = initial_backoff;
+
+    loop {
+        ctx.set_status(ServiceStatus::Starting);
+        let started = Instant::now();
+
+        // Run in its own task so a panic surfaces as a JoinError instead of unwinding here
+        let result = tokio::spawn({
+            let service = service.clone();
+            let ctx = ctx.clone();
+            async move { service.run(ctx).await }
+        })
+        .await;
+
+        if ctx.is_cancelled() {
+            break;
+        }
+
+        match result {
+            Ok(()) => error!("💥 {} exited unexpectedly", name),
+            Err(e) => error!("💥 {} crashed: {}", name, e),
+        }
+
+        if started.elapsed() >= STABLE_AFTER {
+            backoff = initial_backoff;
+        }
+        restarts.fetch_add(1, Ordering::Relaxed);
+        metrics().service_restarts.with_label_values(&[&name]).inc();
+        ctx.set_status(ServiceStatus::Restarting);
+        warn!("🔁 Restarting {} in {:?}", name, backoff);
+
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = ctx.cancelled() => break,
+        }
+        backoff = (backoff * 2).min(max_backoff);
+    }
+
+    ctx.set_status(ServiceStatus::Stopped);
+    info!("🛑 {} stopped", name);
+}
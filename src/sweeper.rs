@@ -1,9 +1,11 @@
-use crate::{
-    config::Config,
-    db::{Db, Erc20Deposit},
-    faucet::Faucet,
-    wallet::Wallet,
-};
+use crate::config::{ChainConfig, Config};
+use crate::db::PENDING_LEASE_SECS;
+use crate::deposit::now_secs;
+use crate::health::Heartbeat;
+use crate::metrics::{metrics, observe_rpc};
+use crate::reclaim::DustReclaimer;
+use crate::settings::Settings;
+use crate::{deposit::Deposit, faucet::Faucet, storage::Storage, wallet::Wallet};
 use alloy::network::TransactionBuilder;
 use alloy::primitives::{Address, U256};
 use alloy::providers::Provider;
@@ -21,73 +23,146 @@ struct Erc20WebhookInfo<'a> {
     id: &'a str,
     account_id: &'a str,      // Polygon address
     registration_id: &'a str, // Original id used when registering
-    deposit_key: &'a str,
+    tx_hash: &'a str,
     amount: &'a str,
     token_symbol: &'a str,
     token_address: &'a str,
     token_decimals: Option<u8>,
 }
 
+/// Moves deposits detected on one chain to that chain's treasury
 pub struct Sweeper<P> {
     config: Config,
-    db: Db,
+    chain: ChainConfig,
+    /// Read every cycle for the poll interval and treasury address
+    settings: Settings,
+    db: Arc<dyn Storage>,
     wallet: Wallet,
     provider: P,
     faucet: Arc<Faucet<P>>,
+    /// Beats after every sweep cycle that completed without error
+    heartbeat: Arc<Heartbeat>,
+    /// Reclaims dust between sweep cycles, when `DUST_RECLAIM` is on
+    reclaimer: Option<DustReclaimer<P>>,
 }
 
-use crate::traits::Service;
+use crate::traits::{Service, ServiceContext, ServiceStatus};
 use async_trait::async_trait;
 
 #[async_trait]
-impl<T> Service for Sweeper<alloy::providers::RootProvider<T>>
+impl<P> Service for Sweeper<P>
 where
-    T: alloy::transports::Transport + Clone,
+    P: Provider,
 {
-    async fn run(&self) {
-        loop {
-            if let Err(e) = self.process_deposits().await {
-                error!("Error in sweeper loop: {:?}", e);
+    fn name(&self) -> String {
+        format!("sweeper[{}]", self.chain.name)
+    }
+
+    async fn run(&self, ctx: ServiceContext) {
+        info!("Starting Sweeper for {}", self.chain.name);
+        ctx.set_status(ServiceStatus::Running);
+
+        while !ctx.is_cancelled() {
+            self.requeue_interrupted_sweeps().await;
+            match self.process_deposits(&ctx).await {
+                Ok(()) => self.heartbeat.beat(),
+                Err(e) => error!("Error in sweeper loop: {:?}", e),
+            }
+            if let Some(reclaimer) = &self.reclaimer {
+                reclaimer.reclaim_if_due(&ctx).await;
+            }
+            tokio::select! {
+                _ = sleep(Duration::from_secs(self.settings.poll_interval())) => {}
+                _ = ctx.cancelled() => {}
             }
-            sleep(Duration::from_secs(self.config.poll_interval)).await;
         }
+        info!("Sweeper for {} stopped", self.chain.name);
     }
 }
 
-impl<T> Sweeper<alloy::providers::RootProvider<T>>
+impl<P> Sweeper<P>
 where
-    T: alloy::transports::Transport + Clone,
+    P: Provider,
 {
+    /// `provider` and `faucet` must be connected to `chain`, whose chain id must already
+    /// be resolved
     pub fn new(
         config: Config,
-        db: Db,
+        chain: ChainConfig,
+        db: Arc<dyn Storage>,
         wallet: Wallet,
-        provider: alloy::providers::RootProvider<T>,
-        faucet: Arc<Faucet<alloy::providers::RootProvider<T>>>,
+        provider: P,
+        faucet: Arc<Faucet<P>>,
     ) -> Self {
         Self {
+            settings: Settings::new(&config),
             config,
+            chain,
             db,
             wallet,
             provider,
             faucet,
+            heartbeat: Arc::default(),
+            reclaimer: None,
         }
     }
 
-    async fn process_deposits(&self) -> Result<()> {
+    /// Follow `settings` instead of the values `config` had at construction
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Report completed sweep cycles to `heartbeat`, e.g. for readiness checks
+    pub fn with_heartbeat(mut self, heartbeat: Arc<Heartbeat>) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    /// Reclaim the dust of swept addresses with `reclaimer` between sweep cycles
+    pub fn with_dust_reclaimer(mut self, reclaimer: Option<DustReclaimer<P>>) -> Self {
+        self.reclaimer = reclaimer;
+        self
+    }
+
+    /// Retry deposits whose sweep was claimed over a lease ago by a process that has
+    /// presumably stopped, whether this one before a restart or another instance
+    async fn requeue_interrupted_sweeps(&self) {
+        let started_before = now_secs().saturating_sub(PENDING_LEASE_SECS);
+        match self
+            .db
+            .requeue_interrupted_sweeps(self.chain.chain_id, started_before)
+            .await
+        {
+            Ok(0) => {}
+            Ok(count) => info!("Requeued {} interrupted sweeps", count),
+            Err(e) => error!("Failed to requeue interrupted sweeps: {:?}", e),
+        }
+    }
+
+    /// Sweep every detected deposit on this chain. On shutdown the sweep in progress is completed
+    /// and the remaining deposits are left for the next start.
+    async fn process_deposits(&self, ctx: &ServiceContext) -> Result<()> {
         // Process native ETH deposits
-        let deposits = self.db.get_detected_deposits()?;
+        let deposits = self.db.get_detected_deposits(self.chain.chain_id).await?;
+
+        for deposit in deposits {
+            if ctx.is_cancelled() {
+                return Ok(());
+            }
+            // deposit.account_id is the registration_id (original id from registration)
+            let registration_id = &deposit.account_id;
 
-        for (tx_hash, registration_id, amount_str) in deposits {
             info!(
                 "Processing native ETH deposit: tx_hash={}, registration_id={}, amount={}",
-                tx_hash, registration_id, amount_str
+                deposit.key, registration_id, deposit.amount
             );
 
             // Get account details to derive key (registration_id is the key in ACCOUNTS table)
             let (derivation_index, address_str, _webhook_url) = self
                 .db
-                .get_account_by_id(&registration_id)?
+                .get_account_by_id(registration_id)
+                .await?
                 .ok_or_else(|| anyhow::anyhow!("Account not found"))?;
 
             let signer = self.wallet.get_signer(derivation_index)?;
@@ -98,52 +173,91 @@ where
                 .wallet(wallet)
                 .on_provider(&self.provider);
 
+            if self.db.mark_deposit_sweeping(&deposit.key).await?.is_none() {
+                info!("Deposit {} was claimed elsewhere; skipping it", deposit.key);
+                continue;
+            }
+
             match self
-                .sweep_deposit(
-                    &sweep_provider,
-                    &address_str,
-                    &tx_hash,
-                    &registration_id,
-                    &amount_str,
-                )
+                .sweep_deposit(&sweep_provider, &address_str, &deposit)
                 .await
             {
-                Ok(_) => info!("Successfully swept native ETH deposit: {}", tx_hash),
+                Ok(_) => info!("Successfully swept native ETH deposit: {}", deposit.key),
                 Err(e) => {
-                    error!("Failed to sweep native ETH deposit {}: {:?}", tx_hash, e);
+                    error!(
+                        "Failed to sweep native ETH deposit {}: {:?}",
+                        deposit.key, e
+                    );
+                    metrics()
+                        .sweep_failures
+                        .with_label_values(&[&self.chain.chain_id.to_string(), failure_reason(&e)])
+                        .inc();
+                    // Back to detected so the next sweep cycle retries it
+                    self.db
+                        .release_deposit(&deposit.key, &e.to_string())
+                        .await?;
                 }
             }
         }
 
         // Process ERC20 deposits
-        let erc20_deposits = self.db.get_detected_erc20_deposits()?;
+        let erc20_deposits = self
+            .db
+            .get_detected_erc20_deposits(self.chain.chain_id)
+            .await?;
 
         for deposit in erc20_deposits {
-            // deposit.account_id is actually the registration_id (original id from registration)
+            if ctx.is_cancelled() {
+                return Ok(());
+            }
+            // deposit.account_id is the registration_id (original id from registration)
             let registration_id = &deposit.account_id;
+            let token_symbol = deposit.token_symbol.as_deref().unwrap_or_default();
 
             info!(
                 "Processing ERC20 deposit: key={}, token={} ({}), registration_id={}, amount={}",
                 deposit.key,
-                deposit.token_symbol,
-                deposit.token_address,
+                token_symbol,
+                deposit.token_address.as_deref().unwrap_or_default(),
                 registration_id,
                 deposit.amount
             );
 
-            if deposit.token_symbol == "UNKNOWN" {
+            if token_symbol == "UNKNOWN" {
                 error!(
-                    "Skipping ERC20 deposit token symbol for deposit: {}",
+                    "Quarantining ERC20 deposit with unknown token symbol: {}",
                     deposit.key
                 );
-                self.db.mark_erc20_deposit_swept(&deposit.key)?;
+                self.db
+                    .quarantine_deposit(&deposit.key, "unknown token symbol")
+                    .await?;
+                metrics()
+                    .sweep_failures
+                    .with_label_values(&[&self.chain.chain_id.to_string(), "quarantined"])
+                    .inc();
+                continue;
+            }
+
+            if token_symbol.len() > 5 {
+                error!(
+                    "Quarantining ERC20 deposit: token symbol '{}' exceeds 5 characters for deposit: {}",
+                    token_symbol, deposit.key
+                );
+                self.db
+                    .quarantine_deposit(&deposit.key, "token symbol exceeds 5 characters")
+                    .await?;
+                metrics()
+                    .sweep_failures
+                    .with_label_values(&[&self.chain.chain_id.to_string(), "quarantined"])
+                    .inc();
                 continue;
             }
 
             // Get account details to derive key (registration_id is the key in ACCOUNTS table)
             let (derivation_index, address_str, _webhook_url) = self
                 .db
-                .get_account_by_id(registration_id)?
+                .get_account_by_id(registration_id)
+                .await?
                 .ok_or_else(|| anyhow::anyhow!("Account not found"))?;
 
             let signer = self.wallet.get_signer(derivation_index)?;
@@ -157,6 +271,11 @@ where
                 .wallet(wallet)
                 .on_provider(&self.provider);
 
+            if self.db.mark_deposit_sweeping(&deposit.key).await?.is_none() {
+                info!("Deposit {} was claimed elsewhere; skipping it", deposit.key);
+                continue;
+            }
+
             // Try to sweep, but don't fail the entire loop if one sweep fails
             match self
                 .sweep_erc20_deposit(&sweep_provider, &address_str, &deposit)
@@ -165,8 +284,15 @@ where
                 Ok(_) => info!("Successfully swept ERC20 deposit: {}", deposit.key),
                 Err(e) => {
                     error!("Failed to sweep ERC20 deposit {}: {:?}", deposit.key, e);
+                    metrics()
+                        .sweep_failures
+                        .with_label_values(&[&self.chain.chain_id.to_string(), failure_reason(&e)])
+                        .inc();
                     // Don't return error - continue processing other deposits
                     // This deposit will be retried in the next sweep cycle
+                    self.db
+                        .release_deposit(&deposit.key, &e.to_string())
+                        .await?;
                 }
             }
         }
@@ -178,24 +304,24 @@ where
         &self,
         provider: &SP,
         from_address_str: &str,
-        tx_hash: &str,
-        registration_id: &str,
-        amount_str: &str,
+        deposit: &Deposit,
     ) -> Result<()>
     where
-        SP: Provider<T, alloy::network::Ethereum>,
+        SP: Provider,
     {
         let from_address = Address::from_str(from_address_str)?;
-        let to_address = Address::from_str(&self.config.treasury_address)?;
+        let to_address =
+            Address::from_str(&self.settings.chain(self.chain.chain_id)?.treasury_address)?;
 
         // Check balance again to be sure (and to calculate gas)
-        let mut balance = provider.get_balance(from_address).await?;
+        let mut balance = observe_rpc("eth_getBalance", provider.get_balance(from_address)).await?;
 
         // Standard ETH transfer gas limit
         let gas_limit: u128 = 21000;
 
         // Get current fee estimates (EIP-1559 compatible)
-        let fee_estimate = provider.estimate_eip1559_fees(None).await?;
+        let fee_estimate =
+            observe_rpc("eth_feeHistory", provider.estimate_eip1559_fees(None)).await?;
         let max_fee_per_gas = fee_estimate.max_fee_per_gas;
 
         // Calculate gas cost with 50% buffer for price fluctuations
@@ -215,7 +341,11 @@ where
             );
 
             // Fund the address via faucet
-            match self.faucet.fund_new_address(from_address_str).await {
+            match self
+                .faucet
+                .fund_gas(from_address_str, gas_cost_with_buffer, balance)
+                .await
+            {
                 Ok(tx_hash) => {
                     info!(
                         "Successfully funded address {} via faucet with tx: {}. Waiting for balance update...",
@@ -226,7 +356,8 @@ where
                     sleep(Duration::from_secs(2)).await;
 
                     // Re-check the balance after funding
-                    balance = provider.get_balance(from_address).await?;
+                    balance =
+                        observe_rpc("eth_getBalance", provider.get_balance(from_address)).await?;
                     info!(
                         "Updated balance after faucet funding: {} wei for address {}",
                         balance, from_address_str
@@ -258,22 +389,40 @@ where
             .with_value(value_to_send)
             .with_gas_limit(gas_limit);
 
-        let pending_tx = provider.send_transaction(tx).await?;
-        let receipt = pending_tx.get_receipt().await?;
+        let pending_tx =
+            observe_rpc("eth_sendRawTransaction", provider.send_transaction(tx)).await?;
+        let receipt = observe_rpc("eth_getTransactionReceipt", pending_tx.get_receipt()).await?;
+
+        let sweep_tx_hash = receipt.transaction_hash.to_string();
+        if !receipt.status() {
+            self.db
+                .mark_deposit_failed(&deposit.key, &sweep_tx_hash, "sweep transaction reverted")
+                .await?;
+            return Err(anyhow::anyhow!(
+                "Sweep transaction {} reverted",
+                sweep_tx_hash
+            ));
+        }
 
         info!("Swept funds! Tx hash: {:?}", receipt.transaction_hash);
 
         // Update DB
-        self.db.mark_deposit_swept(tx_hash)?;
+        self.db
+            .mark_deposit_swept(&deposit.key, &sweep_tx_hash)
+            .await?;
+        metrics()
+            .deposits_swept
+            .with_label_values(&[&self.chain.chain_id.to_string(), "native"])
+            .inc();
 
         // Send Webhook (for native deposits, id = tx_hash)
         // account_id = Polygon address, registration_id = original id from registration
         self.send_webhook(
-            tx_hash,
+            &deposit.key,
             from_address_str,
-            registration_id,
-            tx_hash,
-            amount_str,
+            &deposit.account_id,
+            &deposit.tx_hash,
+            &deposit.amount,
         )
         .await?;
 
@@ -284,33 +433,34 @@ where
         &self,
         provider: &SP,
         from_address_str: &str,
-        deposit: &Erc20Deposit,
+        deposit: &Deposit,
     ) -> Result<()>
     where
-        SP: Provider<T, alloy::network::Ethereum>,
+        SP: Provider,
     {
         let from_address = Address::from_str(from_address_str)?;
-        let to_address = Address::from_str(&self.config.treasury_address)?;
-        let token_address = Address::from_str(&deposit.token_address)?;
+        let to_address =
+            Address::from_str(&self.settings.chain(self.chain.chain_id)?.treasury_address)?;
+        let token_address_str = deposit
+            .token_address
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Deposit {} has no token address", deposit.key))?;
+        let token_symbol = deposit.token_symbol.as_deref().unwrap_or_default();
+        let token_address = Address::from_str(token_address_str)?;
 
         // Check token balance first
         let token_balance = get_token_balance(&self.provider, token_address, from_address).await?;
 
-        if deposit.token_symbol.len() > 5 {
-            error!(
-                "Skipping ERC20 deposit token symbol '{}' exceeds 5 characters for deposit: {}",
-                deposit.token_symbol, deposit.key
-            );
-            self.db.mark_erc20_deposit_swept(&deposit.key)?;
-            return Ok(());
-        }
-
         if token_balance.is_zero() {
             info!(
                 "ERC20 balance is zero for {} token at {}, skipping sweep",
-                deposit.token_symbol, from_address_str
+                token_symbol, from_address_str
             );
-            return Ok(());
+            return Err(anyhow::anyhow!(
+                "ERC20 balance is zero for {} at {}",
+                token_symbol,
+                from_address_str
+            ));
         }
 
         let amount = U256::from_str(&deposit.amount).unwrap_or(token_balance);
@@ -330,12 +480,14 @@ where
             .with_input(call_data.clone());
 
         // Estimate actual gas needed for this specific transaction
-        let estimated_gas = provider.estimate_gas(&tx_for_estimate).await?;
+        let estimated_gas =
+            observe_rpc("eth_estimateGas", provider.estimate_gas(&tx_for_estimate)).await?;
 
         let gas_limit_with_buffer = estimated_gas + (estimated_gas / 10);
 
         // Get current fee estimates (EIP-1559 compatible)
-        let fee_estimate = provider.estimate_eip1559_fees(None).await?;
+        let fee_estimate =
+            observe_rpc("eth_feeHistory", provider.estimate_eip1559_fees(None)).await?;
         let max_fee_per_gas = fee_estimate.max_fee_per_gas;
 
         // Calculate worst-case gas cost with safety buffer
@@ -350,7 +502,8 @@ where
         );
 
         // Check native balance (need gas for ERC20 transfer)
-        let mut native_balance = provider.get_balance(from_address).await?;
+        let mut native_balance =
+            observe_rpc("eth_getBalance", provider.get_balance(from_address)).await?;
 
         info!(
             "Native balance: {} wei for address {}",
@@ -365,7 +518,15 @@ where
             );
 
             // Fund the address via faucet
-            match self.faucet.fund_new_address(from_address_str).await {
+            match self
+                .faucet
+                .fund_gas(
+                    from_address_str,
+                    estimated_gas_cost_with_buffer,
+                    native_balance,
+                )
+                .await
+            {
                 Ok(tx_hash) => {
                     info!(
                         "Successfully funded address {} via faucet with tx: {}. Waiting for balance update...",
@@ -376,7 +537,8 @@ where
                     sleep(Duration::from_secs(2)).await;
 
                     // Re-check the balance after funding
-                    native_balance = provider.get_balance(from_address).await?;
+                    native_balance =
+                        observe_rpc("eth_getBalance", provider.get_balance(from_address)).await?;
                     info!(
                         "Updated native balance after faucet funding: {} wei for address {}",
                         native_balance, from_address_str
@@ -417,12 +579,7 @@ where
 
         info!(
             "Sweeping {} {} tokens (raw: {}) from {} to {} (native balance: {} wei)",
-            token_balance,
-            deposit.token_symbol,
-            token_balance,
-            from_address,
-            to_address,
-            native_balance
+            token_balance, token_symbol, token_balance, from_address, to_address, native_balance
         );
 
         // Build final transaction with estimated gas limit
@@ -435,17 +592,35 @@ where
         info!("Transaction request: {:?}", tx);
         info!("++++++++++++++++");
 
-        let pending_tx = provider.send_transaction(tx).await?;
+        let pending_tx =
+            observe_rpc("eth_sendRawTransaction", provider.send_transaction(tx)).await?;
         info!("++++++++++++++++");
         info!("Pending transaction: {:?}", pending_tx.tx_hash());
         info!("++++++++++++++++");
-        let receipt = pending_tx.get_receipt().await?;
+        let receipt = observe_rpc("eth_getTransactionReceipt", pending_tx.get_receipt()).await?;
         info!("++++++++++++++++");
         info!("Receipt: {:?}", receipt.transaction_hash);
         info!("++++++++++++++++");
 
+        let sweep_tx_hash = receipt.transaction_hash.to_string();
+        if !receipt.status() {
+            self.db
+                .mark_deposit_failed(&deposit.key, &sweep_tx_hash, "sweep transaction reverted")
+                .await?;
+            return Err(anyhow::anyhow!(
+                "ERC20 sweep transaction {} reverted",
+                sweep_tx_hash
+            ));
+        }
+
         // Update DB
-        self.db.mark_erc20_deposit_swept(&deposit.key)?;
+        self.db
+            .mark_deposit_swept(&deposit.key, &sweep_tx_hash)
+            .await?;
+        metrics()
+            .deposits_swept
+            .with_label_values(&[&self.chain.chain_id.to_string(), token_symbol])
+            .inc();
 
         info!("++++++++++++++++");
         info!("Marked ERC20 deposit swept: {:?}", deposit.key);
@@ -454,22 +629,23 @@ where
         // Fetch token decimals from DB
         let token_decimals = self
             .db
-            .get_token_metadata(&deposit.token_address)?
+            .get_token_metadata(token_address_str)
+            .await?
             .map(|(_, decimals, _)| decimals);
 
         // deposit.account_id is actually the registration_id
         let registration_id = &deposit.account_id;
 
-        // Send Webhook (for ERC20 deposits, id = deposit.key which is tx_hash:log_index)
+        // Send Webhook (id = deposit.key, which is chain_id:tx_hash:log_index for ERC20 deposits)
         // account_id = Polygon address (from_address_str), registration_id = original id from registration
         let webhook_info = Erc20WebhookInfo {
             id: &deposit.key,
             account_id: from_address_str,
             registration_id,
-            deposit_key: &deposit.key,
+            tx_hash: &deposit.tx_hash,
             amount: &amount.to_string(),
-            token_symbol: &deposit.token_symbol,
-            token_address: &deposit.token_address,
+            token_symbol,
+            token_address: token_address_str,
             token_decimals,
         };
         self.send_erc20_webhook(&webhook_info).await?;
@@ -485,8 +661,16 @@ where
         tx_hash: &str,
         amount: &str,
     ) -> Result<()> {
+        if !self.db.is_account_active(registration_id).await? {
+            info!(
+                "Skipping swept webhook for deactivated account {}",
+                registration_id
+            );
+            return Ok(());
+        }
+
         // Get the webhook URL using registration_id (the key in ACCOUNTS table)
-        let Some(webhook_url) = self.db.get_webhook_url(registration_id)? else {
+        let Some(webhook_url) = self.db.get_webhook_url(registration_id).await? else {
             error!(
                 "No webhook URL found for registration_id: {}",
                 registration_id
@@ -494,12 +678,13 @@ where
             return Ok(());
         };
 
-        let client = reqwest::Client::new();
+        let client = crate::metrics::webhook_client();
         let payload = serde_json::json!({
             "id": id,
             "event": "deposit_swept",
             "account_id": account_id,
             "registration_id": registration_id,
+            "chain_id": self.chain.chain_id,
             "original_tx_hash": tx_hash,
             "amount": amount,
             "token_type": "native"
@@ -517,7 +702,7 @@ where
             request = request.header("Authorization", format!("Bearer {}", token));
         }
 
-        let res = request.send().await;
+        let res = crate::metrics::send_webhook("deposit_swept", request).await;
 
         match res {
             Ok(r) => info!(
@@ -533,8 +718,16 @@ where
     }
 
     async fn send_erc20_webhook(&self, info: &Erc20WebhookInfo<'_>) -> Result<()> {
+        if !self.db.is_account_active(info.registration_id).await? {
+            info!(
+                "Skipping ERC20 swept webhook for deactivated account {}",
+                info.registration_id
+            );
+            return Ok(());
+        }
+
         // Get the webhook URL using registration_id (the key in ACCOUNTS table)
-        let Some(webhook_url) = self.db.get_webhook_url(info.registration_id)? else {
+        let Some(webhook_url) = self.db.get_webhook_url(info.registration_id).await? else {
             error!(
                 "No webhook URL found for registration_id: {}",
                 info.registration_id
@@ -542,13 +735,14 @@ where
             return Ok(());
         };
 
-        let client = reqwest::Client::new();
+        let client = crate::metrics::webhook_client();
         let mut payload = serde_json::json!({
             "id": info.id,
             "event": "deposit_swept",
             "account_id": info.account_id,
             "registration_id": info.registration_id,
-            "original_tx_hash": info.deposit_key.split(':').nth(0).unwrap(),
+            "chain_id": self.chain.chain_id,
+            "original_tx_hash": info.tx_hash,
             "amount": info.amount,
             "token_type": "erc20",
             "token_symbol": info.token_symbol,
@@ -572,7 +766,7 @@ where
             request = request.header("Authorization", format!("Bearer {}", token));
         }
 
-        let res = request.send().await;
+        let res = crate::metrics::send_webhook("deposit_swept", request).await;
 
         match res {
             Ok(r) => info!(
@@ -588,6 +782,20 @@ where
     }
 }
 
+/// Label a failed sweep for the `sweep_failures_total` metric
+fn failure_reason(error: &anyhow::Error) -> &'static str {
+    let message = error.to_string();
+    if message.contains("reverted") {
+        "reverted"
+    } else if message.contains("faucet") {
+        "faucet_funding"
+    } else if message.contains("balance is zero") {
+        "zero_balance"
+    } else {
+        "error"
+    }
+}
+
 // ERC20 helper types and functions
 use alloy::sol;
 
@@ -605,15 +813,17 @@ sol! {
     }
 }
 
-async fn get_token_balance<T>(
-    provider: &alloy::providers::RootProvider<T>,
+async fn get_token_balance<P>(
+    provider: &P,
     token_address: Address,
     owner_address: Address,
 ) -> Result<U256>
 where
-    T: alloy::transports::Transport + Clone,
+    P: Provider,
 {
     let contract = IERC20::new(token_address, provider);
-    let balance = contract.balanceOf(owner_address).call().await?._0;
+    let balance = observe_rpc("eth_call", contract.balanceOf(owner_address).call())
+        .await?
+        ._0;
     Ok(balance)
 }
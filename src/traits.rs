@@ -1,6 +1,76 @@
 use async_trait::async_trait;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
 
+/// A long-running background task such as the Monitor or the Sweeper.
+///
+/// `run` should return only once `ctx` is cancelled; returning earlier (or panicking)
+/// makes the [`Supervisor`](crate::supervisor::Supervisor) restart it.
 #[async_trait]
 pub trait Service: Send + Sync {
-    async fn run(&self);
+    /// Name used in logs and health reports
+    fn name(&self) -> String;
+
+    async fn run(&self, ctx: ServiceContext);
+}
+
+/// Lifecycle of a supervised service
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceStatus {
+    Starting,
+    Running,
+    /// Crashed and waiting for its restart backoff to elapse
+    Restarting,
+    /// Shutdown was requested; finishing the current unit of work
+    Stopping,
+    Stopped,
+}
+
+impl ServiceStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServiceStatus::Starting => "starting",
+            ServiceStatus::Running => "running",
+            ServiceStatus::Restarting => "restarting",
+            ServiceStatus::Stopping => "stopping",
+            ServiceStatus::Stopped => "stopped",
+        }
+    }
+}
+
+/// Passed to [`Service::run`]: a shutdown signal and a place to report status
+#[derive(Clone, Debug)]
+pub struct ServiceContext {
+    shutdown: CancellationToken,
+    status: Arc<Mutex<ServiceStatus>>,
+}
+
+impl ServiceContext {
+    pub fn new(shutdown: CancellationToken) -> Self {
+        Self {
+            shutdown,
+            status: Arc::new(Mutex::new(ServiceStatus::Starting)),
+        }
+    }
+
+    /// True once shutdown was requested. Services check this between units of work
+    /// (a block, a sweep) so nothing is abandoned halfway.
+    pub fn is_cancelled(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+
+    /// Resolves when shutdown is requested, e.g. to cut a poll interval short
+    pub async fn cancelled(&self) {
+        self.shutdown.cancelled().await
+    }
+
+    pub fn set_status(&self, status: ServiceStatus) {
+        *self.status.lock().unwrap() = status;
+    }
+
+    pub fn status(&self) -> ServiceStatus {
+        *self.status.lock().unwrap()
+    }
 }
@@ -0,0 +1,168 @@
+use crate::error::{ServiceError, ServiceResult};
+use reqwest::Url;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Longest accepted account id
+pub const MAX_ACCOUNT_ID_LEN: usize = 128;
+
+/// Longest accepted `Idempotency-Key`
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// Account ids are 1 to 128 ASCII letters, digits, and `_-.:@`, so they are safe to put
+/// in URLs, logs, and webhook payloads unescaped
+pub fn check_account_id(id: &str) -> ServiceResult<()> {
+    if id.trim().is_empty() {
+        return Err(ServiceError::invalid("id must not be empty"));
+    }
+    if id.len() > MAX_ACCOUNT_ID_LEN {
+        return Err(ServiceError::invalid(format!(
+            "id must be at most {} characters",
+            MAX_ACCOUNT_ID_LEN
+        )));
+    }
+    if let Some(c) = id.chars().find(|c| !is_account_id_char(*c)) {
+        return Err(ServiceError::invalid(format!(
+            "id contains {:?}; only ASCII letters, digits, and _-.:@ are allowed",
+            c
+        )));
+    }
+    Ok(())
+}
+
+fn is_account_id_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ':' | '@')
+}
+
+/// Webhook URLs must be http(s) with a host. With an allowlist, the host must match one
+/// of its entries (`hooks.example.com`, or `*.example.com` for any subdomain); without
+/// one, hosts that name the service's own machine or network are rejected, so webhooks
+/// can't be aimed at internal services.
+///
+/// Hostnames are not resolved, so a public name pointing at an internal address passes
+/// the default check; configure an allowlist to rule that out.
+pub fn check_webhook_url(url: &str, allowed_hosts: &[String]) -> ServiceResult<()> {
+    let parsed = Url::parse(url)
+        .map_err(|e| ServiceError::invalid(format!("webhook_url is not a valid URL: {}", e)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(ServiceError::invalid(format!(
+            "webhook_url must use http or https, not {}",
+            parsed.scheme()
+        )));
+    }
+    let host = match parsed.host_str() {
+        Some(host) if !host.is_empty() => host.to_ascii_lowercase(),
+        _ => return Err(ServiceError::invalid("webhook_url must have a host")),
+    };
+    if !parsed.username().is_empty() || parsed.password().is_some() {
+        return Err(ServiceError::invalid(
+            "webhook_url must not contain credentials",
+        ));
+    }
+
+    if allowed_hosts.is_empty() {
+        if is_internal_host(&host) {
+            return Err(ServiceError::WebhookUrlNotAllowed(format!(
+                "{} is an internal address; add it to webhook_allowed_hosts to allow it",
+                host
+            )));
+        }
+    } else if !allowed_hosts
+        .iter()
+        .any(|pattern| host_matches(pattern, &host))
+    {
+        return Err(ServiceError::WebhookUrlNotAllowed(format!(
+            "{} is not in webhook_allowed_hosts",
+            host
+        )));
+    }
+    Ok(())
+}
+
+/// Idempotency keys are 1 to 255 visible ASCII characters, e.g. a UUID
+pub fn check_idempotency_key(key: &str) -> ServiceResult<()> {
+    if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+        return Err(ServiceError::invalid(format!(
+            "Idempotency-Key must be 1 to {} characters",
+            MAX_IDEMPOTENCY_KEY_LEN
+        )));
+    }
+    if !key.chars().all(|c| c.is_ascii_graphic()) {
+        return Err(ServiceError::invalid(
+            "Idempotency-Key must only contain visible ASCII characters",
+        ));
+    }
+    Ok(())
+}
+
+/// Problem with an entry of `webhook_allowed_hosts`, if any
+pub fn check_host_pattern(pattern: &str) -> Option<String> {
+    let host = pattern.strip_prefix("*.").unwrap_or(pattern);
+    if host.is_empty() {
+        return Some(format!("{:?} is not a host name", pattern));
+    }
+    if host.contains('*') {
+        return Some(format!(
+            "{:?}: only a leading \"*.\" wildcard is supported",
+            pattern
+        ));
+    }
+    if host.contains(|c: char| c.is_whitespace() || matches!(c, '/' | '?' | '#' | '@')) {
+        return Some(format!(
+            "{:?} must be a host name or address, not a URL",
+            pattern
+        ));
+    }
+    None
+}
+
+/// Whether `host` (lower-cased, as in a URL) matches an allowlist entry
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .strip_suffix(domain)
+            .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+        None => pattern == host,
+    }
+}
+
+fn is_internal_host(host: &str) -> bool {
+    if host == "localhost" || host.ends_with(".localhost") {
+        return true;
+    }
+    // IPv6 hosts are bracketed in URLs
+    let literal = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+    match literal.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => is_internal_ipv4(ip),
+        Ok(IpAddr::V6(ip)) => is_internal_ipv6(ip),
+        Err(_) => false,
+    }
+}
+
+fn is_internal_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || a == 0
+        // Carrier-grade NAT, 100.64.0.0/10
+        || (a == 100 && (64..128).contains(&b))
+}
+
+fn is_internal_ipv6(ip: Ipv6Addr) -> bool {
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return is_internal_ipv4(v4);
+    }
+    let first = ip.segments()[0];
+    ip.is_loopback()
+        || ip.is_unspecified()
+        // Unique local, fc00::/7
+        || (first & 0xfe00) == 0xfc00
+        // Link-local, fe80::/10
+        || (first & 0xffc0) == 0xfe80
+}
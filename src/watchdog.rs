@@ -0,0 +1,258 @@
+use crate::config::ChainConfig;
+use crate::db::{FundingFilter, FundingReason, FundingStatus};
+use crate::deposit::now_secs;
+use crate::faucet::Faucet;
+use crate::metrics::{self, metrics};
+use crate::storage::Storage;
+use crate::traits::{Service, ServiceContext, ServiceStatus};
+use alloy::primitives::U256;
+use alloy::providers::Provider;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, warn};
+
+/// Refills are capped over this rolling window
+const REFILL_WINDOW_SECS: u64 = 24 * 60 * 60;
+
+/// Watches a chain's faucet balance: alerts once when it drops below `faucet_min_balance`,
+/// and, when the chain has a `faucet_refill`, tops it up to the target from the refill key
+/// within the daily cap
+pub struct FaucetWatchdog<P> {
+    chain: ChainConfig,
+    faucet: Arc<Faucet<P>>,
+    db: Arc<dyn Storage>,
+    interval_secs: u64,
+    /// Sends the refills; `None` when refills are off
+    refiller: Option<Faucet<P>>,
+    alert_webhook_url: Option<String>,
+    webhook_jwt_token: Option<String>,
+    /// Whether the last check found the balance low, so each drop alerts once
+    low: AtomicBool,
+    /// Whether the daily cap was reported since the balance dropped
+    cap_reported: AtomicBool,
+}
+
+impl<P> FaucetWatchdog<P>
+where
+    P: Provider,
+{
+    pub fn new(
+        chain: ChainConfig,
+        faucet: Arc<Faucet<P>>,
+        db: Arc<dyn Storage>,
+        interval_secs: u64,
+    ) -> Self {
+        Self {
+            chain,
+            faucet,
+            db,
+            interval_secs: interval_secs.max(1),
+            refiller: None,
+            alert_webhook_url: None,
+            webhook_jwt_token: None,
+            low: AtomicBool::new(false),
+            cap_reported: AtomicBool::new(false),
+        }
+    }
+
+    /// Refill the faucet from `refiller`, a faucet on the refill key, when the chain has a
+    /// `faucet_refill`
+    pub fn with_refiller(mut self, refiller: Option<Faucet<P>>) -> Self {
+        self.refiller = refiller;
+        self
+    }
+
+    /// Post alerts to `url`, with `jwt_token` as a bearer token
+    pub fn with_alerts(mut self, url: Option<String>, jwt_token: Option<String>) -> Self {
+        self.alert_webhook_url = url;
+        self.webhook_jwt_token = jwt_token;
+        self
+    }
+
+    /// Read the faucet balance, alert if it just dropped below the minimum, and refill it
+    /// while it is low
+    pub async fn check(&self) -> Result<()> {
+        let chain_id = self.chain.chain_id;
+        let threshold = U256::from_str_radix(&self.chain.faucet_min_balance, 10)?;
+        let balance = self.faucet.balance().await?;
+        let low = balance < threshold;
+        metrics()
+            .faucet_low_balance
+            .with_label_values(&[&chain_id.to_string()])
+            .set(i64::from(low));
+
+        let was_low = self.low.swap(low, Ordering::Relaxed);
+        if !low {
+            if was_low {
+                info!(
+                    "🚰 Faucet on chain {} is back to {} wei (minimum {} wei)",
+                    chain_id, balance, threshold
+                );
+                self.cap_reported.store(false, Ordering::Relaxed);
+            }
+            return Ok(());
+        }
+        if !was_low {
+            warn!(
+                "⚠️ Faucet on chain {} holds {} wei, below its minimum of {} wei",
+                chain_id, balance, threshold
+            );
+            self.alert(
+                "faucet_low_balance",
+                serde_json::json!({
+                    "balance": balance.to_string(),
+                    "threshold": threshold.to_string(),
+                }),
+            )
+            .await;
+        }
+
+        self.refill(balance).await
+    }
+
+    async fn refill(&self, balance: U256) -> Result<()> {
+        let (Some(refiller), Some(refill)) = (&self.refiller, &self.chain.faucet_refill) else {
+            return Ok(());
+        };
+        let chain_id = self.chain.chain_id;
+        let target = U256::from_str_radix(&refill.target, 10)?;
+        let daily_cap = U256::from_str_radix(&refill.daily_cap, 10)?;
+        let refilled = self.refilled_in_window().await?;
+        let amount = target
+            .saturating_sub(balance)
+            .min(daily_cap.saturating_sub(refilled));
+        if amount.is_zero() {
+            if !self.cap_reported.swap(true, Ordering::Relaxed) {
+                warn!(
+                    "⚠️ Not refilling the faucet on chain {}: {} wei refilled in the last 24 hours reaches the daily cap of {} wei",
+                    chain_id, refilled, daily_cap
+                );
+            }
+            return Ok(());
+        }
+
+        let faucet_address = self.faucet.address()?.to_string();
+        info!(
+            "🚰 Refilling the faucet on chain {} with {} wei (target {} wei)",
+            chain_id, amount, target
+        );
+        let mut payload = serde_json::json!({ "amount": amount.to_string() });
+        match refiller
+            .transfer(&faucet_address, amount, FundingReason::FaucetRefill)
+            .await
+        {
+            Ok(tx_hash) => {
+                info!(
+                    "Refilled the faucet on chain {} with tx: {}",
+                    chain_id, tx_hash
+                );
+                payload["success"] = true.into();
+                payload["tx_hash"] = tx_hash.into();
+            }
+            Err(e) => {
+                error!("Failed to refill the faucet on chain {}: {:?}", chain_id, e);
+                payload["success"] = false.into();
+                payload["error"] = e.to_string().into();
+            }
+        }
+        self.alert("faucet_refill", payload).await;
+        Ok(())
+    }
+
+    /// Wei of the refills started in the last 24 hours that may have been sent
+    async fn refilled_in_window(&self) -> Result<U256> {
+        let since = now_secs().saturating_sub(REFILL_WINDOW_SECS);
+        let fundings = self
+            .db
+            .list_fundings(&FundingFilter {
+                chain_id: Some(self.chain.chain_id),
+                address: Some(self.faucet.address()?.to_string()),
+            })
+            .await?;
+        fundings
+            .iter()
+            .filter(|f| {
+                f.reason == FundingReason::FaucetRefill
+                    && f.status != FundingStatus::Failed
+                    && f.created_at >= since
+            })
+            .try_fold(U256::ZERO, |total, f| {
+                Ok(total.saturating_add(U256::from_str_radix(&f.amount, 10)?))
+            })
+    }
+
+    /// Post `event` with `fields` to the alert webhook, if one is configured
+    async fn alert(&self, event: &str, fields: serde_json::Value) {
+        let Some(url) = &self.alert_webhook_url else {
+            return;
+        };
+        let faucet_address = match self.faucet.address() {
+            Ok(address) => address.to_string(),
+            Err(e) => {
+                error!("Failed to derive the faucet address: {:?}", e);
+                return;
+            }
+        };
+        let now = now_secs();
+        let mut payload = serde_json::json!({
+            "event": event,
+            "chain_id": self.chain.chain_id,
+            "faucet_address": faucet_address,
+            "id": format!("{}:{}:{}", self.chain.chain_id, event, now),
+            "timestamp": now,
+        });
+        if let (Some(payload), serde_json::Value::Object(fields)) =
+            (payload.as_object_mut(), fields)
+        {
+            payload.extend(fields);
+        }
+
+        let mut request = metrics::webhook_client().post(url).json(&payload);
+        if let Some(token) = &self.webhook_jwt_token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        match metrics::send_webhook(event, request).await {
+            Ok(r) => info!("{} alert sent to {}: status={}", event, url, r.status()),
+            Err(e) => error!("Failed to send {} alert to {}: {:?}", event, url, e),
+        }
+    }
+}
+
+#[async_trait]
+impl<P> Service for FaucetWatchdog<P>
+where
+    P: Provider,
+{
+    fn name(&self) -> String {
+        format!("faucet_watchdog[{}]", self.chain.name)
+    }
+
+    async fn run(&self, ctx: ServiceContext) {
+        info!(
+            "Starting faucet watchdog for {} (every {}s)",
+            self.chain.name, self.interval_secs
+        );
+        ctx.set_status(ServiceStatus::Running);
+
+        let mut ticker = interval(Duration::from_secs(self.interval_secs));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        while !ctx.is_cancelled() {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = ctx.cancelled() => break,
+            }
+            if let Err(e) = self.check().await {
+                error!(
+                    "Faucet watchdog check failed for {}: {:?}",
+                    self.chain.name, e
+                );
+            }
+        }
+
+        info!("Faucet watchdog for {} stopped", self.chain.name);
+    }
+}